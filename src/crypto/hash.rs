@@ -5,6 +5,11 @@ use sha3::Digest;
 // CONSTANTS
 // ================================================================================================
 
+// Inputs longer than 64 bytes are absorbed into the state (Poseidon, Rescue, GMiMC) 4 elements at
+// a time; each element holds 15 bytes so that it is always a valid field element
+const LONG_INPUT_RATE: usize = 4;
+const LONG_INPUT_ELEMENT_BYTES: usize = 15;
+
 // Exponents for S-BOX (Poseidon, Rescue, GMiMC) and inverse S-BOX (rescue)
 const ALPHA: u128 = 3;
 const INV_ALPHA: u128 = 226854911280625642308916371969163307691;
@@ -117,76 +122,64 @@ const ARK: [u128; 546] = [
 // ------------------------------------------------------------------------------------------------
 /// Poseidon hash function
 pub fn poseidon(values: &[u8], result: &mut [u8]) {
-    debug_assert!(values.len() <= 64, "expected 64 or fewer input bytes but received {}", values.len());
     debug_assert!(result.len() == 32, "expected result to be exactly 32 bytes but received {}", result.len());
+    let state = absorb(values, poseidon_permutation);
+    result.copy_from_slice(as_bytes(&state[..2]));
+}
 
-    // copy values into state and set the remaining state elements to 0
-    let mut state = [0u128; 6];
-    let state_bytes: &mut [u8; 64] = unsafe { &mut *(&state as *const _ as *mut [u8; 64]) };
-    state_bytes[..values.len()].copy_from_slice(values);
-
+fn poseidon_permutation(state: &mut [u128; 6]) {
     // execute round function 48 times
     for i in 0..91 {
 
-        add_constants(&mut state, i * 6);
+        add_constants(state, i * 6);
 
         if i < 4 || i >= 87 {
             // full round
-            apply_sbox(&mut state);
+            apply_sbox(state);
         }
         else {
             // partial round
             state[5] = field::exp(state[5], ALPHA);
         }
 
-        apply_mds(&mut state);
+        apply_mds(state);
     }
-
-    // return the result
-    result.copy_from_slice(as_bytes(&state[..2]));
 }
 
 // ------------------------------------------------------------------------------------------------
 /// Rescue hash function
 pub fn rescue(values: &[u8], result: &mut [u8]) {
-    debug_assert!(values.len() <= 64, "expected 64 or fewer input bytes but received {}", values.len());
     debug_assert!(result.len() == 32, "expected result to be exactly 32 bytes but received {}", result.len());
+    let state = absorb(values, rescue_permutation);
+    result.copy_from_slice(as_bytes(&state[..2]));
+}
 
-    // copy values into state and set the remaining state elements to 0
-    let mut state = [0u128; 6];
-    let state_bytes: &mut [u8; 64] = unsafe { &mut *(&state as *const _ as *mut [u8; 64]) };
-    state_bytes[..values.len()].copy_from_slice(values);
-
+fn rescue_permutation(state: &mut [u128; 6]) {
     // apply round function 10 times
-    add_constants(&mut state, 0);
+    add_constants(state, 0);
     for i in 0..10 {
 
         // step 1
-        apply_inv_sbox(&mut state);
-        apply_mds(&mut state);
-        add_constants(&mut state, (i * 2 + 1) * 6);
+        apply_inv_sbox(state);
+        apply_mds(state);
+        add_constants(state, (i * 2 + 1) * 6);
 
         // step 2
-        apply_sbox(&mut state);
-        apply_mds(&mut state);
-        add_constants(&mut state, (i * 2 + 2) * 6);
+        apply_sbox(state);
+        apply_mds(state);
+        add_constants(state, (i * 2 + 2) * 6);
     }
-
-    // return the result
-    result.copy_from_slice(as_bytes(&state[..2]));
 }
 
 // ------------------------------------------------------------------------------------------------
 /// GMiMC_erf hash function
 pub fn gmimc(values: &[u8], result: &mut [u8]) {
-    debug_assert!(values.len() <= 64, "expected 64 or fewer input bytes but received {}", values.len());
     debug_assert!(result.len() == 32, "expected result to be exactly 32 bytes but received {}", result.len());
+    let state = absorb(values, gmimc_permutation);
+    result.copy_from_slice(as_bytes(&state[..2]));
+}
 
-    // copy values into state and set the remaining state elements to 0
-    let mut state = [0u128; 6];
-    let state_bytes: &mut [u8; 64] = unsafe { &mut *(&state as *const _ as *mut [u8; 64]) };
-    state_bytes[..values.len()].copy_from_slice(values);
-
+fn gmimc_permutation(state: &mut [u128; 6]) {
     for i in 0..166 {
         let s0 = state[0];
         let mask = field::exp(field::add(s0, ARK[i]), ALPHA);
@@ -195,9 +188,6 @@ pub fn gmimc(values: &[u8], result: &mut [u8]) {
         }
         state[5] = s0;
     }
-
-    // return the result
-    result.copy_from_slice(as_bytes(&state[..2]));
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -219,6 +209,35 @@ pub fn sha3(values: &[u8], result: &mut [u8]) {
 
 // HELPER FUNCTIONS
 // ================================================================================================
+
+/// Absorbs `values` into a new state using the provided permutation. Inputs of up to 64 bytes
+/// are copied into the state directly and the permutation is applied once. Longer inputs are
+/// absorbed in chunks of LONG_INPUT_RATE elements, and the input length is put into the last
+/// state element to separate them from short inputs.
+fn absorb(values: &[u8], permutation: fn(&mut [u128; 6])) -> [u128; 6] {
+    let mut state = [0u128; 6];
+
+    if values.len() <= 64 {
+        // copy values into state and set the remaining state elements to 0
+        let state_bytes: &mut [u8; 64] = unsafe { &mut *(&state as *const _ as *mut [u8; 64]) };
+        state_bytes[..values.len()].copy_from_slice(values);
+        permutation(&mut state);
+    }
+    else {
+        state[5] = values.len() as u128;
+        for chunk in values.chunks(LONG_INPUT_RATE * LONG_INPUT_ELEMENT_BYTES) {
+            for (i, element_bytes) in chunk.chunks(LONG_INPUT_ELEMENT_BYTES).enumerate() {
+                let mut element = [0u8; 16];
+                element[..element_bytes.len()].copy_from_slice(element_bytes);
+                state[i] = field::add(state[i], u128::from_le_bytes(element));
+            }
+            permutation(&mut state);
+        }
+    }
+
+    return state;
+}
+
 fn add_constants(state: &mut[u128; 6], offset: usize) {
     for i in 0..6 {
         state[i] = field::add(state[i], ARK[offset + i]);
@@ -295,4 +314,28 @@ mod tests {
              71,  74, 255, 77,  41, 125,  25, 152, 162, 106,  65, 108,  84, 216, 37, 37],
             result);
     }
+
+    #[test]
+    fn long_inputs() {
+        let value = [1u128, 2, 3, 4, 5, 6, 7, 8, 9];
+        let hash_fns: [fn(&[u8], &mut [u8]); 3] = [super::poseidon, super::rescue, super::gmimc];
+        for hash_fn in hash_fns.iter() {
+            let mut result1 = [0; 32];
+            hash_fn(as_bytes(&value), &mut result1);
+
+            // hashing the same value again should produce the same result
+            let mut result2 = [0; 32];
+            hash_fn(as_bytes(&value), &mut result2);
+            assert_eq!(result1, result2);
+
+            // changing the last value or the length of the input should change the result
+            let mut result2 = [0; 32];
+            hash_fn(as_bytes(&[1u128, 2, 3, 4, 5, 6, 7, 8, 10]), &mut result2);
+            assert_ne!(result1, result2);
+
+            let mut result2 = [0; 32];
+            hash_fn(as_bytes(&[1u128, 2, 3, 4, 5, 6, 7, 8, 9, 0]), &mut result2);
+            assert_ne!(result1, result2);
+        }
+    }
 }
@@ -8,6 +8,7 @@ use alloc::{collections::BTreeSet as HashSet, vec::Vec};
 
 // use sp_std::collections::{ BTreeSet };
 use serde::{ Serialize, Deserialize };
+use codec::{ Encode, Decode };
 use crate::crypto::{ HashFunction };
 use sp_std::collections::btree_set::BTreeSet;
 
@@ -18,7 +19,7 @@ pub struct MerkleTree {
     values  : Vec<[u8; 32]>
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
 pub struct BatchMerkleProof {
     pub values  : Vec<[u8; 32]>,
     pub nodes   : Vec<Vec<[u8; 32]>>,
@@ -3,4 +3,38 @@ pub mod hash;
 mod merkle;
 pub use merkle::{ MerkleTree, BatchMerkleProof, build_merkle_nodes };
 
-pub type HashFunction = fn(&[u8], &mut [u8]);
\ No newline at end of file
+pub type HashFunction = fn(&[u8], &mut [u8]);
+
+// HASH FUNCTION IDENTIFIERS
+// ================================================================================================
+
+/// Hash functions which can be used in serialized proofs together with their stable identifiers;
+/// identifiers must never be changed or re-used.
+const HASH_FUNCTIONS: [(u8, HashFunction); 5] = [
+    (0, hash::blake3),
+    (1, hash::sha3),
+    (2, hash::rescue),
+    (3, hash::poseidon),
+    (4, hash::gmimc),
+];
+
+/// Returns a stable identifier of the hash function, or None if the function is not one of
+/// the hash functions defined in the `hash` module.
+pub fn get_hash_fn_id(hash_fn: HashFunction) -> Option<u8> {
+    for &(id, hf) in HASH_FUNCTIONS.iter() {
+        if hf as usize == hash_fn as usize {
+            return Some(id);
+        }
+    }
+    return None;
+}
+
+/// Returns the hash function for the specified identifier, or None if the identifier is unknown.
+pub fn get_hash_fn(id: u8) -> Option<HashFunction> {
+    for &(hf_id, hf) in HASH_FUNCTIONS.iter() {
+        if hf_id == id {
+            return Some(hf);
+        }
+    }
+    return None;
+}
@@ -5,7 +5,9 @@ use serde::{Serialize, Deserialize};
 
 #[macro_use]
 extern crate alloc;
-use alloc::string::String;
+
+#[cfg(feature = "std")]
+extern crate std;
 
 // RE-EXPORTS
 // ================================================================================================
@@ -14,12 +16,15 @@ pub mod math;
 pub mod utils;
 
 mod stark;
-pub use stark::{ StarkProof, ProofOptions, GenOutput, ProgramAssembly };
+pub use stark::{
+    StarkProof, ProofStats, FriLayerStats, ProofOptions, GenOutput, ProgramAssembly, VerificationError, ProofValidationError,
+    PolicyViolation, BatchVerifier, BatchItem, VerifierPolicy, SecurityEstimate, CostEstimate, get_cheapest_options };
 mod processor;
-pub use processor::{ OpCode, OpHint };
+pub use processor::{ OpCode, OpHint, FlowOps, ExecutionError, ExecutionErrorKind, Debugger, Breakpoint, VmState, RunResult, ExecutionReport, ExecutionOptions };
 
 mod programs;
-pub use programs::{ Program, ProgramInputs, assembly, blocks };
+pub use programs::{
+    Program, ProgramInputs, ProgramDecodingError, StackAnalysis, StackWarning, StackError, DepthRange, assembly, blocks };
 
 
 extern crate console_error_panic_hook;
@@ -33,11 +38,118 @@ extern crate web_sys;
 use codec::{Decode, Encode};
 use crate::alloc::string::ToString;
 
-pub fn verify(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> Result<bool, String>
+/// Executes the specified `program` and returns the result together with a STARK-based proof of execution.
+///
+/// * `inputs` specifies the initial stack state and provides secret input tapes;
+/// * `num_outputs` specifies the number of elements from the top of the stack to be returned;
+///
+/// Returns an error if the program fails during execution (e.g. if an assertion fails, or if
+/// the program reads from an empty secret input tape). Execution is not limited; use
+/// `execute_with_limits()` to execute untrusted programs.
+pub fn execute(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions) -> Result<(Vec<u128>, StarkProof), ExecutionError>
+{
+    return execute_with_limits(program, inputs, num_outputs, options, &ExecutionOptions::unlimited());
+}
+
+/// Same as `execute()`, but execution stops with an error as soon as it exceeds any of the
+/// `limits` (e.g. if the program runs for more cycles than allowed); use this to execute
+/// untrusted programs, which could otherwise loop forever.
+pub fn execute_with_limits(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions, limits: &ExecutionOptions) -> Result<(Vec<u128>, StarkProof), ExecutionError>
+{
+    check_num_outputs(num_outputs)?;
+
+    // execute the program to create an execution trace
+    let (register_traces, ctx_depth, loop_depth) = processor::execute_with_limits(program, inputs, limits)?;
+    let mut trace = stark::TraceTable::new(register_traces, ctx_depth, loop_depth, options.extension_factor());
+    debug!("Generated execution trace of {} registers and {} steps",
+        trace.register_count(),
+        trace.unextended_length());
+
+    // copy the user stack state the the last step to return as output
+    let last_state = trace.get_state(trace.unextended_length() - 1);
+    let outputs = last_state.user_stack()[..num_outputs].to_vec();
+
+    // make sure number of executed operations was sufficient
+    let last_step = trace.unextended_length() - 1;
+    let stack_top: Vec<u128> = last_state.user_stack().iter().take(MIN_STACK_DEPTH).copied().collect();
+    if (last_state.op_counter() as usize) < MIN_TRACE_LENGTH {
+        let kind = ExecutionErrorKind::TooFewOperations;
+        return Err(ExecutionError::new(kind, last_step, OpCode::Noop, OpHint::None, stack_top));
+    }
+
+    // make sure program hash generated by the VM matches the hash of the program
+    let program_hash = utils::as_bytes(last_state.program_hash());
+    if program.hash() != program_hash {
+        let kind = ExecutionErrorKind::ProgramHashMismatch;
+        return Err(ExecutionError::new(kind, last_step, OpCode::Noop, OpHint::None, stack_top));
+    }
+
+    // generate STARK proof
+    let proof = stark::prove(&mut trace, program.hash(), inputs.get_public_inputs(), &outputs, options);
+    return Ok((outputs, proof));
+}
+
+/// Executes the specified `program` without building an execution trace or generating a proof;
+/// this is much faster than `execute()`, and can be used to compute program outputs or to check
+/// that a program runs against the provided `inputs`.
+///
+/// * `num_outputs` specifies the number of elements from the top of the stack to be returned;
+///
+/// Fails with the same errors as `execute()`.
+pub fn run(program: &Program, inputs: &ProgramInputs, num_outputs: usize) -> Result<RunResult, ExecutionError>
+{
+    check_num_outputs(num_outputs)?;
+    return processor::run(program, inputs, num_outputs);
+}
+
+/// Same as `run()`, but execution stops with an error as soon as it exceeds any of the `limits`;
+/// fails with the same errors as `execute_with_limits()`.
+pub fn run_with_limits(program: &Program, inputs: &ProgramInputs, num_outputs: usize, limits: &ExecutionOptions) -> Result<RunResult, ExecutionError>
+{
+    check_num_outputs(num_outputs)?;
+    return processor::run_with_limits(program, inputs, num_outputs, limits);
+}
+
+/// Executes the `program` without generating a proof, and returns the location of the span
+/// operation executed at every step of the execution trace (None for steps at which control flow
+/// operations or alignment NOOPs are executed). Together with a source map returned by
+/// `assembly::compile_with_source_map()`, this makes it possible to find the assembly instruction
+/// executed at a given step.
+pub fn trace_locations(program: &Program, inputs: &ProgramInputs) -> Result<Vec<Option<blocks::OpLocation>>, ExecutionError> {
+    let (_, _, _, locations) = processor::execute_with_locations(program, inputs)?;
+    return Ok(locations);
+}
+
+/// Verifies that if a program with the specified `program_hash` is executed against the provided
+/// `public_inputs` and some secret inputs, the result is equal to the `outputs`.
+pub fn verify(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> Result<bool, VerificationError>
 {
     return stark::verify(program_hash, public_inputs, outputs, proof);
 }
 
+/// Same as `verify()`, but the proof is rejected early if it does not satisfy the `policy`
+/// (e.g. if its security level is too low, or if it uses a hash function which is not allowed).
+pub fn verify_with_policy(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof, policy: &VerifierPolicy) -> Result<bool, VerificationError>
+{
+    return stark::verify_with_policy(program_hash, public_inputs, outputs, proof, policy);
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns an error if more than `MAX_OUTPUTS` outputs are requested; the error is reported
+/// for step 0, before the program is executed.
+fn check_num_outputs(num_outputs: usize) -> Result<(), ExecutionError> {
+    if num_outputs > MAX_OUTPUTS {
+        let kind = ExecutionErrorKind::TooManyOutputs;
+        return Err(ExecutionError::new(kind, 0, OpCode::Noop, OpHint::None, Vec::new()));
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests;
+
 
 
 // pub fn main_test() {
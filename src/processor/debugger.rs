@@ -0,0 +1,422 @@
+use crate::{
+    programs::{ Program, ProgramInputs, blocks::OpLocation },
+    stark::TraceState,
+    SPONGE_WIDTH,
+};
+use sp_std::vec::Vec;
+use super::{ OpCode, FlowOps, ExecutionError, ExecutionOptions, ExecutionLog };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Steps through the execution trace of a program; the program is executed in full when the
+/// debugger is created, and the debugger then moves back and forth over the recorded trace.
+/// If the execution fails, the recorded trace ends right before the failing step.
+pub struct Debugger {
+    trace       : Vec<Vec<u128>>,
+    log         : ExecutionLog,
+    decoder     : TraceState,
+    breakpoints : Vec<Breakpoint>,
+    last_step   : usize,
+    state       : VmState,
+    error       : Option<ExecutionError>,
+}
+
+/// Describes a condition at which the debugger stops when running through the trace.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Breakpoint {
+    /// Stop when the specified step of the execution trace is reached.
+    Step(usize),
+    /// Stop right before a span operation with the specified op code is executed.
+    Op(OpCode),
+}
+
+/// State of the VM at a single step of the execution trace, together with the operations
+/// which are executed to get to the next step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VmState {
+    step        : usize,
+    op_counter  : u128,
+    flow_op     : FlowOps,
+    op_code     : OpCode,
+    location    : Option<OpLocation>,
+    sponge      : [u128; SPONGE_WIDTH],
+    ctx_stack   : Vec<u128>,
+    loop_stack  : Vec<u128>,
+    user_stack  : Vec<u128>,
+    tape_a_len  : usize,
+    tape_b_len  : usize,
+}
+
+// DEBUGGER IMPLEMENTATION
+// ================================================================================================
+impl Debugger {
+
+    /// Executes the `program` against the specified inputs within the specified execution
+    /// `options` and returns a debugger positioned at the first step of the execution trace.
+    /// If the program cannot be executed, the debugger stops at the step right before the
+    /// failing step, and the failure is returned by `error()`.
+    pub fn new(program: &Program, inputs: &ProgramInputs, options: &ExecutionOptions) -> Debugger {
+        let (trace, ctx_depth, loop_depth, log, error) = super::execute_with_log(program, inputs, options, true);
+
+        let stack_depth = trace.len() - TraceState::compute_decoder_width(ctx_depth, loop_depth);
+        let mut decoder = TraceState::new(ctx_depth, loop_depth, stack_depth);
+
+        let last_step = match &error {
+            // the VM stops at the step before the failing step; the failing operation is the
+            // operation executed at this step
+            Some(err) => err.step() - 1,
+            // otherwise, the VM stops executing operations at the first step marked with a VOID
+            // operation
+            None => (0..trace[0].len())
+                .find(|&step| { decoder.update_from_trace(&trace, step); decode_flow_op(&decoder) == FlowOps::Void })
+                .expect("execution trace does not contain a VOID operation"),
+        };
+
+        let state = read_state(&mut decoder, &trace, &log, 0);
+        return Debugger { trace, log, decoder, breakpoints: Vec::new(), last_step, state, error };
+    }
+
+    /// Returns the error which stopped the execution of the program, if any.
+    pub fn error(&self) -> Option<&ExecutionError> {
+        return self.error.as_ref();
+    }
+
+    // BREAKPOINTS
+    // --------------------------------------------------------------------------------------------
+
+    /// Adds a breakpoint; the debugger stops at breakpoints when running or stepping over blocks.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    /// Removes a breakpoint; returns false if the breakpoint was not set.
+    pub fn remove_breakpoint(&mut self, breakpoint: Breakpoint) -> bool {
+        let count = self.breakpoints.len();
+        self.breakpoints.retain(|&b| b != breakpoint);
+        return self.breakpoints.len() != count;
+    }
+
+    /// Removes all breakpoints.
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Returns all breakpoints in the order in which they were added.
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        return &self.breakpoints;
+    }
+
+    // STEPPING
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns the state of the VM at the current step.
+    pub fn state(&self) -> &VmState {
+        return &self.state;
+    }
+
+    /// Returns the last step of the execution trace at which the VM executed an operation;
+    /// the state at this step is the final state of the VM. If the execution failed, this is
+    /// the step right before the failing step.
+    pub fn last_step(&self) -> usize {
+        return self.last_step;
+    }
+
+    /// Returns true if the debugger is positioned at the final state of the VM.
+    pub fn is_finished(&self) -> bool {
+        return self.state.step == self.last_step;
+    }
+
+    /// Moves the debugger to the specified step; steps beyond the last step are clamped to it.
+    pub fn seek(&mut self, step: usize) -> &VmState {
+        let step = sp_std::cmp::min(step, self.last_step);
+        self.state = read_state(&mut self.decoder, &self.trace, &self.log, step);
+        return &self.state;
+    }
+
+    /// Executes the next operation; if the operation starts a Group, Switch, or Loop block,
+    /// the debugger stops at the first step inside the block. Returns None if the debugger is
+    /// already at the final state.
+    pub fn step_into(&mut self) -> Option<&VmState> {
+        if self.is_finished() { return None; }
+        let step = self.state.step + 1;
+        return Some(self.seek(step));
+    }
+
+    /// Executes the next operation; if the operation starts a Group, Switch, or Loop block, the
+    /// whole block is executed and the debugger stops right after the block is closed, unless
+    /// a breakpoint is reached inside the block. Returns None if the debugger is already at the
+    /// final state.
+    pub fn step_over(&mut self) -> Option<&VmState> {
+        if self.is_finished() { return None; }
+
+        let mut depth = 0usize;
+        loop {
+            match self.state.flow_op {
+                FlowOps::Begin | FlowOps::Loop => depth += 1,
+                FlowOps::Tend | FlowOps::Fend  => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+            let step = self.state.step + 1;
+            self.seek(step);
+
+            if depth == 0 || self.is_finished() || self.at_breakpoint() { break; }
+        }
+        return Some(&self.state);
+    }
+
+    /// Executes operations until a breakpoint or the final state is reached. Returns None if
+    /// the debugger is already at the final state.
+    pub fn resume(&mut self) -> Option<&VmState> {
+        if self.is_finished() { return None; }
+
+        loop {
+            let step = self.state.step + 1;
+            self.seek(step);
+            if self.is_finished() || self.at_breakpoint() { break; }
+        }
+        return Some(&self.state);
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    fn at_breakpoint(&self) -> bool {
+        return self.breakpoints.iter().any(|&breakpoint| match breakpoint {
+            Breakpoint::Step(step) => self.state.step == step,
+            Breakpoint::Op(op_code) => self.state.flow_op == FlowOps::Hacc && self.state.op_code == op_code,
+        });
+    }
+}
+
+impl Iterator for Debugger {
+    type Item = VmState;
+
+    /// Executes the next operation and returns the resulting state of the VM (same as
+    /// `step_into()`).
+    fn next(&mut self) -> Option<VmState> {
+        return self.step_into().cloned();
+    }
+}
+
+// VM STATE IMPLEMENTATION
+// ================================================================================================
+impl VmState {
+
+    /// Returns the step of the execution trace described by this state.
+    pub fn step(&self) -> usize {
+        return self.step;
+    }
+
+    /// Returns the number of span operations executed before this step.
+    pub fn op_counter(&self) -> u128 {
+        return self.op_counter;
+    }
+
+    /// Returns the control flow operation executed at this step; this is HACC when a span
+    /// operation is executed, and VOID after the program has finished.
+    pub fn flow_op(&self) -> FlowOps {
+        return self.flow_op;
+    }
+
+    /// Returns the user operation executed at this step; this is NOOP at steps at which control
+    /// flow operations other than HACC are executed.
+    pub fn op_code(&self) -> OpCode {
+        return self.op_code;
+    }
+
+    /// Returns the location of the span operation executed at this step, if any.
+    pub fn location(&self) -> Option<OpLocation> {
+        return self.location;
+    }
+
+    pub fn sponge(&self) -> &[u128] {
+        return &self.sponge;
+    }
+
+    pub fn ctx_stack(&self) -> &[u128] {
+        return &self.ctx_stack;
+    }
+
+    pub fn loop_stack(&self) -> &[u128] {
+        return &self.loop_stack;
+    }
+
+    /// Returns the user stack; the top of the stack is the first item.
+    pub fn user_stack(&self) -> &[u128] {
+        return &self.user_stack;
+    }
+
+    /// Returns the number of values remaining on secret tape A.
+    pub fn tape_a_len(&self) -> usize {
+        return self.tape_a_len;
+    }
+
+    /// Returns the number of values remaining on secret tape B.
+    pub fn tape_b_len(&self) -> usize {
+        return self.tape_b_len;
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+fn read_state(decoder: &mut TraceState, trace: &Vec<Vec<u128>>, log: &ExecutionLog, step: usize) -> VmState {
+    decoder.update_from_trace(trace, step);
+
+    let mut sponge = [0; SPONGE_WIDTH];
+    sponge.copy_from_slice(decoder.sponge());
+    let (tape_a_len, tape_b_len) = log.tape_lengths[step];
+
+    return VmState {
+        step,
+        op_counter  : decoder.op_counter(),
+        flow_op     : decode_flow_op(decoder),
+        op_code     : OpCode::from_u8(decoder.op_code() as u8).expect("invalid op code in execution trace"),
+        location    : log.locations[step],
+        sponge,
+        ctx_stack   : decoder.ctx_stack().to_vec(),
+        loop_stack  : decoder.loop_stack().to_vec(),
+        user_stack  : decoder.user_stack().to_vec(),
+        tape_a_len,
+        tape_b_len,
+    };
+}
+
+fn decode_flow_op(decoder: &TraceState) -> FlowOps {
+    let bits = decoder.cf_op_bits();
+    return FlowOps::from_u8((bits[0] | bits[1] << 1 | bits[2] << 2) as u8);
+}
+
+// TESTS
+// ================================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{ programs::{ assembly, blocks::OpLocation, ProgramInputs }, ExecutionErrorKind };
+    use sp_std::vec::Vec;
+    use super::{ Debugger, Breakpoint, OpCode, FlowOps, ExecutionOptions };
+
+    #[test]
+    fn step_into_and_over_switch() {
+        // the value read from tape A is the second operand of ADD after the conditional block
+        let program = assembly::compile("begin read push.1 if.true push.2 else push.3 end add end").unwrap();
+        let inputs = ProgramInputs::new(&[], &[5], &[]);
+        let mut debugger = Debugger::new(&program, &inputs, &ExecutionOptions::default());
+
+        assert_eq!(0, debugger.state().step());
+        assert_eq!(OpCode::Begin, debugger.state().op_code());
+        assert_eq!(1, debugger.state().tape_a_len());
+
+        // stop right before READ is executed
+        debugger.add_breakpoint(Breakpoint::Op(OpCode::Read));
+        let state = debugger.resume().unwrap();
+        assert_eq!(OpCode::Read, state.op_code());
+        assert_eq!(1, state.tape_a_len());
+
+        let state = debugger.step_into().unwrap();
+        assert_eq!(0, state.tape_a_len());
+        assert_eq!(5, state.user_stack()[0]);
+
+        // move to the start of the conditional block and step into it
+        while debugger.state().flow_op() != FlowOps::Begin {
+            debugger.step_into();
+        }
+        let block_step = debugger.state().step();
+        let ctx_stack = debugger.state().ctx_stack().to_vec();
+        assert_eq!(block_step + 1, debugger.step_into().unwrap().step());
+        assert_eq!(FlowOps::Hacc, debugger.state().flow_op());
+        assert_eq!(Some(OpLocation { span: 1, op: 0 }), debugger.state().location());
+
+        // step over the conditional block from its start
+        let state = debugger.seek(block_step).clone();
+        let state_after = debugger.step_over().unwrap();
+        assert_eq!(FlowOps::Hacc, state_after.flow_op());
+        assert_eq!(ctx_stack, state_after.ctx_stack());
+        assert_eq!(&[2, 5], &state_after.user_stack()[..2]);
+        assert!(state_after.op_counter() > state.op_counter());
+
+        // run to the end of the program
+        let state = debugger.resume().unwrap();
+        assert_eq!(7, state.user_stack()[0]);
+        assert!(debugger.is_finished());
+        assert_eq!(None, debugger.resume());
+    }
+
+    #[test]
+    fn step_over_loop() {
+        let program = assembly::compile("begin mul read while.true dup mul read end end").unwrap();
+        let inputs = ProgramInputs::new(&[5, 3], &[1, 1, 0], &[]);
+        let mut debugger = Debugger::new(&program, &inputs, &ExecutionOptions::default());
+
+        while debugger.state().flow_op() != FlowOps::Loop {
+            debugger.step_into();
+        }
+        assert_eq!(&[1, 15], &debugger.state().user_stack()[..2]);
+        assert_eq!(2, debugger.state().tape_a_len());
+
+        // the whole loop is executed, and the loop stack is empty again
+        let state = debugger.step_over().unwrap();
+        assert_eq!(50625, state.user_stack()[0]);
+        assert_eq!(0, state.tape_a_len());
+        assert_eq!(&[0], state.loop_stack());
+
+        // a breakpoint inside the loop body stops stepping over the loop
+        let mut debugger = Debugger::new(&program, &inputs, &ExecutionOptions::default());
+        while debugger.state().flow_op() != FlowOps::Loop {
+            debugger.step_into();
+        }
+        debugger.add_breakpoint(Breakpoint::Op(OpCode::Mul));
+        let state = debugger.step_over().unwrap();
+        assert_eq!(OpCode::Mul, state.op_code());
+        assert_eq!(&[15, 15], &state.user_stack()[..2]);
+    }
+
+    #[test]
+    fn iterate_states() {
+        let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+        let mut debugger = Debugger::new(&program, &ProgramInputs::none(), &ExecutionOptions::default());
+        debugger.add_breakpoint(Breakpoint::Step(3));
+
+        let last_step = debugger.last_step();
+        let states: Vec<_> = debugger.by_ref().collect();
+        assert_eq!(last_step, states.len());
+        assert_eq!(8, states[last_step - 1].user_stack()[0]);
+        assert!(debugger.is_finished());
+
+        // breakpoints at steps are reached when resuming from an earlier step
+        debugger.seek(0);
+        assert_eq!(3, debugger.resume().unwrap().step());
+        assert!(debugger.remove_breakpoint(Breakpoint::Step(3)));
+        assert_eq!(last_step, debugger.resume().unwrap().step());
+    }
+
+    #[test]
+    fn failed_execution() {
+        // the debugger stops right before the failing ASSERT operation
+        let program = assembly::compile("begin push.3 push.2 assert end").unwrap();
+        let mut debugger = Debugger::new(&program, &ProgramInputs::none(), &ExecutionOptions::default());
+        let err = debugger.error().cloned().unwrap();
+        assert_eq!(ExecutionErrorKind::AssertFailed, err.kind());
+        assert_eq!(err.step() - 1, debugger.last_step());
+
+        let state = debugger.resume().unwrap();
+        assert_eq!(OpCode::Assert, state.op_code());
+        assert_eq!(&[2, 3], &state.user_stack()[..2]);
+        assert!(state.location().is_some());
+        assert!(debugger.is_finished());
+
+        // execution limits are applied to the execution
+        let program = assembly::compile("begin push.1 while.true push.1 end end").unwrap();
+        let debugger = Debugger::new(&program, &ProgramInputs::none(), &ExecutionOptions::new(100, 1024, 0));
+        assert_eq!(ExecutionErrorKind::CycleLimitExceeded, debugger.error().unwrap().kind());
+        assert_eq!(100, debugger.last_step());
+
+        // a successful execution has no error
+        let program = assembly::compile("begin push.3 push.2 add end").unwrap();
+        assert!(Debugger::new(&program, &ProgramInputs::none(), &ExecutionOptions::default()).error().is_none());
+    }
+}
@@ -6,6 +6,7 @@ use crate::{
     SPONGE_WIDTH, BASE_CYCLE_LENGTH, PUSH_OP_ALIGNMENT,
 };
 use super::opcodes::{ FlowOps, UserOps };
+use super::ExecutionErrorKind;
 use sp_std::{vec, vec::Vec};
 
 #[cfg(test)]
@@ -85,7 +86,6 @@ impl Decoder {
     }
 
     /// Returns value of the current step pointer.
-    #[cfg(test)]
     pub fn current_step(&self) -> usize {
         return self.step;
     }
@@ -157,16 +157,21 @@ impl Decoder {
     // OPERATION DECODERS
     // --------------------------------------------------------------------------------------------
 
-    /// Initiates a new program block (Group or Switch).
-    pub fn start_block(&mut self) {
+    /// Initiates a new program block (Group or Switch); fails if blocks are nested deeper than
+    /// the maximum context depth.
+    pub fn start_block(&mut self) -> Result<(), ExecutionErrorKind> {
         assert!(self.step % BASE_CYCLE_LENGTH == BASE_CYCLE_LENGTH - 1,
             "cannot start context block at step {}: operation alignment is not valid", self.step);
+        if self.ctx_depth >= MAX_CONTEXT_DEPTH {
+            return Err(ExecutionErrorKind::ContextStackOverflow);
+        }
 
         self.advance_step(false);
         self.save_context();
         self.copy_loop_stack();
         self.set_op_bits(FlowOps::Begin, UserOps::Noop);
         self.set_sponge([0, 0, 0, 0]);
+        return Ok(());
     }
 
     /// Terminates a program block (Group, Switch, or Loop).
@@ -191,16 +196,24 @@ impl Decoder {
         }
     }
 
-    /// Initiates a new Loop block
-    pub fn start_loop(&mut self, loop_image: u128) {
+    /// Initiates a new Loop block; fails if blocks or loops are nested deeper than the maximum
+    /// context or loop depth.
+    pub fn start_loop(&mut self, loop_image: u128) -> Result<(), ExecutionErrorKind> {
         assert!(self.step % BASE_CYCLE_LENGTH == BASE_CYCLE_LENGTH - 1,
             "cannot start a loop at step {}: operation alignment is not valid", self.step);
+        if self.ctx_depth >= MAX_CONTEXT_DEPTH {
+            return Err(ExecutionErrorKind::ContextStackOverflow);
+        }
+        if self.loop_depth >= MAX_LOOP_DEPTH {
+            return Err(ExecutionErrorKind::LoopStackOverflow);
+        }
 
         self.advance_step(false);
         self.save_context();
         self.save_loop_image(loop_image);
         self.set_op_bits(FlowOps::Loop, UserOps::Noop);
         self.set_sponge([0, 0, 0, 0]);
+        return Ok(());
     }
 
     /// Prepares the decoder for the next iteration of a loop.
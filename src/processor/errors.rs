@@ -0,0 +1,147 @@
+use sp_std::{ fmt, vec::Vec };
+use super::{ OpCode, OpHint };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Describes a failure which prevented the VM from executing a program to completion.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecutionError {
+    kind        : ExecutionErrorKind,
+    step        : usize,
+    op_code     : OpCode,
+    op_hint     : OpHint,
+    stack_top   : Vec<u128>,
+}
+
+/// Describes the reason why program execution failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExecutionErrorKind {
+    /// The operation needs more items than the stack holds.
+    StackUnderflow,
+    /// The operation would grow the stack beyond the maximum stack depth.
+    StackOverflow,
+    /// The operation needs a value from secret tape A, but the tape is empty.
+    EmptyTapeA,
+    /// The operation needs a value from secret tape B, but the tape is empty.
+    EmptyTapeB,
+    /// ASSERT operation was executed against a value other than 1.
+    AssertFailed,
+    /// ASSERTEQ operation was executed against values which are not equal.
+    AssertEqFailed,
+    /// The hint attached to the operation is not valid for this operation.
+    InvalidHint,
+    /// A boolean operation was executed against a value which is neither 0 nor 1.
+    NonBinaryValue,
+    /// A selection operation, a conditional block, or a loop was executed against a condition
+    /// which is neither 0 nor 1.
+    NonBinaryCondition,
+    /// A value read from a secret tape by CMP or BINACC operation is neither 0 nor 1.
+    NonBinaryInput,
+    /// A power of 2 expected by CMP or BINACC operation is not a power of 2.
+    NotPowerOfTwo,
+    /// INV operation was executed against 0.
+    InverseOfZero,
+    /// The auxiliary value provided for EQ operation is not valid.
+    InvalidEqAux,
+    /// Blocks are nested deeper than the maximum context depth.
+    ContextStackOverflow,
+    /// Loops are nested deeper than the maximum loop depth.
+    LoopStackOverflow,
+    /// Execution would take more steps than allowed by execution options.
+    CycleLimitExceeded,
+    /// Execution would need an execution trace longer than allowed by execution options.
+    TraceLengthExceeded,
+    /// Secret input tapes hold more values than allowed by execution options.
+    SecretInputLimitExceeded,
+    /// A sequence of program blocks does not start with a Span block.
+    MalformedProgram,
+    /// More outputs were requested than the VM can return.
+    TooManyOutputs,
+    /// Execution took fewer operations than the minimum length of the execution trace.
+    TooFewOperations,
+    /// Program hash computed by the VM does not match the hash of the program.
+    ProgramHashMismatch,
+}
+
+// EXECUTION ERROR IMPLEMENTATION
+// ================================================================================================
+impl ExecutionError {
+
+    pub fn new(kind: ExecutionErrorKind, step: usize, op_code: OpCode, op_hint: OpHint, stack_top: Vec<u128>) -> ExecutionError {
+        return ExecutionError { kind, step, op_code, op_hint, stack_top };
+    }
+
+    /// Returns the reason why execution failed.
+    pub fn kind(&self) -> ExecutionErrorKind {
+        return self.kind;
+    }
+
+    /// Returns the step of the execution trace at which execution failed.
+    pub fn step(&self) -> usize {
+        return self.step;
+    }
+
+    /// Returns the operation executed by the stack at the failed step; this is NOOP when
+    /// execution failed while entering or leaving a program block.
+    pub fn op_code(&self) -> OpCode {
+        return self.op_code;
+    }
+
+    /// Returns the hint of the operation executed at the failed step.
+    pub fn op_hint(&self) -> OpHint {
+        return self.op_hint;
+    }
+
+    /// Returns the top of the stack right before the failed step; the top of the stack is the
+    /// first item.
+    pub fn stack_top(&self) -> &[u128] {
+        return &self.stack_top;
+    }
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{} at step {} ({}{}); stack top: {:?}",
+            self.kind.as_str(), self.step, self.op_code, self.op_hint, self.stack_top);
+    }
+}
+
+// EXECUTION ERROR KIND IMPLEMENTATION
+// ================================================================================================
+impl ExecutionErrorKind {
+
+    /// Returns a static description of the error.
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            ExecutionErrorKind::StackUnderflow           => "stack underflow",
+            ExecutionErrorKind::StackOverflow            => "stack overflow",
+            ExecutionErrorKind::EmptyTapeA               => "attempt to read from empty tape A",
+            ExecutionErrorKind::EmptyTapeB               => "attempt to read from empty tape B",
+            ExecutionErrorKind::AssertFailed             => "ASSERT failed",
+            ExecutionErrorKind::AssertEqFailed           => "ASSERTEQ failed",
+            ExecutionErrorKind::InvalidHint              => "invalid execution hint",
+            ExecutionErrorKind::NonBinaryValue           => "operation on a non-binary value",
+            ExecutionErrorKind::NonBinaryCondition       => "non-binary condition",
+            ExecutionErrorKind::NonBinaryInput           => "non-binary value on a secret tape",
+            ExecutionErrorKind::NotPowerOfTwo            => "expected a power of 2",
+            ExecutionErrorKind::InverseOfZero            => "cannot compute INV of 0",
+            ExecutionErrorKind::InvalidEqAux             => "invalid AUX value for EQ operation",
+            ExecutionErrorKind::ContextStackOverflow     => "context stack overflow",
+            ExecutionErrorKind::LoopStackOverflow        => "loop stack overflow",
+            ExecutionErrorKind::CycleLimitExceeded       => "cycle limit exceeded",
+            ExecutionErrorKind::TraceLengthExceeded      => "maximum trace length exceeded",
+            ExecutionErrorKind::SecretInputLimitExceeded => "too many secret inputs",
+            ExecutionErrorKind::MalformedProgram         => "block sequence does not start with a Span block",
+            ExecutionErrorKind::TooManyOutputs           => "too many outputs requested",
+            ExecutionErrorKind::TooFewOperations         => "too few operations executed",
+            ExecutionErrorKind::ProgramHashMismatch      => "program hash does not match trace hash",
+        };
+    }
+}
+
+impl fmt::Display for ExecutionErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", self.as_str());
+    }
+}
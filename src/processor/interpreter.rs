@@ -0,0 +1,221 @@
+use crate::{
+    math::field,
+    programs::{ Program, ProgramInputs, blocks::{ ProgramBlock, Span, Loop } },
+    MAX_CONTEXT_DEPTH, MAX_LOOP_DEPTH, HACC_NUM_ROUNDS,
+};
+use sp_std::vec::Vec;
+use super::{ Stack, OpCode, OpHint, ExecutionError, ExecutionErrorKind, ExecutionReport, ExecutionOptions };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Result of running a program without building an execution trace.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunResult {
+    outputs : Vec<u128>,
+    stack   : Vec<u128>,
+    cycles  : usize,
+    report  : ExecutionReport,
+}
+
+/// Executes programs against a stack which does not keep register traces; control flow is
+/// followed in the same way as by the decoder, but block hashes are not computed.
+struct Interpreter {
+    stack       : Stack,
+    ctx_depth   : usize,
+    loop_depth  : usize,
+    report      : ExecutionReport,
+}
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Executes the `program` against the specified inputs without building an execution trace, and
+/// returns `num_outputs` items from the top of the stack, the final state of the stack, and the
+/// number of steps the execution trace would have before it is padded to a power of 2; fails
+/// for the same programs and inputs as `execute()`, and with the same errors.
+pub fn run(program: &Program, inputs: &ProgramInputs, num_outputs: usize) -> Result<RunResult, ExecutionError>
+{
+    return run_with_limits(program, inputs, num_outputs, &ExecutionOptions::unlimited());
+}
+
+/// Runs the `program` in the same way as `run()`, but stops with an error as soon as execution
+/// exceeds any of the specified `limits`; limits are checked in the same way as by
+/// `execute_with_limits()`.
+pub fn run_with_limits(program: &Program, inputs: &ProgramInputs, num_outputs: usize, limits: &ExecutionOptions) -> Result<RunResult, ExecutionError>
+{
+    let mut interpreter = Interpreter {
+        stack       : Stack::without_trace(inputs, limits),
+        ctx_depth   : 1,
+        loop_depth  : 0,
+        report      : ExecutionReport::new(),
+    };
+    interpreter.stack.check_secret_inputs()?;
+
+    // execute body of the program
+    interpreter.execute_blocks(program.root().body())?;
+    interpreter.close_block()?;
+
+    // items beyond the stack depth are read as zeros, same as in the execution trace
+    let stack = interpreter.stack.get_stack_state();
+    let mut outputs: Vec<u128> = stack.iter().take(num_outputs).copied().collect();
+    outputs.resize(num_outputs, field::ZERO);
+
+    let cycles = interpreter.stack.current_step();
+    let mut report = interpreter.report;
+    report.finalize(cycles, interpreter.stack.max_depth());
+
+    return Ok(RunResult { outputs, stack, cycles, report });
+}
+
+// RUN RESULT IMPLEMENTATION
+// ================================================================================================
+impl RunResult {
+
+    /// Returns the requested number of items from the top of the stack.
+    pub fn outputs(&self) -> &[u128] {
+        return &self.outputs;
+    }
+
+    /// Returns all items on the stack at the end of execution; the top of the stack is the
+    /// first item.
+    pub fn stack(&self) -> &[u128] {
+        return &self.stack;
+    }
+
+    /// Returns the number of steps executed by the VM.
+    pub fn cycles(&self) -> usize {
+        return self.cycles;
+    }
+
+    /// Returns statistics of the execution; these can be used to estimate the cost of proving
+    /// the execution before the proof is generated.
+    pub fn report(&self) -> &ExecutionReport {
+        return &self.report;
+    }
+}
+
+// INTERPRETER IMPLEMENTATION
+// ================================================================================================
+impl Interpreter {
+
+    /// Executes a sequence of blocks; the first block in the sequence must be a Span block.
+    fn execute_blocks(&mut self, blocks: &[ProgramBlock]) -> Result<(), ExecutionError> {
+        match &blocks[0] {
+            ProgramBlock::Span(block) => self.execute_span(block, true)?,
+            _ => return Err(self.stack.flow_error(ExecutionErrorKind::MalformedProgram)),
+        }
+
+        for block in blocks.iter().skip(1) {
+            match block {
+                ProgramBlock::Span(block) => self.execute_span(block, false)?,
+                ProgramBlock::Group(block) => {
+                    self.start_block()?;
+                    self.execute_blocks(block.body())?;
+                    self.close_block()?;
+                },
+                ProgramBlock::Switch(block) => {
+                    match self.stack.get_stack_top() {
+                        0 => {
+                            self.start_block()?;
+                            self.execute_blocks(block.false_branch())?;
+                            self.close_block()?;
+                        },
+                        1 => {
+                            self.start_block()?;
+                            self.execute_blocks(block.true_branch())?;
+                            self.close_block()?;
+                        },
+                        _ => return Err(self.stack.flow_error(ExecutionErrorKind::NonBinaryCondition)),
+                    }
+                },
+                ProgramBlock::Loop(block) => {
+                    match self.stack.get_stack_top() {
+                        0 => {
+                            self.start_block()?;
+                            self.execute_skip_block(block)?;
+                            self.close_block()?;
+                        },
+                        1 => self.execute_loop(block)?,
+                        _ => return Err(self.stack.flow_error(ExecutionErrorKind::NonBinaryCondition)),
+                    }
+                },
+            }
+        }
+        return Ok(());
+    }
+
+    /// Executes all instructions in a Span block; a Span block which is not the first block in
+    /// a sequence is preceded by a NOOP.
+    fn execute_span(&mut self, block: &Span, is_first: bool) -> Result<(), ExecutionError> {
+        if !is_first {
+            self.stack.execute(OpCode::Noop, OpHint::None)?;
+            self.report.record_alignment_noop();
+        }
+
+        for i in 0..block.length() {
+            let (op_code, op_hint) = block.get_op(i);
+            self.stack.execute(op_code, op_hint)?;
+            self.report.record_op(op_code);
+        }
+        return Ok(());
+    }
+
+    fn execute_skip_block(&mut self, block: &Loop) -> Result<(), ExecutionError> {
+        return match &block.skip()[0] {
+            ProgramBlock::Span(block) => self.execute_span(block, true),
+            _ => Err(self.stack.flow_error(ExecutionErrorKind::MalformedProgram)),
+        };
+    }
+
+    fn execute_loop(&mut self, block: &Loop) -> Result<(), ExecutionError> {
+        if self.ctx_depth >= MAX_CONTEXT_DEPTH {
+            return Err(self.stack.flow_error(ExecutionErrorKind::ContextStackOverflow));
+        }
+        if self.loop_depth >= MAX_LOOP_DEPTH {
+            return Err(self.stack.flow_error(ExecutionErrorKind::LoopStackOverflow));
+        }
+        self.ctx_depth += 1;
+        self.loop_depth += 1;
+        self.report.record_depths(self.ctx_depth, self.loop_depth);
+        self.stack.execute(OpCode::Noop, OpHint::None)?;
+
+        // execute blocks in loop body until top of the stack becomes 0
+        loop {
+            self.execute_blocks(block.body())?;
+            match self.stack.get_stack_top() {
+                0 => {
+                    self.loop_depth -= 1;
+                    self.stack.execute(OpCode::Noop, OpHint::None)?;
+                    break;
+                },
+                1 => self.stack.execute(OpCode::Noop, OpHint::None)?,
+                _ => return Err(self.stack.flow_error(ExecutionErrorKind::NonBinaryCondition)),
+            }
+        }
+
+        self.execute_skip_block(block)?;
+        return self.close_block();
+    }
+
+    fn start_block(&mut self) -> Result<(), ExecutionError> {
+        if self.ctx_depth >= MAX_CONTEXT_DEPTH {
+            return Err(self.stack.flow_error(ExecutionErrorKind::ContextStackOverflow));
+        }
+        self.ctx_depth += 1;
+        self.report.record_depths(self.ctx_depth, self.loop_depth);
+        self.stack.execute(OpCode::Noop, OpHint::None)?;
+        return Ok(());
+    }
+
+    /// Closes the currently executing block; this takes the same number of steps as merging
+    /// block hash into the program hash in the decoder.
+    fn close_block(&mut self) -> Result<(), ExecutionError> {
+        self.ctx_depth -= 1;
+        self.report.record_alignment_noop();
+        for _ in 0..(HACC_NUM_ROUNDS + 2) {
+            self.stack.execute(OpCode::Noop, OpHint::None)?;
+        }
+        return Ok(());
+    }
+}
@@ -1,9 +1,9 @@
 use crate::{
     math::field,
-    programs::{ Program, ProgramInputs, blocks::{ ProgramBlock, Span, Loop } },
+    programs::{ Program, ProgramInputs, blocks::{ ProgramBlock, Span, Loop, OpLocation } },
     MIN_TRACE_LENGTH, HACC_NUM_ROUNDS,
 };
-use sp_std::vec::Vec;
+use sp_std::{ vec, vec::Vec };
 
 // RE-EXPORTS
 // ================================================================================================
@@ -15,23 +15,72 @@ mod stack;
 pub use stack::{ Stack };
 
 pub mod opcodes;
-pub use opcodes::{ UserOps as OpCode, OpHint };
+pub use opcodes::{ UserOps as OpCode, FlowOps, OpHint };
+
+mod errors;
+pub use errors::{ ExecutionError, ExecutionErrorKind };
+
+mod debugger;
+pub use debugger::{ Debugger, Breakpoint, VmState };
+
+mod interpreter;
+pub use interpreter::{ run, run_with_limits, RunResult };
+
+mod report;
+pub use report::ExecutionReport;
+
+mod options;
+pub use options::ExecutionOptions;
 
 // PUBLIC FUNCTIONS
 // ================================================================================================
 
-/// Returns register traces resulting from executing the `program` against the specified inputs.
-pub fn execute(program: &Program, inputs: &ProgramInputs) -> (Vec<Vec<u128>>, usize, usize)
+/// Returns register traces resulting from executing the `program` against the specified inputs;
+/// fails if any operation of the program cannot be executed. Execution is not limited; use
+/// `execute_with_limits()` to execute untrusted programs.
+#[cfg(test)]
+pub fn execute(program: &Program, inputs: &ProgramInputs) -> Result<(Vec<Vec<u128>>, usize, usize), ExecutionError>
+{
+    return execute_with_limits(program, inputs, &ExecutionOptions::unlimited());
+}
+
+/// Executes the `program` in the same way as `execute()`, but stops with an error as soon as
+/// execution exceeds any of the specified `limits`.
+pub fn execute_with_limits(program: &Program, inputs: &ProgramInputs, limits: &ExecutionOptions) -> Result<(Vec<Vec<u128>>, usize, usize), ExecutionError>
+{
+    let (register_traces, context_depth, loop_depth, _, error) = execute_with_log(program, inputs, limits, false);
+    if let Some(err) = error { return Err(err); }
+    return Ok((register_traces, context_depth, loop_depth));
+}
+
+/// Executes the `program` in the same way as `execute()`, and also returns the location of the
+/// span operation executed at every step of the execution trace; steps at which the VM executes
+/// control flow operations or NOOPs added for block alignment have no location.
+pub fn execute_with_locations(program: &Program, inputs: &ProgramInputs) -> Result<(Vec<Vec<u128>>, usize, usize, Vec<Option<OpLocation>>), ExecutionError>
+{
+    let limits = ExecutionOptions::unlimited();
+    let (register_traces, context_depth, loop_depth, log, error) = execute_with_log(program, inputs, &limits, true);
+    if let Some(err) = error { return Err(err); }
+    return Ok((register_traces, context_depth, loop_depth, log.locations));
+}
+
+/// Executes the `program` in the same way as `execute()`, and also returns the log of the
+/// execution; if `keep_log` is true, the log contains one entry per step of the execution trace,
+/// otherwise, nothing is recorded and the returned log is empty. If execution fails, the traces
+/// and the log are returned together with the error; only the steps before the failing step
+/// (see `ExecutionError::step()`) hold valid states of the VM.
+fn execute_with_log(program: &Program, inputs: &ProgramInputs, limits: &ExecutionOptions, keep_log: bool) -> (Vec<Vec<u128>>, usize, usize, ExecutionLog, Option<ExecutionError>)
 {
-    // initialize decoder and stack components
+    // initialize decoder and stack components; the stack enforces execution limits
     let mut decoder = Decoder::new(MIN_TRACE_LENGTH);
-    let mut stack = Stack::new(inputs, MIN_TRACE_LENGTH);
+    let mut stack = Stack::new(inputs, MIN_TRACE_LENGTH, limits);
+    let mut log = if keep_log { ExecutionLog::new(stack.tape_lengths()) } else { ExecutionLog::disabled() };
 
-    // execute body of the program
-    execute_blocks(program.root().body(), &mut decoder, &mut stack);
-    close_block(&mut decoder, &mut stack, field::ZERO, true);
+    // execute the program; on failure, the traces are finalized at the step the VM reached
+    let error = execute_root(program, &mut decoder, &mut stack, &mut log).err();
 
     // fill in remaining steps to make sure the length of the trace is a power of 2
+    log.finalize(decoder.trace_length());
     decoder.finalize_trace();
     stack.finalize_trace();
 
@@ -43,148 +92,219 @@ pub fn execute(program: &Program, inputs: &ProgramInputs) -> (Vec<Vec<u128>>, us
     let mut register_traces = decoder.into_register_traces();
     register_traces.append(&mut stack.into_register_traces());
 
-    return (register_traces, context_depth, loop_depth);
+    return (register_traces, context_depth, loop_depth, log, error);
 }
 
 // HELPER FUNCTIONS
 // ================================================================================================
-fn execute_blocks(blocks: &[ProgramBlock], decoder: &mut Decoder, stack: &mut Stack)
+
+/// Checks secret inputs against execution limits, and executes the root block of the program.
+fn execute_root(program: &Program, decoder: &mut Decoder, stack: &mut Stack, log: &mut ExecutionLog) -> Result<(), ExecutionError>
 {
-    // execute first block in the sequence, which mast be a Span block
+    stack.check_secret_inputs()?;
+    execute_blocks(program.root().body(), 0, decoder, stack, log)?;
+    return close_block(decoder, stack, field::ZERO, true);
+}
 
+/// Executes a sequence of blocks; `first_span` is the number of the first span in the sequence
+/// (see `OpLocation` for details on span numbering).
+fn execute_blocks(blocks: &[ProgramBlock], first_span: usize, decoder: &mut Decoder, stack: &mut Stack, log: &mut ExecutionLog) -> Result<(), ExecutionError>
+{
+    // execute first block in the sequence, which mast be a Span block
     match &blocks[0] {
         ProgramBlock::Span(block) => {
-
-            execute_span(block, decoder, stack, true)
-        
+            execute_span(block, Some(first_span), decoder, stack, log, true)?
         },
-
-        _ => panic!("first block in a sequence must be a Span block"),
+        _ => return Err(stack.flow_error(ExecutionErrorKind::MalformedProgram)),
     }
 
     // execute all other blocks in the sequence one after another
+    let mut span = first_span + 1;
     for block in blocks.iter().skip(1) {
         match block {
-            ProgramBlock::Span(block) => execute_span(block, decoder, stack, false),
+            ProgramBlock::Span(block) => execute_span(block, Some(span), decoder, stack, log, false)?,
             ProgramBlock::Group(block) => {
-                start_block(decoder, stack);
-                execute_blocks(block.body(), decoder, stack);
-                close_block(decoder, stack, field::ZERO, true);
+                start_block(decoder, stack)?;
+                execute_blocks(block.body(), span, decoder, stack, log)?;
+                close_block(decoder, stack, field::ZERO, true)?;
             },
             ProgramBlock::Switch(block) => {
-                start_block(decoder, stack);
                 let condition = stack.get_stack_top();
                 match condition {
                     0 => {
-                        execute_blocks(block.false_branch(), decoder, stack);
-                        close_block(decoder, stack, block.true_branch_hash(), false);
+                        start_block(decoder, stack)?;
+                        let false_span = span + block.true_branch_span_count();
+                        execute_blocks(block.false_branch(), false_span, decoder, stack, log)?;
+                        close_block(decoder, stack, block.true_branch_hash(), false)?;
                     },
                     1 => {
-                        execute_blocks(block.true_branch(), decoder, stack);
-                        close_block(decoder, stack, block.false_branch_hash(), true);
+                        start_block(decoder, stack)?;
+                        execute_blocks(block.true_branch(), span, decoder, stack, log)?;
+                        close_block(decoder, stack, block.false_branch_hash(), true)?;
                     },
-                    _ => panic!("cannot select a branch based on a non-binary condition {}", condition)
+                    _ => return Err(stack.flow_error(ExecutionErrorKind::NonBinaryCondition)),
                 };
             },
             ProgramBlock::Loop(block) => {
                 let condition = stack.get_stack_top();
                 match condition {
                     0 => {
-                        start_block(decoder, stack);
-                        execute_blocks(block.skip(), decoder, stack);
-                        close_block(decoder, stack, block.body_hash(), false);
+                        start_block(decoder, stack)?;
+                        execute_skip_block(block, decoder, stack, log)?;
+                        close_block(decoder, stack, block.body_hash(), false)?;
                     },
-                    1 => execute_loop(block, decoder, stack),
-                    _ => panic!("cannot enter loop based on a non-binary condition {}", condition)
+                    1 => execute_loop(block, span, decoder, stack, log)?,
+                    _ => return Err(stack.flow_error(ExecutionErrorKind::NonBinaryCondition)),
                 }
             },
         }
+        span += block.span_count();
     }
+    return Ok(());
 }
 
-/// Executes all instructions in a Span block.
-fn execute_span(block: &Span, decoder: &mut Decoder, stack: &mut Stack, is_first: bool)
+/// Executes all instructions in a Span block; `span` is the number of the span in the program,
+/// or None if the span is a skip block of a loop.
+fn execute_span(block: &Span, span: Option<usize>, decoder: &mut Decoder, stack: &mut Stack, log: &mut ExecutionLog, is_first: bool) -> Result<(), ExecutionError>
 {
     // if this is the first Span block in a sequence of blocks, it needs to be
     // pre-padded with a NOOP to make sure the first instruction in the block
     // starts executing on a step which is a multiple of 16
     if !is_first {
         decoder.decode_op(OpCode::Noop, field::ZERO);
-        stack.execute(OpCode::Noop, OpHint::None);
+        stack.execute(OpCode::Noop, OpHint::None)?;
     }
 
     // execute all other instructions in the block
     for i in 0..block.length() {
+        // operation bits are recorded at the step which precedes the decoding of the operation
+        let step = decoder.current_step();
+
         let (op_code, op_hint) = block.get_op(i);
         decoder.decode_op(op_code, op_hint.value());
 
-        stack.execute(op_code, op_hint);
-
+        // the location is recorded even if the operation fails, so that the failing operation
+        // can be located in the log
+        let result = stack.execute(op_code, op_hint);
+        log.record_op(step, span.map(|span| OpLocation { span, op: i }), stack.tape_lengths());
+        result?;
     }
+    return Ok(());
+}
+
+/// Executes the skip block of a loop; operations of skip blocks have no locations.
+fn execute_skip_block(block: &Loop, decoder: &mut Decoder, stack: &mut Stack, log: &mut ExecutionLog) -> Result<(), ExecutionError>
+{
+    return match &block.skip()[0] {
+        ProgramBlock::Span(block) => execute_span(block, None, decoder, stack, log, true),
+        _ => Err(stack.flow_error(ExecutionErrorKind::MalformedProgram)),
+    };
 }
 
 /// Starts executing a new program block.
-fn start_block(decoder: &mut Decoder, stack: &mut Stack)
+fn start_block(decoder: &mut Decoder, stack: &mut Stack) -> Result<(), ExecutionError>
 {
-    decoder.start_block();
-    stack.execute(OpCode::Noop, OpHint::None);
+    decoder.start_block().map_err(|kind| stack.flow_error(kind))?;
+    stack.execute(OpCode::Noop, OpHint::None)?;
+    return Ok(());
 }
 
 /// Closes the currently executing program block.
-fn close_block(decoder: &mut Decoder, stack: &mut Stack, sibling_hash: u128, is_true_branch: bool)
+fn close_block(decoder: &mut Decoder, stack: &mut Stack, sibling_hash: u128, is_true_branch: bool) -> Result<(), ExecutionError>
 {
     // a sequence of blocks always ends on a step which is one less than a multiple of 16;
     // all sequences end one operation short of multiple of 16 - so, we need to pad them
     // with a single NOOP ensure proper alignment
     decoder.decode_op(OpCode::Noop, field::ZERO);
-    stack.execute(OpCode::Noop, OpHint::None);
+    stack.execute(OpCode::Noop, OpHint::None)?;
 
     // end the block, this prepares decoder registers for merging block hash into
     // program hash
     decoder.end_block(sibling_hash, is_true_branch);
-    stack.execute(OpCode::Noop, OpHint::None);
+    stack.execute(OpCode::Noop, OpHint::None)?;
 
     // execute NOOPs to merge block hash into the program hash
     for _ in 0..HACC_NUM_ROUNDS {
         decoder.decode_op(OpCode::Noop, field::ZERO);
-        stack.execute(OpCode::Noop, OpHint::None);
+        stack.execute(OpCode::Noop, OpHint::None)?;
     }
+    return Ok(());
 }
 
 /// Executes the specified loop.
-fn execute_loop(block: &Loop, decoder: &mut Decoder, stack: &mut Stack)
+fn execute_loop(block: &Loop, first_span: usize, decoder: &mut Decoder, stack: &mut Stack, log: &mut ExecutionLog) -> Result<(), ExecutionError>
 {
     // mark the beginning of the loop block
-    decoder.start_loop(block.image());
-    stack.execute(OpCode::Noop, OpHint::None);
+    decoder.start_loop(block.image()).map_err(|kind| stack.flow_error(kind))?;
+    stack.execute(OpCode::Noop, OpHint::None)?;
 
     // execute blocks in loop body until top of the stack becomes 0
     loop {
-        execute_blocks(block.body(), decoder, stack);
+        execute_blocks(block.body(), first_span, decoder, stack, log)?;
 
         let condition = stack.get_stack_top();
         match condition {
             0 => {
                 decoder.break_loop();
-                stack.execute(OpCode::Noop, OpHint::None);
+                stack.execute(OpCode::Noop, OpHint::None)?;
                 break;
             },
             1 => {
                 decoder.wrap_loop();
-                stack.execute(OpCode::Noop, OpHint::None);
+                stack.execute(OpCode::Noop, OpHint::None)?;
             },
-            _ => panic!("cannot exit loop based on a non-binary condition {}", condition)
+            _ => return Err(stack.flow_error(ExecutionErrorKind::NonBinaryCondition)),
         };
     }
 
     // execute the contents of the skip block to make sure the loop was exited correctly
-    match &block.skip()[0] {
-        ProgramBlock::Span(block) => execute_span(block, decoder, stack, true),
-        _ => panic!("invalid skip block content: content must be a Span block"),
-    }
+    execute_skip_block(block, decoder, stack, log)?;
 
     // close block
-    close_block(decoder, stack, block.skip_hash(), true);
+    return close_block(decoder, stack, block.skip_hash(), true);
+}
+
+// EXECUTION LOG
+// ================================================================================================
+
+/// Information about program execution which is not captured by register traces.
+struct ExecutionLog {
+    locations       : Vec<Option<OpLocation>>,
+    tape_lengths    : Vec<(usize, usize)>,
+    enabled         : bool,
+}
+
+impl ExecutionLog {
+
+    fn new(init_tape_lengths: (usize, usize)) -> ExecutionLog {
+        return ExecutionLog { locations: Vec::new(), tape_lengths: vec![init_tape_lengths], enabled: true };
+    }
+
+    /// Returns a log which ignores all records; used when the caller does not need the log.
+    fn disabled() -> ExecutionLog {
+        return ExecutionLog { locations: Vec::new(), tape_lengths: Vec::new(), enabled: false };
+    }
+
+    /// Records an operation decoded at the specified `step`: the location of the operation is
+    /// recorded for the `step`, and tape lengths after the operation are recorded for the next
+    /// step; steps skipped since the last operation inherit the last recorded values.
+    fn record_op(&mut self, step: usize, location: Option<OpLocation>, tape_lengths: (usize, usize)) {
+        if !self.enabled { return; }
+        self.locations.resize(step, None);
+        self.locations.push(location);
+
+        let last_tape_lengths = self.tape_lengths[self.tape_lengths.len() - 1];
+        self.tape_lengths.resize(step + 1, last_tape_lengths);
+        self.tape_lengths.push(tape_lengths);
+    }
+
+    /// Extends the log to cover all steps of the execution trace.
+    fn finalize(&mut self, trace_length: usize) {
+        if !self.enabled { return; }
+        let last_tape_lengths = self.tape_lengths[self.tape_lengths.len() - 1];
+        self.tape_lengths.resize(trace_length, last_tape_lengths);
+        self.locations.resize(trace_length, None);
+    }
 }
 
 // TESTS
@@ -193,15 +313,16 @@ fn execute_loop(block: &Loop, decoder: &mut Decoder, stack: &mut Stack)
 #[cfg(test)]
 mod tests {
 
-    use crate::{ programs::assembly, stark::TraceState, utils::as_bytes };
-    use super::{ ProgramInputs };
+    use crate::{ programs::{ assembly, blocks::OpLocation }, stark::TraceState, utils::as_bytes };
+    use sp_std::vec::Vec;
+    use super::{ ProgramInputs, OpCode, ExecutionErrorKind };
 
     #[test]
     fn execute_span() {
         let program = assembly::compile("begin add push.5 mul push.7 end").unwrap();
         let inputs = ProgramInputs::from_public(&[1, 2]);
 
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(64, trace_length);
@@ -223,7 +344,7 @@ mod tests {
         let program = assembly::compile("begin add block push.5 mul push.7 end end").unwrap();
         let inputs = ProgramInputs::from_public(&[1, 2]);
 
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(64, trace_length);
@@ -249,7 +370,7 @@ mod tests {
         
         // execute true branch
         let inputs = ProgramInputs::new(&[5, 3], &[1], &[]);
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(128, trace_length);
@@ -269,7 +390,7 @@ mod tests {
 
         // execute false branch
         let inputs = ProgramInputs::new(&[5, 3], &[0], &[]);
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(128, trace_length);
@@ -295,7 +416,7 @@ mod tests {
 
         // don't enter the loop
         let inputs = ProgramInputs::new(&[5, 3], &[0], &[]);
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(64, trace_length);
@@ -315,7 +436,7 @@ mod tests {
 
         // execute one iteration
         let inputs = ProgramInputs::new(&[5, 3], &[1, 0], &[]);
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(128, trace_length);
@@ -335,7 +456,7 @@ mod tests {
 
         // execute five iteration
         let inputs = ProgramInputs::new(&[5, 3], &[1, 1, 1, 1, 1, 0], &[]);
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(256, trace_length);
@@ -354,6 +475,52 @@ mod tests {
         assert_eq!([43143988327398919500410556793212890625, 0, 0, 0, 0, 0, 0, 0], state.user_stack());
     }
 
+    #[test]
+    fn execute_with_locations() {
+        let program = assembly::compile("begin push.5 push.1 if.true push.2 else push.3 end add end").unwrap();
+        let inputs = ProgramInputs::none();
+
+        let (trace, _, _, locations) = super::execute_with_locations(&program, &inputs).unwrap();
+        assert_eq!(trace[0].len(), locations.len());
+
+        // operations of the first span are executed starting from the first step
+        assert_eq!(Some(OpLocation { span: 0, op: 0 }), locations[0]);
+        assert_eq!(Some(OpLocation { span: 0, op: 8 }), locations[8]);
+
+        // only the true branch (span 1) is executed, and the false branch (span 2) is skipped
+        assert!(locations.contains(&Some(OpLocation { span: 1, op: 8 })));
+        assert!(!locations.iter().any(|location| location.map_or(false, |l| l.span == 2)));
+
+        // control flow steps have no location
+        assert!(locations.contains(&None));
+
+        // spans of a skipped true branch (spans 1 to 3) are still counted when numbering spans
+        // of the false branch and the spans which follow it
+        let program = assembly::compile(
+            "begin push.5 push.0 if.true push.2 if.true push.3 else push.4 end else push.5 end add end").unwrap();
+        let (_, _, _, locations) = super::execute_with_locations(&program, &inputs).unwrap();
+        let mut spans: Vec<usize> = locations.iter().filter_map(|location| location.map(|l| l.span)).collect();
+        spans.dedup();
+        assert_eq!(vec![0, 4, 5], spans);
+    }
+
+    #[test]
+    fn execute_failures() {
+        // failed assertion is reported for the ASSERT operation
+        let program = assembly::compile("begin push.2 assert end").unwrap();
+        let err = super::execute(&program, &ProgramInputs::none()).err().unwrap();
+        assert_eq!(ExecutionErrorKind::AssertFailed, err.kind());
+        assert_eq!(OpCode::Assert, err.op_code());
+        assert_eq!(2, err.stack_top()[0]);
+
+        // non-binary condition is reported when entering a conditional block
+        let program = assembly::compile("begin push.2 if.true push.3 else push.4 end end").unwrap();
+        let err = super::execute(&program, &ProgramInputs::none()).err().unwrap();
+        assert_eq!(ExecutionErrorKind::NonBinaryCondition, err.kind());
+        assert_eq!(OpCode::Noop, err.op_code());
+        assert_eq!(2, err.stack_top()[0]);
+    }
+
     fn build_trace_state(num_registers: usize, ctx_depth: usize, loop_depth: usize) -> TraceState {
         let decoder_width = TraceState::compute_decoder_width(ctx_depth, loop_depth);
         let stack_depth = num_registers - decoder_width;
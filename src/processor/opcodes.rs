@@ -19,8 +19,19 @@ impl FlowOps {
     pub fn op_index(&self) -> usize {
         return (*self as usize) & 0b111;
     }
+
+    /// Returns the control flow operation encoded by the three lower bits of `code`.
+    pub fn from_u8(code: u8) -> FlowOps {
+        return FLOW_OPS[(code & 0b111) as usize];
+    }
 }
 
+/// All control flow operations ordered by their op codes.
+const FLOW_OPS: [FlowOps; 8] = [
+    FlowOps::Hacc, FlowOps::Begin, FlowOps::Tend,  FlowOps::Fend,
+    FlowOps::Loop, FlowOps::Wrap,  FlowOps::Break, FlowOps::Void,
+];
+
 impl sp_std::fmt::Display for FlowOps {
 
     fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
@@ -93,8 +104,25 @@ pub enum UserOps {
     Noop        = 0b0_11_11111,         // no shift
 }
 
+/// All user operations; an operation is identified by its op code.
+const USER_OPS: [UserOps; 32] = [
+    UserOps::Assert, UserOps::AssertEq, UserOps::Eq,     UserOps::Drop,   UserOps::Drop4,
+    UserOps::Choose, UserOps::Choose2,  UserOps::CSwap2, UserOps::Add,    UserOps::Mul,
+    UserOps::And,    UserOps::Or,       UserOps::Inv,    UserOps::Neg,    UserOps::Not,
+    UserOps::Read,   UserOps::Read2,    UserOps::Dup,    UserOps::Dup2,   UserOps::Dup4,
+    UserOps::Pad2,   UserOps::Swap,     UserOps::Swap2,  UserOps::Swap4,  UserOps::Roll4,
+    UserOps::Roll8,  UserOps::BinAcc,   UserOps::Push,   UserOps::Cmp,    UserOps::RescR,
+    UserOps::Begin,  UserOps::Noop,
+];
+
 impl UserOps {
 
+    /// Returns the user operation with the specified op code, or None if no operation has
+    /// this op code.
+    pub fn from_u8(code: u8) -> Option<UserOps> {
+        return USER_OPS.iter().find(|&&op_code| op_code as u8 == code).copied();
+    }
+
     pub fn ld_index(&self) -> usize {
         return match self {
             UserOps::Push | UserOps::Cmp | UserOps::RescR => {
@@ -116,6 +144,70 @@ impl UserOps {
             }
         };
     }
+
+    /// Returns the minimum stack depth required to execute this operation with the specified
+    /// hint; executing the operation on a shallower stack results in a stack underflow.
+    pub fn min_stack_depth(&self, hint: OpHint) -> usize {
+        return match self {
+            UserOps::Begin | UserOps::Noop | UserOps::Push | UserOps::Pad2 => 0,
+
+            UserOps::Read => match hint {
+                OpHint::EqStart => 2,
+                _ => 0,
+            },
+            UserOps::Read2 => match hint {
+                OpHint::PmpathStart(_) => 3,
+                _ => 0,
+            },
+
+            UserOps::Assert | UserOps::Dup | UserOps::Drop
+                | UserOps::Inv | UserOps::Neg | UserOps::Not => 1,
+
+            UserOps::AssertEq | UserOps::Dup2 | UserOps::Swap
+                | UserOps::Add | UserOps::Mul | UserOps::And | UserOps::Or => 2,
+
+            UserOps::Choose | UserOps::Eq => 3,
+
+            UserOps::Dup4 | UserOps::Drop4 | UserOps::Swap2 | UserOps::Roll4 => 4,
+
+            UserOps::BinAcc => match hint {
+                OpHint::RcStart(_) => 5,
+                _ => 4,
+            },
+
+            UserOps::Choose2 | UserOps::CSwap2 | UserOps::RescR => 6,
+
+            UserOps::Swap4 | UserOps::Roll8 => 8,
+
+            UserOps::Cmp => match hint {
+                OpHint::CmpStart(_) => 10,
+                _ => 8,
+            },
+        };
+    }
+
+    /// Returns the change in stack depth caused by this operation; negative values correspond
+    /// to left shifts, and positive values correspond to right shifts.
+    pub fn stack_shift(&self) -> isize {
+        return match self {
+            UserOps::Assert | UserOps::Drop | UserOps::Add | UserOps::Mul
+                | UserOps::And | UserOps::Or => -1,
+
+            UserOps::AssertEq | UserOps::Eq | UserOps::Choose | UserOps::CSwap2 => -2,
+
+            UserOps::Drop4 | UserOps::Choose2 => -4,
+
+            UserOps::Push | UserOps::Read | UserOps::Dup => 1,
+
+            UserOps::Read2 | UserOps::Dup2 | UserOps::Pad2 => 2,
+
+            UserOps::Dup4 => 4,
+
+            UserOps::Begin | UserOps::Noop | UserOps::Inv | UserOps::Neg | UserOps::Not
+                | UserOps::Swap | UserOps::Swap2 | UserOps::Swap4 | UserOps::Roll4 | UserOps::Roll8
+                | UserOps::Cmp | UserOps::BinAcc | UserOps::RescR => 0,
+        };
+    }
 }
 
 impl sp_std::fmt::Display for UserOps {
@@ -171,7 +263,15 @@ impl sp_std::fmt::Display for UserOps {
 
 // OPERATION HINTS
 // ================================================================================================
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+
+/// Bounds of the number of bits processed by range check and comparison sequences, and of the
+/// depth of Merkle trees processed by PMPATH sequences; leaf indexes must fit into 128 bits.
+pub const MIN_HINT_BITS     : u32 = 4;
+pub const MAX_HINT_BITS     : u32 = 128;
+pub const MIN_PMPATH_DEPTH  : u32 = 2;
+pub const MAX_PMPATH_DEPTH  : u32 = 128;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum OpHint {
     EqStart,
     RcStart(u32),
@@ -188,6 +288,16 @@ impl OpHint {
             _ => 0,
         };
     }
+
+    /// Returns true if the numeric parameter of the hint is within the bounds supported by
+    /// the operation which consumes the hint.
+    pub fn is_valid(&self) -> bool {
+        return match *self {
+            OpHint::RcStart(n) | OpHint::CmpStart(n) => n >= MIN_HINT_BITS && n <= MAX_HINT_BITS,
+            OpHint::PmpathStart(n) => n >= MIN_PMPATH_DEPTH && n <= MAX_PMPATH_DEPTH,
+            _ => true,
+        };
+    }
 }
 
 impl sp_std::fmt::Display for OpHint {
@@ -0,0 +1,71 @@
+use crate::MIN_TRACE_LENGTH;
+
+// CONSTANTS
+// ================================================================================================
+const DEFAULT_MAX_CYCLES        : usize = 1_000_000;
+const DEFAULT_MAX_TRACE_LENGTH  : usize = 1 << 20;
+const DEFAULT_MAX_SECRET_INPUTS : usize = 1 << 20;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Limits on resources used by program execution; execution fails with an error as soon as any
+/// of the limits is exceeded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionOptions {
+    max_cycles          : usize,
+    max_trace_length    : usize,
+    max_secret_inputs   : usize,
+}
+
+// EXECUTION OPTIONS IMPLEMENTATION
+// ================================================================================================
+impl ExecutionOptions {
+
+    /// Returns new execution options; panics if `max_trace_length` is not a power of 2, or if
+    /// it is smaller than the minimum trace length.
+    pub fn new(max_cycles: usize, max_trace_length: usize, max_secret_inputs: usize) -> ExecutionOptions {
+        assert!(max_trace_length.is_power_of_two(), "max_trace_length must be a power of 2");
+        assert!(max_trace_length >= MIN_TRACE_LENGTH,
+            "max_trace_length cannot be smaller than {}", MIN_TRACE_LENGTH);
+
+        return ExecutionOptions { max_cycles, max_trace_length, max_secret_inputs };
+    }
+
+    /// Returns execution options which do not limit execution in practice; these options are
+    /// used by `execute()` and `run()`.
+    pub fn unlimited() -> ExecutionOptions {
+        return ExecutionOptions {
+            max_cycles          : usize::MAX,
+            max_trace_length    : 1 << (usize::BITS - 1),
+            max_secret_inputs   : usize::MAX,
+        };
+    }
+
+    /// Returns the maximum number of steps the VM can execute.
+    pub fn max_cycles(&self) -> usize {
+        return self.max_cycles;
+    }
+
+    /// Returns the maximum length of the execution trace after it is padded to a power of 2.
+    pub fn max_trace_length(&self) -> usize {
+        return self.max_trace_length;
+    }
+
+    /// Returns the maximum number of values on both secret input tapes combined; this includes
+    /// values pushed onto the tapes by operation hints during execution.
+    pub fn max_secret_inputs(&self) -> usize {
+        return self.max_secret_inputs;
+    }
+}
+
+impl Default for ExecutionOptions {
+
+    fn default() -> ExecutionOptions {
+        return ExecutionOptions {
+            max_cycles          : DEFAULT_MAX_CYCLES,
+            max_trace_length    : DEFAULT_MAX_TRACE_LENGTH,
+            max_secret_inputs   : DEFAULT_MAX_SECRET_INPUTS,
+        };
+    }
+}
@@ -0,0 +1,128 @@
+use crate::MIN_TRACE_LENGTH;
+use sp_std::{ cmp, fmt, vec, vec::Vec };
+use super::OpCode;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Op codes of user operations fit into 7 bits.
+const NUM_OP_CODES: usize = 128;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Statistics of a program execution which determine the cost of proving it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecutionReport {
+    step_count      : usize,
+    alignment_noops : usize,
+    op_counts       : Vec<usize>,
+    max_ctx_depth   : usize,
+    max_loop_depth  : usize,
+    max_stack_depth : usize,
+}
+
+// EXECUTION REPORT IMPLEMENTATION
+// ================================================================================================
+impl ExecutionReport {
+
+    pub(super) fn new() -> ExecutionReport {
+        return ExecutionReport {
+            step_count      : 0,
+            alignment_noops : 0,
+            op_counts       : vec![0; NUM_OP_CODES],
+            max_ctx_depth   : 0,
+            max_loop_depth  : 0,
+            max_stack_depth : 0,
+        };
+    }
+
+    /// Returns the number of steps executed by the VM before the trace is padded.
+    pub fn step_count(&self) -> usize {
+        return self.step_count;
+    }
+
+    /// Returns the length of the execution trace after it is padded to a power of 2.
+    pub fn trace_length(&self) -> usize {
+        return cmp::max(MIN_TRACE_LENGTH, (self.step_count + 1).next_power_of_two());
+    }
+
+    /// Returns the number of NOOPs inserted by the VM between blocks to keep operations aligned
+    /// on cycle boundaries; NOOPs inserted by the compiler into span blocks are counted as span
+    /// operations.
+    pub fn alignment_noops(&self) -> usize {
+        return self.alignment_noops;
+    }
+
+    /// Returns the number of times operations with the specified op code were executed in span
+    /// blocks.
+    pub fn op_count(&self, op_code: OpCode) -> usize {
+        return self.op_counts[op_code as usize];
+    }
+
+    /// Returns the number of executions of every operation executed at least once, ordered by
+    /// op code.
+    pub fn op_counts(&self) -> Vec<(OpCode, usize)> {
+        return self.op_counts.iter().enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(code, &count)| (OpCode::from_u8(code as u8).unwrap(), count))
+            .collect();
+    }
+
+    /// Returns the number of rounds of Rescue hash function computed by RESCR operations.
+    pub fn rescr_rounds(&self) -> usize {
+        return self.op_count(OpCode::RescR);
+    }
+
+    /// Returns the maximum depth of the context stack; the root block of the program does not
+    /// count towards the depth, same as for the depth returned by `execute()`.
+    pub fn max_ctx_depth(&self) -> usize {
+        return self.max_ctx_depth;
+    }
+
+    pub fn max_loop_depth(&self) -> usize {
+        return self.max_loop_depth;
+    }
+
+    /// Returns the maximum depth of the user stack, which is the number of stack registers in
+    /// the execution trace.
+    pub fn max_stack_depth(&self) -> usize {
+        return self.max_stack_depth;
+    }
+
+    // RECORDING
+    // --------------------------------------------------------------------------------------------
+
+    pub(super) fn record_op(&mut self, op_code: OpCode) {
+        self.op_counts[op_code as usize] += 1;
+    }
+
+    pub(super) fn record_alignment_noop(&mut self) {
+        self.alignment_noops += 1;
+    }
+
+    /// Records depths of context and loop stacks; `ctx_depth` includes the root block.
+    pub(super) fn record_depths(&mut self, ctx_depth: usize, loop_depth: usize) {
+        self.max_ctx_depth = cmp::max(self.max_ctx_depth, ctx_depth - 1);
+        self.max_loop_depth = cmp::max(self.max_loop_depth, loop_depth);
+    }
+
+    pub(super) fn finalize(&mut self, step_count: usize, max_stack_depth: usize) {
+        self.step_count = step_count;
+        self.max_stack_depth = max_stack_depth;
+    }
+}
+
+impl fmt::Display for ExecutionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "steps:                {} ({} after padding)", self.step_count, self.trace_length())?;
+        writeln!(f, "alignment noops:      {}", self.alignment_noops)?;
+        writeln!(f, "max depths:           {} context, {} loop, {} stack",
+            self.max_ctx_depth, self.max_loop_depth, self.max_stack_depth)?;
+        write!(f, "operations:")?;
+        for (op_code, count) in self.op_counts() {
+            write!(f, "\n  {}: {}", op_code, count)?;
+        }
+        return Ok(());
+    }
+}
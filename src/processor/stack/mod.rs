@@ -4,6 +4,7 @@ use crate::{
     ProgramInputs, OpCode, OpHint,
     HASH_STATE_WIDTH, MIN_STACK_DEPTH, MAX_STACK_DEPTH,
 };
+use super::{ ExecutionError, ExecutionErrorKind, ExecutionOptions };
 use sp_std::{vec, vec::Vec};
 
 #[cfg(test)]
@@ -18,6 +19,9 @@ pub struct Stack {
     max_depth   : usize,
     depth       : usize,
     step        : usize,
+    keep_trace  : bool,
+    dropped     : usize,
+    limits      : ExecutionOptions,
 }
 
 // STACK IMPLEMENTATION
@@ -26,8 +30,9 @@ impl Stack {
 
     /// Returns a new Stack with enough memory allocated for each register to hold trace lengths
     /// of `init_trace_length` steps. Register traces will be expanded dynamically if the number
-    /// of actual steps exceeds this initial setting.
-    pub fn new(inputs: &ProgramInputs, init_trace_length: usize) -> Stack {
+    /// of actual steps exceeds this initial setting, but never beyond the maximum trace length
+    /// set by `limits`.
+    pub fn new(inputs: &ProgramInputs, init_trace_length: usize, limits: &ExecutionOptions) -> Stack {
 
         // allocate space for register traces and initialize the first state with public inputs
         let public_inputs = inputs.get_public_inputs();
@@ -55,17 +60,40 @@ impl Stack {
             max_depth: public_inputs.len(),
             depth: public_inputs.len(),
             step: 0,
+            keep_trace: true,
+            dropped: 0,
+            limits: *limits,
         };
     }
 
-    /// Executes `opcode` against the current state of the stack.
-    pub fn execute(&mut self, op_code: OpCode, op_hint: OpHint) {
+    /// Returns a new Stack which keeps only the last two states of the stack instead of full
+    /// register traces; such a stack can be used to execute programs when the execution trace
+    /// is not needed.
+    pub fn without_trace(inputs: &ProgramInputs, limits: &ExecutionOptions) -> Stack {
+        let mut stack = Stack::new(inputs, 2, limits);
+        stack.keep_trace = false;
+        return stack;
+    }
+
+    /// Executes `opcode` against the current state of the stack; if the operation fails, the
+    /// returned error describes the failure together with the state of the stack before it.
+    pub fn execute(&mut self, op_code: OpCode, op_hint: OpHint) -> Result<(), ExecutionError> {
+
+        // make sure the next step stays within execution limits; the trace must have room for
+        // the state after the last step
+        let next_step = self.current_step() + 1;
+        if next_step > self.limits.max_cycles() {
+            return Err(self.build_error(ExecutionErrorKind::CycleLimitExceeded, self.step + 1, op_code, op_hint));
+        }
+        if next_step >= self.limits.max_trace_length() {
+            return Err(self.build_error(ExecutionErrorKind::TraceLengthExceeded, self.step + 1, op_code, op_hint));
+        }
 
         // increment step pointer and make sure there is enough memory allocated to hold the trace
         self.advance_step();
 
         // execute the appropriate action against the current state of the stack
-        match op_code {
+        let result = match op_code {
 
             OpCode::Begin       => self.op_noop(),
             OpCode::Noop        => self.op_noop(),
@@ -109,7 +137,9 @@ impl Stack {
             OpCode::BinAcc      => self.op_binacc(op_hint),
 
             OpCode::RescR       => self.op_rescr(),
-        }
+        };
+
+        return result.map_err(|kind| self.build_error(kind, self.step, op_code, op_hint));
     }
 
     /// Returns trace length of register traces in the decoder.
@@ -117,10 +147,27 @@ impl Stack {
         return self.registers[0].len();
     }
 
-    /// Returns value of the current step pointer.
-    #[cfg(test)]
+    /// Returns the number of steps executed so far (the absolute cycle of the current state);
+    /// when the trace is not kept, this differs from the row of the current state in register
+    /// traces, so all cycle-dependent logic must use this value.
     pub fn current_step(&self) -> usize {
-        return self.step;
+        return self.dropped + self.step;
+    }
+
+    /// Returns the maximum depth the stack has reached so far.
+    pub fn max_depth(&self) -> usize {
+        return self.max_depth;
+    }
+
+    /// Returns all values on the stack at the current step; the top of the stack is the first
+    /// value.
+    pub fn get_stack_state(&self) -> Vec<u128> {
+        return self.registers[..self.depth].iter().map(|register| register[self.step]).collect();
+    }
+
+    /// Returns the number of values remaining on secret tapes A and B.
+    pub fn tape_lengths(&self) -> (usize, usize) {
+        return (self.tape_a.len(), self.tape_b.len());
     }
 
     /// Returns the value at the top of the stack at the current step.
@@ -128,6 +175,22 @@ impl Stack {
         return self.registers[0][self.step];
     }
 
+    /// Returns an error if secret input tapes hold more values than allowed by execution
+    /// limits; the error is reported for the first step of the execution.
+    pub fn check_secret_inputs(&self) -> Result<(), ExecutionError> {
+        if self.tape_a.len() + self.tape_b.len() > self.limits.max_secret_inputs() {
+            return Err(self.build_error(ExecutionErrorKind::SecretInputLimitExceeded,
+                self.step + 1, OpCode::Noop, OpHint::None));
+        }
+        return Ok(());
+    }
+
+    /// Returns an error for a failure which happened while entering or leaving a program block
+    /// at the next step; the stack executes NOOPs at such steps.
+    pub fn flow_error(&self, kind: ExecutionErrorKind) -> ExecutionError {
+        return self.build_error(kind, self.step + 1, OpCode::Noop, OpHint::None);
+    }
+
     /// Populate all register traces with values for steps between the current step
     /// and the end of the trace.
     pub fn finalize_trace(&mut self) {
@@ -149,44 +212,49 @@ impl Stack {
 
     // FLOW CONTROL OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_noop(&mut self) {
+    fn op_noop(&mut self) -> Result<(), ExecutionErrorKind> {
         self.copy_state(0);
+        return Ok(());
     }
 
-    fn op_assert(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
+    fn op_assert(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(1)?;
         let value = self.registers[0][self.step - 1];
-        assert!(value == field::ONE, "ASSERT failed at step {}", self.step);
+        if value != field::ONE { return Err(ExecutionErrorKind::AssertFailed); }
         self.shift_left(1, 1);
+        return Ok(());
     }
 
-    fn op_asserteq(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_asserteq(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(2)?;
         let x = self.registers[0][self.step - 1];
         let y = self.registers[1][self.step - 1];
-        assert!(x == y, "ASSERTEQ failed at step {},x = {:?},y={:?}", self.step,x,y);
+        if x != y { return Err(ExecutionErrorKind::AssertEqFailed); }
         self.shift_left(2, 2);
+        return Ok(());
     }
 
     // INPUT OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_push(&mut self, hint: OpHint) {
-        self.shift_right(0, 1);
+    fn op_push(&mut self, hint: OpHint) -> Result<(), ExecutionErrorKind> {
+        self.shift_right(0, 1)?;
         let op_value = match hint {
             OpHint::PushValue(value) => value,
-            _ => panic!("invalid value for PUSH operation at step {}", self.step),
+            _ => return Err(ExecutionErrorKind::InvalidHint),
         };
         self.registers[0][self.step] = op_value;
+        return Ok(());
     }
 
-    fn op_read(&mut self, hint: OpHint) {
+    fn op_read(&mut self, hint: OpHint) -> Result<(), ExecutionErrorKind> {
         // process execution hint
         match hint {
             OpHint::EqStart => {
                 // if we are about to equality comparison sequence, push inverse of the difference
                 // between top two stack values onto secret tape A, if they are equal; otherwise
                 // push value 1
-                assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+                self.check_depth(2)?;
+                self.check_tape_growth(1)?;
                 let x = self.registers[0][self.step - 1];
                 let y = self.registers[1][self.step - 1];
                 if x == y {
@@ -196,26 +264,27 @@ impl Stack {
                     self.tape_a.push(field::inv(field::sub(x, y)));
                 }
             },
-            OpHint::None => {
-                assert!(self.tape_a.len() > 0, "1attempt to read from empty tape A at step {}", self.step);
-            },
-            _ => panic!("execution hint {:?} is not valid for READ operation", hint)
+            OpHint::None => (),
+            _ => return Err(ExecutionErrorKind::InvalidHint),
         }
 
-        self.shift_right(0, 1);
-        let value = self.tape_a.pop().unwrap();
+        let value = self.tape_a.pop().ok_or(ExecutionErrorKind::EmptyTapeA)?;
+        self.shift_right(0, 1)?;
         self.registers[0][self.step] = value;
+        return Ok(());
     }
 
-    fn op_read2(&mut self, hint: OpHint) {
+    fn op_read2(&mut self, hint: OpHint) -> Result<(), ExecutionErrorKind> {
         // process execution hint
         match hint {
             OpHint::PmpathStart(n) => {
-                assert!(self.depth >= 3, "stack underflow at step {}", self.step);
+                if !hint.is_valid() { return Err(ExecutionErrorKind::InvalidHint); }
+                self.check_depth(3)?;
 
                 let n = (n - 1) as usize;
-                assert!(self.tape_a.len() >= n, "too few items on tape A for pmpath macro");
-                assert!(self.tape_b.len() >= n, "too few items on tape B for pmpath macro");
+                if self.tape_a.len() < n { return Err(ExecutionErrorKind::EmptyTapeA); }
+                if self.tape_b.len() < n { return Err(ExecutionErrorKind::EmptyTapeB); }
+                self.check_tape_growth(n)?;
 
                 let idx = self.registers[2][self.step - 1];
 
@@ -231,78 +300,84 @@ impl Stack {
                     self.tape_a.push(v_a[i]);
                 }
             },
-            OpHint::None => {
-                assert!(self.tape_a.len() > 0, "2attempt to read from empty tape A at step {}", self.step);
-                assert!(self.tape_b.len() > 0, "3attempt to read from empty tape B at step {}", self.step);
-            },
-            _ => panic!("execution hint {:?} is not valid for READ2 operation", hint)
+            OpHint::None => (),
+            _ => return Err(ExecutionErrorKind::InvalidHint),
         }
 
-        self.shift_right(0, 2);
-        let value_a = self.tape_a.pop().unwrap();
-        let value_b = self.tape_b.pop().unwrap();
+        let value_a = self.tape_a.pop().ok_or(ExecutionErrorKind::EmptyTapeA)?;
+        let value_b = self.tape_b.pop().ok_or(ExecutionErrorKind::EmptyTapeB)?;
+        self.shift_right(0, 2)?;
         self.registers[0][self.step] = value_b;
         self.registers[1][self.step] = value_a;
+        return Ok(());
     }
 
     // STACK MANIPULATION OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_dup(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
-        self.shift_right(0, 1);
+    fn op_dup(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(1)?;
+        self.shift_right(0, 1)?;
         self.registers[0][self.step] = self.registers[0][self.step - 1];
+        return Ok(());
     }
 
-    fn op_dup2(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
-        self.shift_right(0, 2);
+    fn op_dup2(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(2)?;
+        self.shift_right(0, 2)?;
         self.registers[0][self.step] = self.registers[0][self.step - 1];
         self.registers[1][self.step] = self.registers[1][self.step - 1];
+        return Ok(());
     }
 
-    fn op_dup4(&mut self) {
-        assert!(self.depth >= 4, "stack underflow at step {}", self.step);
-        self.shift_right(0, 4);
+    fn op_dup4(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(4)?;
+        self.shift_right(0, 4)?;
         self.registers[0][self.step] = self.registers[0][self.step - 1];
         self.registers[1][self.step] = self.registers[1][self.step - 1];
         self.registers[2][self.step] = self.registers[2][self.step - 1];
         self.registers[3][self.step] = self.registers[3][self.step - 1];
+        return Ok(());
     }
 
-    fn op_pad2(&mut self) {
-        self.shift_right(0, 2);
+    fn op_pad2(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.shift_right(0, 2)?;
         self.registers[0][self.step] = field::ZERO;
         self.registers[1][self.step] = field::ZERO;
+        return Ok(());
     }
 
-    fn op_drop(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
+    fn op_drop(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(1)?;
         self.shift_left(1, 1);
+        return Ok(());
     }
 
-    fn op_drop4(&mut self) {
-        assert!(self.depth >= 4, "stack underflow at step {}", self.step);
+    fn op_drop4(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(4)?;
         self.shift_left(4, 4);
+        return Ok(());
     }
 
-    fn op_swap(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_swap(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(2)?;
         self.registers[0][self.step] = self.registers[1][self.step - 1];
         self.registers[1][self.step] = self.registers[0][self.step - 1];
         self.copy_state(2);
+        return Ok(());
     }
 
-    fn op_swap2(&mut self) {
-        assert!(self.depth >= 4, "stack underflow at step {}", self.step);
+    fn op_swap2(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(4)?;
         self.registers[0][self.step] = self.registers[2][self.step - 1];
         self.registers[1][self.step] = self.registers[3][self.step - 1];
         self.registers[2][self.step] = self.registers[0][self.step - 1];
         self.registers[3][self.step] = self.registers[1][self.step - 1];
         self.copy_state(4);
+        return Ok(());
     }
 
-    fn op_swap4(&mut self) {
-        assert!(self.depth >= 8, "stack underflow at step {}", self.step);
+    fn op_swap4(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(8)?;
         self.registers[0][self.step] = self.registers[4][self.step - 1];
         self.registers[1][self.step] = self.registers[5][self.step - 1];
         self.registers[2][self.step] = self.registers[6][self.step - 1];
@@ -312,19 +387,21 @@ impl Stack {
         self.registers[6][self.step] = self.registers[2][self.step - 1];
         self.registers[7][self.step] = self.registers[3][self.step - 1];
         self.copy_state(8);
+        return Ok(());
     }
 
-    fn op_roll4(&mut self) {
-        assert!(self.depth >= 4, "stack underflow at step {}", self.step);
+    fn op_roll4(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(4)?;
         self.registers[0][self.step] = self.registers[3][self.step - 1];
         self.registers[1][self.step] = self.registers[0][self.step - 1];
         self.registers[2][self.step] = self.registers[1][self.step - 1];
         self.registers[3][self.step] = self.registers[2][self.step - 1];
         self.copy_state(4);
+        return Ok(());
     }
 
-    fn op_roll8(&mut self) {
-        assert!(self.depth >= 8, "stack underflow at step {}", self.step);
+    fn op_roll8(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(8)?;
         self.registers[0][self.step] = self.registers[7][self.step - 1];
         self.registers[1][self.step] = self.registers[0][self.step - 1];
         self.registers[2][self.step] = self.registers[1][self.step - 1];
@@ -334,12 +411,13 @@ impl Stack {
         self.registers[6][self.step] = self.registers[5][self.step - 1];
         self.registers[7][self.step] = self.registers[6][self.step - 1];
         self.copy_state(8);
+        return Ok(());
     }
 
     // SELECTION OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_choose(&mut self) {
-        assert!(self.depth >= 3, "stack underflow at step {}", self.step);
+    fn op_choose(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(3)?;
         let condition = self.registers[2][self.step - 1];
         if condition == field::ONE {
             self.registers[0][self.step] = self.registers[0][self.step - 1];
@@ -348,13 +426,14 @@ impl Stack {
             self.registers[0][self.step] = self.registers[1][self.step - 1];
         }
         else {
-            assert!(false, "CHOOSE on a non-binary condition at step {}", self.step);
+            return Err(ExecutionErrorKind::NonBinaryCondition);
         }
         self.shift_left(3, 2);
+        return Ok(());
     }
 
-    fn op_choose2(&mut self) {
-        assert!(self.depth >= 6, "stack underflow at step {}", self.step);
+    fn op_choose2(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(6)?;
         let condition = self.registers[4][self.step - 1];
         if condition == field::ONE {
             self.registers[0][self.step] = self.registers[0][self.step - 1];
@@ -365,13 +444,14 @@ impl Stack {
             self.registers[1][self.step] = self.registers[3][self.step - 1];
         }
         else {
-            assert!(false, "CHOOSE2 on a non-binary condition at step {}", self.step);
+            return Err(ExecutionErrorKind::NonBinaryCondition);
         }
         self.shift_left(6, 4);
+        return Ok(());
     }
 
-    fn op_cswap2(&mut self) {
-        assert!(self.depth >= 6, "stack underflow at step {}", self.step);
+    fn op_cswap2(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(6)?;
         let condition = self.registers[4][self.step - 1];
         if condition == field::ZERO {
             self.registers[0][self.step] = self.registers[0][self.step - 1];
@@ -386,79 +466,85 @@ impl Stack {
             self.registers[3][self.step] = self.registers[1][self.step - 1];
         }
         else {
-            assert!(false, "CSWAP2 on a non-binary condition at step {}", self.step);
+            return Err(ExecutionErrorKind::NonBinaryCondition);
         }
         self.shift_left(6, 2);
+        return Ok(());
     }
 
     // ARITHMETIC AND BOOLEAN OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_add(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_add(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(2)?;
         let x = self.registers[0][self.step - 1];
         let y = self.registers[1][self.step - 1];
         self.registers[0][self.step] = field::add(x, y);
         self.shift_left(2, 1);
+        return Ok(());
     }
 
-    fn op_mul(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_mul(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(2)?;
         let x = self.registers[0][self.step - 1];
         let y = self.registers[1][self.step - 1];
         self.registers[0][self.step] = field::mul(x, y);
         self.shift_left(2, 1);
+        return Ok(());
     }
 
-    fn op_inv(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
+    fn op_inv(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(1)?;
         let x = self.registers[0][self.step - 1];
-        assert!(x != field::ZERO, "cannot compute INV of {} at step {}", field::ZERO, self.step);
+        if x == field::ZERO { return Err(ExecutionErrorKind::InverseOfZero); }
         self.registers[0][self.step] = field::inv(x);
         self.copy_state(1);
+        return Ok(());
     }
 
-    fn op_neg(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
+    fn op_neg(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(1)?;
         let x = self.registers[0][self.step - 1];
         self.registers[0][self.step] = field::neg(x);
         self.copy_state(1);
+        return Ok(());
     }
 
-    fn op_not(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
+    fn op_not(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(1)?;
         let x = self.registers[0][self.step - 1];
-        assert!(is_binary(x), "cannot compute NOT of a non-binary value at step {}", self.step);
+        if !is_binary(x) { return Err(ExecutionErrorKind::NonBinaryValue); }
         self.registers[0][self.step] = field::sub(field::ONE, x);
         self.copy_state(1);
+        return Ok(());
     }
 
-    fn op_and(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_and(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(2)?;
         let x = self.registers[0][self.step - 1];
         let y = self.registers[1][self.step - 1];
-        assert!(is_binary(x), "cannot compute AND for a non-binary value at step {}", self.step);
-        assert!(is_binary(y), "cannot compute AND for a non-binary value at step {}", self.step);
+        if !is_binary(x) || !is_binary(y) { return Err(ExecutionErrorKind::NonBinaryValue); }
 
         self.registers[0][self.step] = if x == field::ONE && y == field::ONE { field::ONE } else { field::ZERO };
         self.shift_left(2, 1);
+        return Ok(());
     }
 
-    fn op_or(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_or(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(2)?;
         let x = self.registers[0][self.step - 1];
         let y = self.registers[1][self.step - 1];
-        assert!(is_binary(x), "cannot compute OR for a non-binary value at step {}", self.step);
-        assert!(is_binary(y), "cannot compute OR for a non-binary value at step {}", self.step);
+        if !is_binary(x) || !is_binary(y) { return Err(ExecutionErrorKind::NonBinaryValue); }
 
         self.registers[0][self.step] = if x == field::ONE || y == field::ONE { field::ONE } else { field::ZERO };
         self.shift_left(2, 1);
+        return Ok(());
     }
 
 
     // COMPARISON OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_eq(&mut self) {
-        assert!(self.depth >= 3, "stack underflow at step {}", self.step);
+    fn op_eq(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(3)?;
         let aux = self.registers[0][self.step - 1];
         let x = self.registers[1][self.step - 1];
         let y = self.registers[2][self.step - 1];
@@ -466,19 +552,22 @@ impl Stack {
             self.registers[0][self.step] = field::ONE;
         } else {
             let diff = field::sub(x, y);
-            assert!(aux == field::inv(diff), "invalid AUX value for EQ operation at step {}", self.step);
+            if aux != field::inv(diff) { return Err(ExecutionErrorKind::InvalidEqAux); }
             self.registers[0][self.step] = field::ZERO;
         }
         self.shift_left(3, 2);
+        return Ok(());
     }
 
-    fn op_cmp(&mut self, hint: OpHint) {
+    fn op_cmp(&mut self, hint: OpHint) -> Result<(), ExecutionErrorKind> {
         // process execution hint
         match hint {
             OpHint::CmpStart(n) => {
                 // if we are about to start comparison sequence, push binary decompositions
                 // of a and b values onto the tapes
-                assert!(self.depth >= 10, "stack underflow at step {}", self.step);
+                if !hint.is_valid() { return Err(ExecutionErrorKind::InvalidHint); }
+                self.check_depth(10)?;
+                self.check_tape_growth(2 * n as usize)?;
                 let a_val = self.registers[8][self.step - 1];
                 let b_val = self.registers[9][self.step - 1];
                 for i in 0..n {
@@ -486,21 +575,14 @@ impl Stack {
                     self.tape_b.push((b_val >> i) & 1);
                 }
             },
-            OpHint::None => {
-                assert!(self.depth >= 8, "stack underflow at step {}", self.step);
-                assert!(self.tape_a.len() > 0, "4attempt to read from empty tape A at step {}", self.step);
-                assert!(self.tape_b.len() > 0, "5attempt to read from empty tape B at step {}", self.step);
-            },
-            _ => panic!("execution hint {:?} is not valid for CMP operation", hint)
+            OpHint::None => self.check_depth(8)?,
+            _ => return Err(ExecutionErrorKind::InvalidHint),
         }
 
         // get next bits of a and b values from the tapes
-        let a_bit = self.tape_a.pop().unwrap();
-        assert!(a_bit == field::ZERO || a_bit == field::ONE,
-            "expected binary input at step {} but received: {}", self.step, a_bit);
-        let b_bit = self.tape_b.pop().unwrap();
-        assert!(b_bit == field::ZERO || b_bit == field::ONE,
-            "expected binary input at step {} but received: {}", self.step, b_bit);
+        let a_bit = self.tape_a.pop().ok_or(ExecutionErrorKind::EmptyTapeA)?;
+        let b_bit = self.tape_b.pop().ok_or(ExecutionErrorKind::EmptyTapeB)?;
+        if !is_binary(a_bit) || !is_binary(b_bit) { return Err(ExecutionErrorKind::NonBinaryInput); }
 
         // determine which bit is greater
         let bit_gt = field::mul(a_bit, field::sub(field::ONE, b_bit));
@@ -508,8 +590,7 @@ impl Stack {
 
         // compute current power of 2 for binary decomposition
         let power_of_two = self.registers[0][self.step - 1];
-        assert!(power_of_two.is_power_of_two(),
-            "expected top of the stack at step {} to be a power of 2, but received {}", self.step, power_of_two);
+        if !power_of_two.is_power_of_two() { return Err(ExecutionErrorKind::NotPowerOfTwo); }
         let next_power_of_two = if power_of_two == 1 {
             field::div(power_of_two, 2)
         }
@@ -533,38 +614,35 @@ impl Stack {
         self.registers[7][self.step] = field::add(self.registers[7][self.step - 1], field::mul(a_bit, power_of_two));
 
         self.copy_state(8);
+        return Ok(());
     }
 
-    fn op_binacc(&mut self, hint: OpHint) {
+    fn op_binacc(&mut self, hint: OpHint) -> Result<(), ExecutionErrorKind> {
         // process execution hint
         match hint {
             OpHint::RcStart(n) => {
                 // if we are about to start range check sequence, push binary decompositions
                 // of the value onto tape A
-                assert!(self.depth >= 5, "stack underflow at step {}", self.step);
+                if !hint.is_valid() { return Err(ExecutionErrorKind::InvalidHint); }
+                self.check_depth(5)?;
+                self.check_tape_growth(n as usize)?;
                 let val = self.registers[4][self.step - 1];
                 for i in 0..n {
                     // most significant bit is pushed first
                     self.tape_a.push((val >> (n - i - 1)) & 1);
                 }
             },
-            OpHint::None => {
-                assert!(self.depth >= 4, "stack underflow at step {}", self.step);
-                assert!(self.tape_a.len() > 0, "attempt to read from empty tape A at step {}", self.step);
-            },
-            _ => panic!("execution hint {:?} is not valid for BINACC operation", hint)
+            OpHint::None => self.check_depth(4)?,
+            _ => return Err(ExecutionErrorKind::InvalidHint),
         }
 
         // get the next bit of the value from tape A
-        let bit = self.tape_a.pop().unwrap();
-        assert!(bit == field::ZERO || bit == field::ONE,
-            "expected binary input at step {} but received: {}", self.step, bit);
+        let bit = self.tape_a.pop().ok_or(ExecutionErrorKind::EmptyTapeA)?;
+        if !is_binary(bit) { return Err(ExecutionErrorKind::NonBinaryInput); }
 
         // compute current power of 2 for binary decomposition
         let power_of_two = self.registers[2][self.step - 1];
-        assert!(power_of_two.is_power_of_two(),
-            "expected 3rd value from the top of the stack at step {} to be a power of 2, but received {}",
-            self.step, power_of_two);
+        if !power_of_two.is_power_of_two() { return Err(ExecutionErrorKind::NotPowerOfTwo); }
         let next_power_of_two = field::mul(power_of_two, 2);
 
         let acc = self.registers[3][self.step - 1];
@@ -576,12 +654,13 @@ impl Stack {
         self.registers[3][self.step] = field::add(acc, field::mul(bit, power_of_two));
 
         self.copy_state(4);
+        return Ok(());
     }
 
     // CRYPTOGRAPHIC OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_rescr(&mut self) {
-        assert!(self.depth >= HASH_STATE_WIDTH, "stack underflow at step {}", self.step);
+    fn op_rescr(&mut self) -> Result<(), ExecutionErrorKind> {
+        self.check_depth(HASH_STATE_WIDTH)?;
         let mut state = [
             self.registers[0][self.step - 1],
             self.registers[1][self.step - 1],
@@ -591,7 +670,8 @@ impl Stack {
             self.registers[5][self.step - 1],
         ];
 
-        hasher::apply_round(&mut state, self.step - 1);
+        // round constants depend on the cycle at which the round starts
+        hasher::apply_round(&mut state, self.current_step() - 1);
 
         self.registers[0][self.step] = state[0];
         self.registers[1][self.step] = state[1];
@@ -601,11 +681,37 @@ impl Stack {
         self.registers[5][self.step] = state[5];
 
         self.copy_state(HASH_STATE_WIDTH);
+        return Ok(());
     }
 
     // HELPER METHODS
     // --------------------------------------------------------------------------------------------
 
+    fn check_depth(&self, min_depth: usize) -> Result<(), ExecutionErrorKind> {
+        if self.depth < min_depth {
+            return Err(ExecutionErrorKind::StackUnderflow);
+        }
+        return Ok(());
+    }
+
+    /// Returns an error if pushing the specified number of values onto secret input tapes
+    /// would make them hold more values than allowed by execution limits.
+    fn check_tape_growth(&self, num_values: usize) -> Result<(), ExecutionErrorKind> {
+        if self.tape_a.len() + self.tape_b.len() + num_values > self.limits.max_secret_inputs() {
+            return Err(ExecutionErrorKind::SecretInputLimitExceeded);
+        }
+        return Ok(());
+    }
+
+    /// Builds an error for a failure at the specified row of register traces; the error is
+    /// reported for the absolute cycle of that row, and includes the top of the stack at the
+    /// preceding row.
+    fn build_error(&self, kind: ExecutionErrorKind, row: usize, op_code: OpCode, op_hint: OpHint) -> ExecutionError {
+        let stack_top = self.registers.iter().take(MIN_STACK_DEPTH).map(|register| register[row - 1]).collect();
+        let step = self.current_step() - self.step + row;
+        return ExecutionError::new(kind, step, op_code, op_hint, stack_top);
+    }
+
     fn copy_state(&mut self, start: usize) {
         for i in start..self.depth {
             self.registers[i][self.step] = self.registers[i][self.step - 1];
@@ -613,7 +719,7 @@ impl Stack {
     }
 
     fn shift_left(&mut self, start: usize, pos_count: usize) {
-        assert!(self.depth >= pos_count, "stack underflow at step {}", self.step);
+        debug_assert!(self.depth >= pos_count, "stack underflow at step {}", self.current_step());
         
         // shift all values by pos_count to the left
         for i in start..self.depth {
@@ -629,10 +735,12 @@ impl Stack {
         self.depth -= pos_count;
     }
 
-    fn shift_right(&mut self, start: usize, pos_count: usize) {
-        
+    fn shift_right(&mut self, start: usize, pos_count: usize) -> Result<(), ExecutionErrorKind> {
+
+        if self.depth + pos_count > MAX_STACK_DEPTH {
+            return Err(ExecutionErrorKind::StackOverflow);
+        }
         self.depth += pos_count;
-        assert!(self.depth <= MAX_STACK_DEPTH, "stack overflow at step {}", self.step);
 
         if self.depth > self.max_depth {
             self.max_depth += pos_count;
@@ -644,6 +752,7 @@ impl Stack {
         for i in start..(self.depth - pos_count) {
             self.registers[i + pos_count][self.step] = self.registers[i][self.step - 1];
         }
+        return Ok(());
     }
 
     /// Extends the stack by the specified number of registers.
@@ -654,6 +763,17 @@ impl Stack {
     }
 
     fn advance_step(&mut self) {
+        // if the trace is not kept, move the current state into the first row and clear the
+        // second row so that the next state is written into it
+        if !self.keep_trace && self.step == 1 {
+            for register in self.registers.iter_mut() {
+                register[0] = register[1];
+                register[1] = field::ZERO;
+            }
+            self.dropped += 1;
+            return;
+        }
+
         // increment step by 1
         self.step += 1;
 
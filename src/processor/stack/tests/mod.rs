@@ -1,6 +1,6 @@
 use crate::math::{ field };
 use crate::utils::{ hasher };
-use super::{ Stack, super::ProgramInputs, OpHint, OpCode };
+use super::{ Stack, super::ProgramInputs, OpHint, OpCode, ExecutionErrorKind, ExecutionOptions };
 use crate::{ HASH_STATE_WIDTH };
 
 mod comparisons;
@@ -14,7 +14,7 @@ const TRACE_LENGTH: usize = 16;
 #[test]
 fn noop() {
     let mut stack = init_stack(&[1, 2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Noop, OpHint::None);
+    stack.execute(OpCode::Noop, OpHint::None).unwrap();
     assert_eq!(vec![1, 2, 3, 4, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(4, stack.depth);
@@ -24,7 +24,7 @@ fn noop() {
 #[test]
 fn assert() {
     let mut stack = init_stack(&[1, 2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Assert, OpHint::None);
+    stack.execute(OpCode::Assert, OpHint::None).unwrap();
     assert_eq!(vec![2, 3, 4, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(3, stack.depth);
@@ -32,16 +32,17 @@ fn assert() {
 }
 
 #[test]
-#[should_panic(expected = "ASSERT failed at step 1")]
 fn assert_fail() {
     let mut stack = init_stack(&[2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Assert, OpHint::None);
+    let err = stack.execute(OpCode::Assert, OpHint::None).err().unwrap();
+    assert_eq!(ExecutionErrorKind::AssertFailed, err.kind());
+    assert_eq!(1, err.step());
 }
 
 #[test]
 fn asserteq() {
     let mut stack = init_stack(&[1, 1, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::AssertEq, OpHint::None);
+    stack.execute(OpCode::AssertEq, OpHint::None).unwrap();
     assert_eq!(vec![3, 4, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(2, stack.depth);
@@ -49,10 +50,11 @@ fn asserteq() {
 }
 
 #[test]
-#[should_panic(expected = "ASSERTEQ failed at step 1")]
 fn asserteq_fail() {
     let mut stack = init_stack(&[2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::AssertEq, OpHint::None);
+    let err = stack.execute(OpCode::AssertEq, OpHint::None).err().unwrap();
+    assert_eq!(ExecutionErrorKind::AssertEqFailed, err.kind());
+    assert_eq!(1, err.step());
 }
 
 // INPUT OPERATIONS
@@ -61,7 +63,7 @@ fn asserteq_fail() {
 #[test]
 fn push() {
     let mut stack = init_stack(&[], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Push, OpHint::PushValue(3));
+    stack.execute(OpCode::Push, OpHint::PushValue(3)).unwrap();
     assert_eq!(vec![3, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(1, stack.depth);
@@ -72,13 +74,13 @@ fn push() {
 fn read() {
     let mut stack = init_stack(&[1], &[2, 3], &[], TRACE_LENGTH);
 
-    stack.execute(OpCode::Read, OpHint::None);
+    stack.execute(OpCode::Read, OpHint::None).unwrap();
     assert_eq!(vec![2, 1, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(2, stack.depth);
     assert_eq!(2, stack.max_depth);
 
-    stack.execute(OpCode::Read, OpHint::None);
+    stack.execute(OpCode::Read, OpHint::None).unwrap();
     assert_eq!(vec![3, 2, 1, 0, 0, 0, 0, 0], get_stack_state(&stack, 2));
 
     assert_eq!(3, stack.depth);
@@ -89,26 +91,85 @@ fn read() {
 fn read2() {
     let mut stack = init_stack(&[1], &[2, 4], &[3, 5], TRACE_LENGTH);
 
-    stack.execute(OpCode::Read2, OpHint::None);
+    stack.execute(OpCode::Read2, OpHint::None).unwrap();
     assert_eq!(vec![3, 2, 1, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(3, stack.depth);
     assert_eq!(3, stack.max_depth);
 
-    stack.execute(OpCode::Read2, OpHint::None);
+    stack.execute(OpCode::Read2, OpHint::None).unwrap();
     assert_eq!(vec![5, 4, 3, 2, 1, 0, 0, 0], get_stack_state(&stack, 2));
 
     assert_eq!(5, stack.depth);
     assert_eq!(5, stack.max_depth);
 }
 
+#[test]
+fn read_fail() {
+    // tape A is empty
+    let mut stack = init_stack(&[1], &[], &[], TRACE_LENGTH);
+    let err = stack.execute(OpCode::Read, OpHint::None).err().unwrap();
+    assert_eq!(ExecutionErrorKind::EmptyTapeA, err.kind());
+    assert_eq!(&[1, 0, 0, 0, 0, 0, 0, 0], err.stack_top());
+
+    // tape B is empty
+    let mut stack = init_stack(&[1], &[2], &[], TRACE_LENGTH);
+    let err = stack.execute(OpCode::Read2, OpHint::None).err().unwrap();
+    assert_eq!(ExecutionErrorKind::EmptyTapeB, err.kind());
+
+    // PUSH without a value
+    let mut stack = init_stack(&[1], &[], &[], TRACE_LENGTH);
+    let err = stack.execute(OpCode::Push, OpHint::None).err().unwrap();
+    assert_eq!(ExecutionErrorKind::InvalidHint, err.kind());
+}
+
+#[test]
+fn invalid_hint_values() {
+    // Merkle tree depth must leave room for at least one leaf index bit, and fit into 128 bits
+    for &n in [0, 1, 129, u32::MAX].iter() {
+        let mut stack = init_stack(&[1, 2, 3], &[4, 5], &[6, 7], TRACE_LENGTH);
+        let err = stack.execute(OpCode::Read2, OpHint::PmpathStart(n)).err().unwrap();
+        assert_eq!(ExecutionErrorKind::InvalidHint, err.kind());
+    }
+
+    // comparisons and range checks work with 4 to 128 bits; hints are checked before anything
+    // is pushed onto the tapes
+    for &n in [0, 3, 129, u32::MAX].iter() {
+        let mut stack = init_stack(&[0, 1, 0, 0, 0, 0, 0, 0], &[], &[], TRACE_LENGTH);
+        let err = stack.execute(OpCode::Cmp, OpHint::CmpStart(n)).err().unwrap();
+        assert_eq!(ExecutionErrorKind::InvalidHint, err.kind());
+        assert_eq!((0, 0), stack.tape_lengths());
+
+        let mut stack = init_stack(&[0, 0, 1, 0, 5], &[], &[], TRACE_LENGTH);
+        let err = stack.execute(OpCode::BinAcc, OpHint::RcStart(n)).err().unwrap();
+        assert_eq!(ExecutionErrorKind::InvalidHint, err.kind());
+        assert_eq!((0, 0), stack.tape_lengths());
+    }
+}
+
 // STACK MANIPULATION OPERATIONS
 // ================================================================================================
 
+#[test]
+fn stack_underflow_and_overflow() {
+    let mut stack = init_stack(&[1, 2, 3], &[], &[], TRACE_LENGTH);
+    let err = stack.execute(OpCode::Swap2, OpHint::None).err().unwrap();
+    assert_eq!(ExecutionErrorKind::StackUnderflow, err.kind());
+    assert_eq!(OpCode::Swap2, err.op_code());
+    assert_eq!(&[1, 2, 3, 0, 0, 0, 0, 0], err.stack_top());
+
+    let mut stack = init_stack(&[1, 2, 3], &[], &[], 64);
+    let mut result = Ok(());
+    while result.is_ok() {
+        result = stack.execute(OpCode::Pad2, OpHint::None);
+    }
+    assert_eq!(ExecutionErrorKind::StackOverflow, result.err().unwrap().kind());
+}
+
 #[test]
 fn dup() {
     let mut stack = init_stack(&[1, 2], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Dup, OpHint::None);
+    stack.execute(OpCode::Dup, OpHint::None).unwrap();
     assert_eq!(vec![1, 1, 2, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(3, stack.depth);
@@ -118,7 +179,7 @@ fn dup() {
 #[test]
 fn dup2() {
     let mut stack = init_stack(&[1, 2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Dup2, OpHint::None);
+    stack.execute(OpCode::Dup2, OpHint::None).unwrap();
     assert_eq!(vec![1, 2, 1, 2, 3, 4, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(6, stack.depth);
@@ -128,7 +189,7 @@ fn dup2() {
 #[test]
 fn dup4() {
     let mut stack = init_stack(&[1, 2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Dup4, OpHint::None);
+    stack.execute(OpCode::Dup4, OpHint::None).unwrap();
     assert_eq!(vec![1, 2, 3, 4, 1, 2, 3, 4], get_stack_state(&stack, 1));
 
     assert_eq!(8, stack.depth);
@@ -138,7 +199,7 @@ fn dup4() {
 #[test]
 fn pad2() {
     let mut stack = init_stack(&[1, 2], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Pad2, OpHint::None);
+    stack.execute(OpCode::Pad2, OpHint::None).unwrap();
     assert_eq!(vec![0, 0, 1, 2, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(4, stack.depth);
@@ -148,7 +209,7 @@ fn pad2() {
 #[test]
 fn drop() {
     let mut stack = init_stack(&[1, 2], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Drop, OpHint::None);
+    stack.execute(OpCode::Drop, OpHint::None).unwrap();
     assert_eq!(vec![2, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(1, stack.depth);
@@ -158,7 +219,7 @@ fn drop() {
 #[test]
 fn drop4() {
     let mut stack = init_stack(&[1, 2, 3, 4, 5], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Drop4, OpHint::None);
+    stack.execute(OpCode::Drop4, OpHint::None).unwrap();
     assert_eq!(vec![5, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(1, stack.depth);
@@ -168,7 +229,7 @@ fn drop4() {
 #[test]
 fn swap() {
     let mut stack = init_stack(&[1, 2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Swap, OpHint::None);
+    stack.execute(OpCode::Swap, OpHint::None).unwrap();
     assert_eq!(vec![2, 1, 3, 4, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(4, stack.depth);
@@ -178,7 +239,7 @@ fn swap() {
 #[test]
 fn swap2() {
     let mut stack = init_stack(&[1, 2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Swap2, OpHint::None);
+    stack.execute(OpCode::Swap2, OpHint::None).unwrap();
     assert_eq!(vec![3, 4, 1, 2, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(4, stack.depth);
@@ -188,7 +249,7 @@ fn swap2() {
 #[test]
 fn swap4() {
     let mut stack = init_stack(&[1, 2, 3, 4, 5, 6, 7, 8], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Swap4, OpHint::None);
+    stack.execute(OpCode::Swap4, OpHint::None).unwrap();
     assert_eq!(vec![5, 6, 7, 8, 1, 2, 3, 4], get_stack_state(&stack, 1));
 
     assert_eq!(8, stack.depth);
@@ -198,7 +259,7 @@ fn swap4() {
 #[test]
 fn roll4() {
     let mut stack = init_stack(&[1, 2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Roll4, OpHint::None);
+    stack.execute(OpCode::Roll4, OpHint::None).unwrap();
     assert_eq!(vec![4, 1, 2, 3, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(4, stack.depth);
@@ -208,7 +269,7 @@ fn roll4() {
 #[test]
 fn roll8() {
     let mut stack = init_stack(&[1, 2, 3, 4, 5, 6, 7, 8], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Roll8, OpHint::None);
+    stack.execute(OpCode::Roll8, OpHint::None).unwrap();
     assert_eq!(vec![8, 1, 2, 3, 4, 5, 6, 7], get_stack_state(&stack, 1));
 
     assert_eq!(8, stack.depth);
@@ -221,7 +282,7 @@ fn roll8() {
 #[test]
 fn add() {
     let mut stack = init_stack(&[1, 2], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Add, OpHint::None);
+    stack.execute(OpCode::Add, OpHint::None).unwrap();
     assert_eq!(vec![3, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(1, stack.depth);
@@ -231,7 +292,7 @@ fn add() {
 #[test]
 fn mul() {
     let mut stack = init_stack(&[2, 3], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Mul, OpHint::None);
+    stack.execute(OpCode::Mul, OpHint::None).unwrap();
     assert_eq!(vec![6, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(1, stack.depth);
@@ -241,7 +302,7 @@ fn mul() {
 #[test]
 fn inv() {
     let mut stack = init_stack(&[2, 3], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Inv, OpHint::None);
+    stack.execute(OpCode::Inv, OpHint::None).unwrap();
     assert_eq!(vec![field::inv(2), 3, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(2, stack.depth);
@@ -249,16 +310,17 @@ fn inv() {
 }
 
 #[test]
-#[should_panic(expected = "cannot compute INV of 0 at step 1")]
 fn inv_zero() {
     let mut stack = init_stack(&[0], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Inv, OpHint::None);
+    let err = stack.execute(OpCode::Inv, OpHint::None).err().unwrap();
+    assert_eq!(ExecutionErrorKind::InverseOfZero, err.kind());
+    assert_eq!(1, err.step());
 }
 
 #[test]
 fn neg() {
     let mut stack = init_stack(&[2, 3], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Neg, OpHint::None);
+    stack.execute(OpCode::Neg, OpHint::None).unwrap();
     assert_eq!(vec![field::neg(2), 3, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(2, stack.depth);
@@ -268,13 +330,13 @@ fn neg() {
 #[test]
 fn not() {
     let mut stack = init_stack(&[1, 2], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Not, OpHint::None);
+    stack.execute(OpCode::Not, OpHint::None).unwrap();
     assert_eq!(vec![0, 2, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(2, stack.depth);
     assert_eq!(2, stack.max_depth);
 
-    stack.execute(OpCode::Not, OpHint::None);
+    stack.execute(OpCode::Not, OpHint::None).unwrap();
     assert_eq!(vec![1, 2, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 2));
 
     assert_eq!(2, stack.depth);
@@ -282,22 +344,23 @@ fn not() {
 }
 
 #[test]
-#[should_panic(expected = "cannot compute NOT of a non-binary value at step 1")]
 fn not_fail() {
     let mut stack = init_stack(&[2, 3], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Not, OpHint::None);
+    let err = stack.execute(OpCode::Not, OpHint::None).err().unwrap();
+    assert_eq!(ExecutionErrorKind::NonBinaryValue, err.kind());
+    assert_eq!(1, err.step());
 }
 
 #[test]
 fn and() {
     let mut stack = init_stack(&[1, 1, 0], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::And, OpHint::None);
+    stack.execute(OpCode::And, OpHint::None).unwrap();
     assert_eq!(vec![1, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(2, stack.depth);
     assert_eq!(3, stack.max_depth);
 
-    stack.execute(OpCode::And, OpHint::None);
+    stack.execute(OpCode::And, OpHint::None).unwrap();
     assert_eq!(vec![0, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 2));
 
     assert_eq!(1, stack.depth);
@@ -305,22 +368,23 @@ fn and() {
 }
 
 #[test]
-#[should_panic(expected = "cannot compute AND for a non-binary value at step 1")]
 fn and_fail() {
     let mut stack = init_stack(&[1, 3], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::And, OpHint::None);
+    let err = stack.execute(OpCode::And, OpHint::None).err().unwrap();
+    assert_eq!(ExecutionErrorKind::NonBinaryValue, err.kind());
+    assert_eq!(1, err.step());
 }
 
 #[test]
 fn or() {
     let mut stack = init_stack(&[0, 0, 1], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Or, OpHint::None);
+    stack.execute(OpCode::Or, OpHint::None).unwrap();
     assert_eq!(vec![0, 1, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(2, stack.depth);
     assert_eq!(3, stack.max_depth);
 
-    stack.execute(OpCode::Or, OpHint::None);
+    stack.execute(OpCode::Or, OpHint::None).unwrap();
     assert_eq!(vec![1, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 2));
 
     assert_eq!(1, stack.depth);
@@ -328,10 +392,11 @@ fn or() {
 }
 
 #[test]
-#[should_panic(expected = "cannot compute OR for a non-binary value at step 1")]
 fn and_or() {
     let mut stack = init_stack(&[1, 3], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Or, OpHint::None);
+    let err = stack.execute(OpCode::Or, OpHint::None).err().unwrap();
+    assert_eq!(ExecutionErrorKind::NonBinaryValue, err.kind());
+    assert_eq!(1, err.step());
 }
 
 // CRYPTOGRAPHIC OPERATIONS
@@ -342,11 +407,11 @@ fn rescr() {
     let mut stack = init_stack(&[0, 0, 1, 2, 3, 4], &[], &[], TRACE_LENGTH);
     let mut expected = vec![0, 0, 1, 2, 3, 4, 0, 0];
 
-    stack.execute(OpCode::RescR, OpHint::None);
+    stack.execute(OpCode::RescR, OpHint::None).unwrap();
     hasher::apply_round(&mut expected[..HASH_STATE_WIDTH], 0);
     assert_eq!(expected, get_stack_state(&stack, 1));
 
-    stack.execute(OpCode::RescR, OpHint::None);
+    stack.execute(OpCode::RescR, OpHint::None).unwrap();
     hasher::apply_round(&mut expected[..HASH_STATE_WIDTH], 1);
     assert_eq!(expected, get_stack_state(&stack, 2));
 
@@ -359,7 +424,7 @@ fn rescr() {
 
 fn init_stack(public_inputs: &[u128], secret_inputs_a: &[u128], secret_inputs_b: &[u128], trace_length: usize) -> Stack {
     let inputs = ProgramInputs::new(public_inputs, secret_inputs_a, secret_inputs_b);
-    return Stack::new(&inputs, trace_length);
+    return Stack::new(&inputs, trace_length, &ExecutionOptions::default());
 }
 
 fn get_stack_state(stack: &Stack, step: usize) -> Vec<u128> {
@@ -0,0 +1,217 @@
+use sp_std::{ cmp, fmt, vec::Vec };
+use crate::{ processor::OpCode, MAX_STACK_DEPTH };
+use super::{ Program, ProgramBlock, Span };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Range of stack depths which are possible at a given point of a program.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DepthRange {
+    pub min     : usize,
+    pub max     : usize,
+}
+
+/// Result of static stack depth analysis of a program.
+///
+/// Instructions are numbered in the order in which they appear in the program (instructions of
+/// true branches precede instructions of false branches); instructions which the VM executes
+/// implicitly to exit loops are not numbered.
+#[derive(Clone, Debug)]
+pub struct StackAnalysis {
+    depths      : Vec<DepthRange>,
+    max_depth   : usize,
+    final_depth : DepthRange,
+    warnings    : Vec<StackWarning>,
+}
+
+/// Describes a program structure which does not cause stack errors by itself, but makes stack
+/// depth dependent on execution path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StackWarning {
+    /// Branches of the conditional block starting at the specified instruction leave the
+    /// stack at different depths.
+    UnbalancedSwitch { step: usize, true_depth: DepthRange, false_depth: DepthRange },
+}
+
+/// Describes the reason why a program was rejected by stack depth analysis.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StackError {
+    /// The instruction may be executed when the stack holds fewer items than it requires.
+    Underflow { step: usize, op_code: OpCode, depth: usize, min_depth: usize },
+    /// The instruction may grow the stack beyond the maximum stack depth.
+    Overflow { step: usize, op_code: OpCode, depth: usize, max_depth: usize },
+}
+
+// STACK ANALYSIS
+// ================================================================================================
+
+/// Computes the range of possible stack depths before every instruction of the program when
+/// the program is executed with `num_inputs` public inputs. Returns an error if any instruction
+/// could underflow the stack or grow it beyond MAX_STACK_DEPTH; loops can be executed any number
+/// of times, so a loop whose body does not preserve stack depth is always rejected.
+pub fn analyze_stack(program: &Program, num_inputs: usize) -> Result<StackAnalysis, StackError> {
+    let init_depth = DepthRange { min: num_inputs, max: num_inputs };
+    let mut analyzer = Analyzer { depths: Vec::new(), max_depth: num_inputs, warnings: Vec::new() };
+    let final_depth = analyzer.analyze_blocks(program.root().body(), init_depth)?;
+
+    return Ok(StackAnalysis {
+        depths      : analyzer.depths,
+        max_depth   : analyzer.max_depth,
+        final_depth : final_depth,
+        warnings    : analyzer.warnings,
+    });
+}
+
+impl StackAnalysis {
+
+    /// Returns the ranges of possible stack depths before every instruction.
+    pub fn depths(&self) -> &[DepthRange] {
+        return &self.depths;
+    }
+
+    /// Returns the greatest stack depth the program may reach.
+    pub fn max_depth(&self) -> usize {
+        return self.max_depth;
+    }
+
+    /// Returns the range of possible stack depths after the program is executed.
+    pub fn final_depth(&self) -> DepthRange {
+        return self.final_depth;
+    }
+
+    /// Returns warnings about the parts of the program which make stack depth path-dependent.
+    pub fn warnings(&self) -> &[StackWarning] {
+        return &self.warnings;
+    }
+}
+
+impl DepthRange {
+
+    /// Returns the smallest range which contains both ranges.
+    pub fn union(&self, other: &DepthRange) -> DepthRange {
+        return DepthRange {
+            min: cmp::min(self.min, other.min),
+            max: cmp::max(self.max, other.max),
+        };
+    }
+}
+
+// ANALYZER
+// ================================================================================================
+struct Analyzer {
+    depths      : Vec<DepthRange>,
+    max_depth   : usize,
+    warnings    : Vec<StackWarning>,
+}
+
+impl Analyzer {
+
+    fn analyze_blocks(&mut self, blocks: &[ProgramBlock], mut depth: DepthRange) -> Result<DepthRange, StackError> {
+        for block in blocks.iter() {
+            depth = match block {
+                ProgramBlock::Span(block) => self.analyze_span(block, depth, true)?,
+                ProgramBlock::Group(block) => self.analyze_blocks(block.body(), depth)?,
+                ProgramBlock::Switch(block) => {
+                    let step = self.depths.len();
+                    let true_depth = self.analyze_blocks(block.true_branch(), depth)?;
+                    let false_depth = self.analyze_blocks(block.false_branch(), depth)?;
+                    if true_depth != false_depth {
+                        self.warnings.push(StackWarning::UnbalancedSwitch { step, true_depth, false_depth });
+                    }
+                    true_depth.union(&false_depth)
+                },
+                ProgramBlock::Loop(block) => {
+                    // the body can be executed any number of times, so depths on entry must
+                    // include depths after every iteration; the body is re-analyzed until the
+                    // range stops growing, which ends with an error if the body changes depth
+                    let step = self.depths.len();
+                    let num_warnings = self.warnings.len();
+                    let mut entry_depth = depth;
+                    let mut exit_depth = self.analyze_blocks(block.body(), entry_depth)?;
+                    while entry_depth.union(&exit_depth) != entry_depth {
+                        entry_depth = entry_depth.union(&exit_depth);
+                        self.depths.truncate(step);
+                        self.warnings.truncate(num_warnings);
+                        exit_depth = self.analyze_blocks(block.body(), entry_depth)?;
+                    }
+
+                    // the loop is exited either before the body is executed, or after the body
+                    // leaves 0 at the top of the stack; in both cases, skip block is executed
+                    let mut skip_depth = entry_depth.union(&exit_depth);
+                    for skip_block in block.skip().iter() {
+                        if let ProgramBlock::Span(span) = skip_block {
+                            skip_depth = self.analyze_span(span, skip_depth, false)?;
+                        }
+                    }
+                    skip_depth
+                },
+            };
+        }
+        return Ok(depth);
+    }
+
+    /// Applies the effect of every operation in the span to the depth range; if `record` is
+    /// true, the depth range before each operation is recorded.
+    fn analyze_span(&mut self, span: &Span, mut depth: DepthRange, record: bool) -> Result<DepthRange, StackError> {
+        for i in 0..span.length() {
+            let (op_code, op_hint) = span.get_op(i);
+            let step = self.depths.len();
+
+            let min_depth = op_code.min_stack_depth(op_hint);
+            if depth.min < min_depth {
+                return Err(StackError::Underflow { step, op_code, depth: depth.min, min_depth });
+            }
+
+            if record {
+                self.depths.push(depth);
+            }
+
+            let shift = op_code.stack_shift();
+            depth = DepthRange {
+                min: (depth.min as isize + shift) as usize,
+                max: (depth.max as isize + shift) as usize,
+            };
+            if depth.max > MAX_STACK_DEPTH {
+                return Err(StackError::Overflow { step, op_code, depth: depth.max, max_depth: MAX_STACK_DEPTH });
+            }
+            self.max_depth = cmp::max(self.max_depth, depth.max);
+        }
+        return Ok(depth);
+    }
+}
+
+// COMMON TRAIT IMPLEMENTATIONS
+// ================================================================================================
+
+impl fmt::Display for DepthRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.min == self.max {
+            return write!(f, "{}", self.min);
+        }
+        return write!(f, "{}..{}", self.min, self.max);
+    }
+}
+
+impl fmt::Display for StackWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StackWarning::UnbalancedSwitch { step, true_depth, false_depth } =>
+                write!(f, "branches of the conditional block at instruction {} leave different stack depths: {} and {}",
+                    step, true_depth, false_depth),
+        }
+    }
+}
+
+impl fmt::Display for StackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StackError::Underflow { step, op_code, depth, min_depth } =>
+                write!(f, "stack underflow at instruction {} ({}): stack depth may be {}, but at least {} is required",
+                    step, op_code, depth, min_depth),
+            StackError::Overflow { step, op_code, depth, max_depth } =>
+                write!(f, "stack overflow at instruction {} ({}): stack depth may reach {}, but at most {} is allowed",
+                    step, op_code, depth, max_depth),
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use alloc::{ borrow::Cow, string::String };
+use crate::math::field;
+use super::AssemblyError;
+use sp_std::vec::Vec;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Name of the built-in constant which is equal to the modulus of the field.
+const MODULUS_NAME: &str = "MODULUS";
+
+// CONSTANT RESOLVER
+// ================================================================================================
+
+/// Removes `const.NAME=expression` declarations from the token stream, and replaces constant
+/// expressions in instruction parameters with their values. Returns the resulting token stream
+/// together with a map from each resulting token to its index in the original stream.
+///
+/// Constant names must consist of uppercase letters, digits, and underscores, and must be
+/// declared before program body begins; each constant can be used only after it is declared.
+pub fn resolve_constants<'a>(tokens: &[&'a str]) -> Result<(Vec<Cow<'a, str>>, Vec<usize>), AssemblyError> {
+    let mut constants: Vec<(&str, u128)> = Vec::new();
+    let mut result = Vec::with_capacity(tokens.len());
+    let mut steps = Vec::with_capacity(tokens.len());
+    let mut program_started = false;
+
+    for (i, &token) in tokens.iter().enumerate() {
+        let op: Vec<&str> = token.split(".").collect();
+        match op[0] {
+            "const" => {
+                if program_started {
+                    return Err(AssemblyError::misplaced_const(&op, i));
+                }
+                let (name, value) = parse_const(&op, &constants, i)?;
+                constants.push((name, value));
+            },
+            "begin" => {
+                program_started = true;
+                result.push(Cow::Borrowed(token));
+                steps.push(i);
+            },
+            // names of procedures and modules are not expressions
+            "proc" | "export" | "exec" | "use" => {
+                result.push(Cow::Borrowed(token));
+                steps.push(i);
+            },
+            _ => {
+                result.push(resolve_params(token, &op, &constants, i)?);
+                steps.push(i);
+            },
+        }
+    }
+
+    return Ok((result, steps));
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Parses `const.NAME=expression` declaration.
+fn parse_const<'a>(op: &[&'a str], constants: &[(&str, u128)], step: usize) -> Result<(&'a str, u128), AssemblyError> {
+    if op.len() == 1 {
+        return Err(AssemblyError::missing_param(op, step));
+    }
+    else if op.len() > 2 {
+        return Err(AssemblyError::extra_param(op, step));
+    }
+
+    let mut parts = op[1].splitn(2, "=");
+    let name = parts.next().unwrap_or("");
+    let expression = match parts.next() {
+        Some(expression) => expression,
+        None => return Err(AssemblyError::invalid_param_reason(op, step,
+            String::from("constant declaration must have the form const.NAME=value"))),
+    };
+
+    if !is_const_name(name) {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("constant name '{}' is invalid; names may contain only uppercase letters, digits, and underscores", name)));
+    }
+    if name == MODULUS_NAME || constants.iter().any(|&(n, _)| n == name) {
+        return Err(AssemblyError::duplicate_const(name, step));
+    }
+
+    let value = evaluate(expression, constants, op, step)?;
+    return Ok((name, value));
+}
+
+/// Replaces parameters of the instruction which are constant expressions with their values;
+/// parameters which are plain literals or keywords (e.g. `eq` in `assert.eq`) are left as is.
+fn resolve_params<'a>(token: &'a str, op: &[&str], constants: &[(&str, u128)], step: usize) -> Result<Cow<'a, str>, AssemblyError> {
+    if !op[1..].iter().any(|&param| is_expression(param)) {
+        return Ok(Cow::Borrowed(token));
+    }
+
+    let mut result = String::from(op[0]);
+    for &param in op[1..].iter() {
+        result.push('.');
+        if is_expression(param) {
+            result.push_str(&format!("{}", evaluate(param, constants, op, step)?));
+        }
+        else {
+            result.push_str(param);
+        }
+    }
+    return Ok(Cow::Owned(result));
+}
+
+/// Evaluates the expression and makes sure the result is a valid field element.
+fn evaluate(expression: &str, constants: &[(&str, u128)], op: &[&str], step: usize) -> Result<u128, AssemblyError> {
+    let mut parser = ExpressionParser { source: expression.as_bytes(), pos: 0, constants };
+    let value = match parser.parse() {
+        Ok(value) => value,
+        Err(ExpressionError::UndefinedConstant(name)) =>
+            return Err(AssemblyError::undefined_const(&name, op, step)),
+        Err(ExpressionError::Invalid(reason)) =>
+            return Err(AssemblyError::invalid_param_reason(op, step, reason)),
+    };
+
+    if value >= field::MODULUS {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("value of '{}' must be smaller than {}", expression, field::MODULUS)));
+    }
+    return Ok(value);
+}
+
+/// A parameter is a constant expression if it references a constant or contains an operator.
+fn is_expression(param: &str) -> bool {
+    return param.chars().any(|c| c.is_ascii_uppercase() || c == '_' || "+-*/^()".contains(c));
+}
+
+fn is_const_name(name: &str) -> bool {
+    return match name.chars().next() {
+        Some(c) => (c.is_ascii_uppercase() || c == '_')
+            && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'),
+        None => false,
+    };
+}
+
+// EXPRESSION PARSER
+// ================================================================================================
+
+enum ExpressionError {
+    UndefinedConstant(String),
+    Invalid(String),
+}
+
+/// Recursive descent parser for integer expressions with the following grammar:
+///   expr  := term (('+' | '-') term)*
+///   term  := power (('*' | '/') power)*
+///   power := atom ('^' power)?
+///   atom  := decimal | hexadecimal | NAME | '(' expr ')'
+/// All operations are performed on unsigned 128-bit integers; overflows are reported as errors.
+struct ExpressionParser<'a, 'b> {
+    source      : &'a [u8],
+    pos         : usize,
+    constants   : &'b [(&'b str, u128)],
+}
+
+impl <'a, 'b> ExpressionParser<'a, 'b> {
+
+    fn parse(&mut self) -> Result<u128, ExpressionError> {
+        let value = self.parse_expr()?;
+        if self.pos < self.source.len() {
+            return Err(self.unexpected());
+        }
+        return Ok(value);
+    }
+
+    fn parse_expr(&mut self) -> Result<u128, ExpressionError> {
+        let mut value = self.parse_term()?;
+        loop {
+            value = match self.peek() {
+                Some(b'+') => { self.pos += 1; checked(value.checked_add(self.parse_term()?), "addition overflow")? },
+                Some(b'-') => { self.pos += 1; checked(value.checked_sub(self.parse_term()?), "subtraction underflow")? },
+                _ => return Ok(value),
+            };
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<u128, ExpressionError> {
+        let mut value = self.parse_power()?;
+        loop {
+            value = match self.peek() {
+                Some(b'*') => { self.pos += 1; checked(value.checked_mul(self.parse_power()?), "multiplication overflow")? },
+                Some(b'/') => { self.pos += 1; checked(value.checked_div(self.parse_power()?), "division by zero")? },
+                _ => return Ok(value),
+            };
+        }
+    }
+
+    fn parse_power(&mut self) -> Result<u128, ExpressionError> {
+        let base = self.parse_atom()?;
+        if self.peek() != Some(b'^') {
+            return Ok(base);
+        }
+        self.pos += 1;
+        let exponent = self.parse_power()?;
+        if exponent > u32::MAX as u128 {
+            return Err(ExpressionError::Invalid(String::from("exponentiation overflow")));
+        }
+        return checked(base.checked_pow(exponent as u32), "exponentiation overflow");
+    }
+
+    fn parse_atom(&mut self) -> Result<u128, ExpressionError> {
+        match self.peek() {
+            Some(b'(') => {
+                self.pos += 1;
+                let value = self.parse_expr()?;
+                if self.peek() != Some(b')') {
+                    return Err(self.unexpected());
+                }
+                self.pos += 1;
+                return Ok(value);
+            },
+            Some(c) if c.is_ascii_digit() => {
+                let literal = self.read_while(|c| c.is_ascii_alphanumeric());
+                let parsed = if literal.starts_with("0x") {
+                    u128::from_str_radix(&literal[2..], 16)
+                }
+                else {
+                    u128::from_str_radix(literal, 10)
+                };
+                return match parsed {
+                    Ok(value) => Ok(value),
+                    Err(_) => Err(ExpressionError::Invalid(format!("value '{}' is invalid", literal))),
+                };
+            },
+            Some(c) if c.is_ascii_uppercase() || c == b'_' => {
+                let name = self.read_while(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == b'_');
+                if name == MODULUS_NAME {
+                    return Ok(field::MODULUS);
+                }
+                return match self.constants.iter().find(|&&(n, _)| n == name) {
+                    Some(&(_, value)) => Ok(value),
+                    None => Err(ExpressionError::UndefinedConstant(String::from(name))),
+                };
+            },
+            _ => return Err(self.unexpected()),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        return self.source.get(self.pos).cloned();
+    }
+
+    fn read_while<F: Fn(u8) -> bool>(&mut self, predicate: F) -> &'a str {
+        let start = self.pos;
+        while self.pos < self.source.len() && predicate(self.source[self.pos]) {
+            self.pos += 1;
+        }
+        // the source was created from a &str, and only ASCII bytes were consumed
+        return sp_std::str::from_utf8(&self.source[start..self.pos]).unwrap_or("");
+    }
+
+    fn unexpected(&self) -> ExpressionError {
+        return match self.source.get(self.pos) {
+            Some(&c) => ExpressionError::Invalid(format!("unexpected character '{}' in expression", c as char)),
+            None => ExpressionError::Invalid(String::from("unexpected end of expression")),
+        };
+    }
+}
+
+fn checked(value: Option<u128>, reason: &str) -> Result<u128, ExpressionError> {
+    return match value {
+        Some(value) => Ok(value),
+        None => Err(ExpressionError::Invalid(String::from(reason))),
+    };
+}
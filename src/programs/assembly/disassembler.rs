@@ -0,0 +1,292 @@
+use alloc::string::{ String, ToString };
+use sp_std::{ cmp, vec, vec::Vec };
+use super::{ Program, ProgramBlock, Span, OpCode, OpHint, HintMap, AssemblyError, BASE_CYCLE_LENGTH, parse_op_token };
+
+// CONSTANTS
+// ================================================================================================
+const INDENT: &str = "    ";
+
+/// Operations which the assembler adds at the start of the first span of a branch.
+pub const PROGRAM_PREFIX     : [OpCode; 1] = [OpCode::Begin];
+pub const TRUE_BRANCH_PREFIX : [OpCode; 1] = [OpCode::Assert];
+pub const FALSE_BRANCH_PREFIX: [OpCode; 2] = [OpCode::Not, OpCode::Assert];
+pub const LOOP_BODY_PREFIX   : [OpCode; 1] = [OpCode::Assert];
+
+/// Number of RESCR operations in a single hashing cycle.
+const HASH_ROUNDS: usize = 10;
+
+// DISASSEMBLER
+// ================================================================================================
+
+/// Converts a program into assembly source code; compiling the source code produces a program
+/// with the same hash. Sequences of operations produced by macro instructions (e.g. `hash.N`,
+/// `smpath.N`, or `gt.N`) are folded back into these instructions, and NOOPs which the assembler
+/// adds for alignment are omitted.
+///
+/// Returns an error if the program contains an operation which cannot be produced by the
+/// assembler (e.g. a standalone CMP operation).
+pub fn disassemble(program: &Program) -> Result<String, AssemblyError> {
+    let mut source = String::from("begin");
+    write_blocks(&mut source, program.root().body(), &PROGRAM_PREFIX, 1)?;
+    source.push_str("\nend");
+    return Ok(source);
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Appends assembly code for a sequence of blocks to the source; `prefix` specifies operations
+/// which the assembler adds at the start of the first block of this sequence.
+fn write_blocks(source: &mut String, blocks: &[ProgramBlock], prefix: &[OpCode], depth: usize) -> Result<(), AssemblyError> {
+    for (i, block) in blocks.iter().enumerate() {
+        match block {
+            ProgramBlock::Span(span) => {
+                // the assembler creates an empty first span if a branch starts with a block;
+                // all other spans must contain at least one instruction
+                let (skip, may_be_empty) = if i == 0 { (prefix.len(), blocks.len() > 1) } else { (0, false) };
+                let instructions = disassemble_span(span, skip, may_be_empty)?;
+                if instructions.len() > 0 {
+                    write_line(source, &instructions.join(" "), depth);
+                }
+            },
+            ProgramBlock::Group(block) => {
+                write_line(source, "block", depth);
+                write_blocks(source, block.body(), &[], depth + 1)?;
+                write_line(source, "end", depth);
+            },
+            ProgramBlock::Switch(block) => {
+                write_line(source, "if.true", depth);
+                write_blocks(source, block.true_branch(), &TRUE_BRANCH_PREFIX, depth + 1)?;
+                // the assembler generates the false branch when else is omitted
+                if !is_implicit_false_branch(block.false_branch()) {
+                    write_line(source, "else", depth);
+                    write_blocks(source, block.false_branch(), &FALSE_BRANCH_PREFIX, depth + 1)?;
+                }
+                write_line(source, "end", depth);
+            },
+            ProgramBlock::Loop(block) => {
+                write_line(source, "while.true", depth);
+                write_blocks(source, block.body(), &LOOP_BODY_PREFIX, depth + 1)?;
+                write_line(source, "end", depth);
+            },
+        }
+    }
+    return Ok(());
+}
+
+/// Converts operations of a span (starting with operation `skip`) into assembly instructions.
+fn disassemble_span(span: &Span, skip: usize, may_be_empty: bool) -> Result<Vec<String>, AssemblyError> {
+    let ops: Vec<OpCode> = (0..span.length()).map(|i| span.get_op(i).0).collect();
+    let hints: Vec<OpHint> = (0..span.length()).map(|i| span.get_hint(i)).collect();
+
+    let mut instructions = Vec::new();
+    let mut i = skip;
+    while i < ops.len() {
+        // skip over a run of NOOPs; some of them may be added by the assembler for alignment
+        let run_start = i;
+        while i < ops.len() && ops[i] == OpCode::Noop {
+            if hints[i] != OpHint::None {
+                return Err(AssemblyError::inexpressible_op(&format!("{}{}", ops[i], hints[i]), i));
+            }
+            i += 1;
+        }
+
+        if i == ops.len() {
+            // the assembler pads spans with NOOPs to one less than a multiple of cycle length;
+            // so, only the NOOPs which would not be added by padding must be written out
+            let mut num_noops = ops.len().saturating_sub(run_start + BASE_CYCLE_LENGTH - 1);
+            if instructions.len() == 0 && num_noops == 0 && !may_be_empty {
+                num_noops = 1;
+            }
+            instructions.resize(instructions.len() + num_noops, String::from("noop"));
+            break;
+        }
+
+        // find an instruction which produces the operations starting at step i; for each number
+        // of explicit NOOPs, try all candidate instructions and pick the one which covers the
+        // most operations (padding added by the assembler is never longer than a cycle)
+        let run_length = i - run_start;
+        let mut matched = None;
+        for num_noops in run_length.saturating_sub(BASE_CYCLE_LENGTH - 1)..(run_length + 1) {
+            let start = run_start + num_noops;
+            for candidate in get_candidates(&ops, &hints, i) {
+                if let Some(end) = match_instruction(&candidate, &ops, &hints, start) {
+                    if matched.as_ref().map_or(true, |&(_, _, best_end)| end > best_end) {
+                        matched = Some((num_noops, candidate, end));
+                    }
+                }
+            }
+            if matched.is_some() { break; }
+        }
+
+        match matched {
+            Some((num_noops, instruction, end)) => {
+                instructions.resize(instructions.len() + num_noops, String::from("noop"));
+                instructions.push(instruction);
+                i = end;
+            },
+            None => return Err(AssemblyError::inexpressible_op(&format!("{}{}", ops[i], hints[i]), i)),
+        }
+    }
+
+    return Ok(instructions);
+}
+
+/// Returns assembly instructions which may have produced a sequence of operations starting at
+/// step i; every candidate must still be verified with `match_instruction()`.
+fn get_candidates(ops: &[OpCode], hints: &[OpHint], i: usize) -> Vec<String> {
+    let candidates: Vec<String> = match ops[i] {
+        OpCode::Push     => vec![format!("push.{}", hints[i].value())],
+        OpCode::Read     => match hints[i] {
+            OpHint::EqStart => vec![String::from("ne"), String::from("eq")],
+            _ => vec![String::from("read")],
+        },
+        OpCode::Read2    => match hints[i] {
+            OpHint::PmpathStart(n) => vec![format!("pmpath.{}", n)],
+            _ => {
+                // if the next instruction also starts with READ2, the depth may be overestimated
+                let depth = count_smpath_nodes(ops, i);
+                vec![format!("smpath.{}", depth), format!("smpath.{}", depth - 1), String::from("read.ab")]
+            },
+        },
+        OpCode::Pad2     => {
+            let mut candidates = Vec::new();
+            // comparison and range check macros are marked by a hint shortly after they begin
+            let window = (i + 1)..cmp::min(i + BASE_CYCLE_LENGTH, hints.len());
+            let hint = hints[window].iter().find(|hint| match hint {
+                OpHint::CmpStart(_) | OpHint::RcStart(_) => true,
+                _ => false,
+            });
+            match hint {
+                Some(&OpHint::CmpStart(n)) => {
+                    candidates.push(format!("lte.{}", n));
+                    candidates.push(format!("gte.{}", n));
+                    candidates.push(format!("gt.{}", n));
+                    candidates.push(format!("lt.{}", n));
+                },
+                Some(&OpHint::RcStart(n)) => {
+                    candidates.push(format!("isodd.{}", n));
+                    candidates.push(format!("rc.{}", n));
+                },
+                _ => (),
+            }
+            for &n in [1, 3, 2, 4].iter() {
+                candidates.push(format!("hash.{}", n));
+            }
+            for &n in [7, 8, 5, 6, 3, 4, 1, 2].iter() {
+                candidates.push(format!("pad.{}", n));
+            }
+            candidates
+        },
+        OpCode::Dup      => to_strings(&["drop.7", "drop.3", "dup"]),
+        OpCode::Dup2     => {
+            // min and max instructions compare copies of the values made by DUP2
+            let mut candidates = Vec::new();
+            let window = (i + 1)..cmp::min(i + BASE_CYCLE_LENGTH, hints.len());
+            if let Some(&OpHint::CmpStart(n)) = hints[window].iter().find(|hint| match hint {
+                OpHint::CmpStart(_) => true,
+                _ => false,
+            }) {
+                candidates.push(format!("max.{}", n));
+                candidates.push(format!("min.{}", n));
+            }
+            candidates.extend(to_strings(&["pick.1", "dup.2"]));
+            candidates
+        },
+        OpCode::Dup4     => to_strings(&["pick.2", "pick.3", "dup.3", "dup.4"]),
+        OpCode::Drop     => to_strings(&["drop.6", "drop.5", "drop.2", "drop"]),
+        OpCode::Drop4    => to_strings(&["drop.8", "drop.4"]),
+        OpCode::Neg      => to_strings(&["sub", "neg"]),
+        OpCode::Inv      => to_strings(&["div", "inv"]),
+        OpCode::Assert   => to_strings(&["assert"]),
+        OpCode::AssertEq => to_strings(&["assert.eq"]),
+        OpCode::Choose   => to_strings(&["choose"]),
+        OpCode::Choose2  => to_strings(&["choose.2"]),
+        OpCode::Swap     => to_strings(&["swap"]),
+        OpCode::Swap2    => to_strings(&["swap.2"]),
+        OpCode::Swap4    => to_strings(&["swap.4"]),
+        OpCode::Roll4    => to_strings(&["roll.4"]),
+        OpCode::Roll8    => to_strings(&["roll.8"]),
+        OpCode::Add      => to_strings(&["add"]),
+        OpCode::Mul      => to_strings(&["mul"]),
+        OpCode::And      => to_strings(&["and"]),
+        OpCode::Or       => to_strings(&["or"]),
+        OpCode::Not      => to_strings(&["not"]),
+        // EQ, CMP, BINACC, RESCR, CSWAP2, and BEGIN operations are produced only as parts of
+        // macro instructions which start with other operations
+        _ => Vec::new(),
+    };
+    return candidates;
+}
+
+/// Compiles the instruction as if it started at step `start` of a span, and returns the step
+/// following the last produced operation if the produced operations and hints match the ones
+/// in the span.
+fn match_instruction(instruction: &str, ops: &[OpCode], hints: &[OpHint], start: usize) -> Option<usize> {
+    // alignment of operations depends only on the position within a cycle
+    let offset = start % BASE_CYCLE_LENGTH;
+    let mut op_codes = vec![OpCode::Noop; offset];
+    let mut op_hints = HintMap::new();
+    let op: Vec<&str> = instruction.split(".").collect();
+    if parse_op_token(op, &mut op_codes, &mut op_hints, 0).is_err() {
+        return None;
+    }
+
+    let end = start + op_codes.len() - offset;
+    if end > ops.len() || op_codes[offset..] != ops[start..end] {
+        return None;
+    }
+
+    for step in start..end {
+        let hint = op_hints.get(&(step - start + offset)).cloned().unwrap_or(OpHint::None);
+        if hint != hints[step] {
+            return None;
+        }
+    }
+
+    return Some(end);
+}
+
+/// Estimates the depth of a Merkle path for `smpath` instruction starting at step i; each node
+/// after the second one adds a full hashing cycle followed by reading of the next node.
+fn count_smpath_nodes(ops: &[OpCode], i: usize) -> usize {
+    // skip over the operations which read the first node and NOOPs added for alignment
+    let mut step = i + 5;
+    while step < ops.len() && ops[step] == OpCode::Noop {
+        step += 1;
+    }
+
+    let mut depth = 2;
+    while step + BASE_CYCLE_LENGTH <= ops.len()
+        && ops[step..(step + HASH_ROUNDS)].iter().all(|&op| op == OpCode::RescR)
+        && ops[step + HASH_ROUNDS + 1] == OpCode::Read2
+    {
+        depth += 1;
+        step += BASE_CYCLE_LENGTH;
+    }
+    return depth;
+}
+
+/// Returns true if the false branch is the one generated by the assembler for `if` without `else`.
+fn is_implicit_false_branch(blocks: &[ProgramBlock]) -> bool {
+    return match blocks {
+        [ProgramBlock::Span(span)] => {
+            span.length() == BASE_CYCLE_LENGTH - 1
+                && span.starts_with(&FALSE_BRANCH_PREFIX)
+                && (FALSE_BRANCH_PREFIX.len()..span.length()).all(|i| span.get_op(i) == (OpCode::Noop, OpHint::None))
+        },
+        _ => false,
+    };
+}
+
+fn write_line(source: &mut String, line: &str, depth: usize) {
+    source.push('\n');
+    for _ in 0..depth {
+        source.push_str(INDENT);
+    }
+    source.push_str(line);
+}
+
+fn to_strings(values: &[&str]) -> Vec<String> {
+    return values.iter().map(|value| value.to_string()).collect();
+}
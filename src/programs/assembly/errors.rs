@@ -1,11 +1,18 @@
 extern crate alloc;
 use alloc::string::String;
+use sp_std::fmt;
+use super::tokenizer::{ Token, SourceLocation, get_line };
+
 // TYPES AND INTERFACES
 // ================================================================================================
+#[derive(Clone, PartialEq, Eq)]
 pub struct AssemblyError {
     message : String,
     step    : usize,
-    op      : String
+    op      : String,
+    location: Option<SourceLocation>,
+    snippet : String,
+    module  : Option<String>,
 }
 
 // ASSEMBLY ERROR IMPLEMENTATION
@@ -20,6 +27,9 @@ impl AssemblyError {
             message : String::from("a program must contain at least one instruction"),
             step    : 0,
             op      : String::from("begin"),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
@@ -28,22 +38,31 @@ impl AssemblyError {
             message : String::from("a program block must contain at least one instruction"),
             step    : step,
             op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
     pub fn invalid_program_start(op: &str) -> AssemblyError {
         return AssemblyError {
-            message : String::from("a program must start with a 'being' instruction"),
+            message : String::from("a program must start with a 'begin' instruction"),
             step    : 0,
             op      : String::from(op),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
-    pub fn invalid_program_end(op: &str) -> AssemblyError {
+    pub fn invalid_program_end(op: &str, step: usize) -> AssemblyError {
         return AssemblyError {
             message : String::from("a program must end with an 'end' instruction"),
-            step    : 0,
+            step    : step,
             op      : String::from(op),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
@@ -52,6 +71,9 @@ impl AssemblyError {
             message : format!("dangling instructions after program end"),
             step    : step,
             op      : String::from("end"),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
@@ -60,6 +82,9 @@ impl AssemblyError {
             message : format!("instruction {} is invalid", op.join(".")),
             step    : step,
             op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
@@ -68,6 +93,9 @@ impl AssemblyError {
             message : format!("malformed instruction {}: parameter is missing", op[0]),
             step    : step,
             op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
@@ -76,6 +104,9 @@ impl AssemblyError {
             message : format!("malformed instruction {}: too many parameters provided", op[0]),
             step    : step,
             op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
@@ -84,6 +115,9 @@ impl AssemblyError {
             message : format!("malformed instruction {}: parameter '{}' is invalid", op[0], op[1]),
             step    : step,
             op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
@@ -92,6 +126,9 @@ impl AssemblyError {
             message : format!("malformed instruction {}: {}", op[0], reason),
             step    : step,
             op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
@@ -100,6 +137,9 @@ impl AssemblyError {
             message : format!("invalid block head '{}'", op.join(".")),
             step    : step,
             op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
@@ -108,6 +148,9 @@ impl AssemblyError {
             message : format!("invalid repeat statement '{}': 2 or more iterations must be specified", op.join(".")),
             step    : step,
             op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
@@ -116,6 +159,9 @@ impl AssemblyError {
             message : format!("else without matching if"),
             step    : step,
             op      : String::from("else"),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
@@ -124,6 +170,9 @@ impl AssemblyError {
             message : format!("block without matching end"),
             step    : step,
             op      : String::from("block"),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
@@ -132,6 +181,9 @@ impl AssemblyError {
             message : format!("if without matching else/end"),
             step    : step,
             op      : String::from("if.true"),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
@@ -140,6 +192,9 @@ impl AssemblyError {
             message : format!("while without matching end"),
             step    : step,
             op      : String::from("while.true"),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
@@ -148,6 +203,9 @@ impl AssemblyError {
             message : format!("repeat without matching end"),
             step    : step,
             op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
@@ -156,9 +214,218 @@ impl AssemblyError {
             message : format!("else without matching end"),
             step    : step,
             op      : String::from("else"),
+            location: None,
+            snippet : String::new(),
+            module  : None,
+        };
+    }
+
+    pub fn unmatched_proc(name: &str, step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("procedure '{}' without matching end", name),
+            step    : step,
+            op      : format!("proc.{}", name),
+            location: None,
+            snippet : String::new(),
+            module  : None,
+        };
+    }
+
+    pub fn duplicate_proc(name: &str, step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("procedure '{}' is already defined", name),
+            step    : step,
+            op      : format!("proc.{}", name),
+            location: None,
+            snippet : String::new(),
+            module  : None,
+        };
+    }
+
+    pub fn undefined_proc(name: &str, step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("procedure '{}' is not defined", name),
+            step    : step,
+            op      : format!("exec.{}", name),
+            location: None,
+            snippet : String::new(),
+            module  : None,
+        };
+    }
+
+    pub fn inlining_limit_exceeded(limit: usize, step: usize, op: &str) -> AssemblyError {
+        return AssemblyError {
+            message : format!("inlining procedure calls produces more than {} instructions", limit),
+            step    : step,
+            op      : String::from(op),
+            location: None,
+            snippet : String::new(),
+            module  : None,
+        };
+    }
+
+    pub fn recursive_proc(name: &str, call_stack: &[&str], step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("procedure '{}' is called recursively: {} -> {}", name, call_stack.join(" -> "), name),
+            step    : step,
+            op      : format!("exec.{}", name),
+            location: None,
+            snippet : String::new(),
+            module  : None,
+        };
+    }
+
+    pub fn misplaced_proc(op: &[&str], step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("procedure {} must be defined before program begin", op.join(".")),
+            step    : step,
+            op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
         };
     }
 
+    pub fn duplicate_const(name: &str, step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("constant '{}' is already defined", name),
+            step    : step,
+            op      : format!("const.{}", name),
+            location: None,
+            snippet : String::new(),
+            module  : None,
+        };
+    }
+
+    pub fn undefined_const(name: &str, op: &[&str], step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("malformed instruction {}: constant '{}' is not defined", op[0], name),
+            step    : step,
+            op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
+        };
+    }
+
+    pub fn misplaced_const(op: &[&str], step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("constant {} must be declared before program begin", op.join(".")),
+            step    : step,
+            op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
+        };
+    }
+
+    pub fn misplaced_import(op: &[&str], step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("import {} must precede procedure definitions and program begin", op.join(".")),
+            step    : step,
+            op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
+        };
+    }
+
+    pub fn duplicate_import(alias: &str, op: &[&str], step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("module '{}' is already imported", alias),
+            step    : step,
+            op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
+        };
+    }
+
+    pub fn module_not_found(path: &str, op: &[&str], step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("module '{}' could not be found", path),
+            step    : step,
+            op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
+        };
+    }
+
+    pub fn import_cycle(path: &str, import_stack: &[&str], op: &[&str], step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("module '{}' is imported cyclically: {} -> {}", path, import_stack.join(" -> "), path),
+            step    : step,
+            op      : op.join("."),
+            location: None,
+            snippet : String::new(),
+            module  : None,
+        };
+    }
+
+    pub fn invalid_module_body(op: &str, step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("a module may contain only imports, constants, and procedures, but found '{}'", op),
+            step    : step,
+            op      : String::from(op),
+            location: None,
+            snippet : String::new(),
+            module  : None,
+        };
+    }
+
+    pub fn inexpressible_op(op: &str, step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("operation {} at step {} of a span cannot be expressed in assembly", op, step),
+            step    : step,
+            op      : String::from(op),
+            location: None,
+            snippet : String::new(),
+            module  : None,
+        };
+    }
+
+    pub fn unmatched_comment(location: SourceLocation, line: &str) -> AssemblyError {
+        return AssemblyError {
+            message : String::from("block comment without matching ]#"),
+            step    : 0,
+            op      : String::from("#["),
+            location: Some(location),
+            snippet : String::from(line),
+            module  : None,
+        };
+    }
+
+    /// Marks this error as originating in the imported module at the specified path; step and
+    /// location of such errors refer to the source of that module. If the error is already
+    /// marked (i.e. it originated in a nested import), the innermost module is kept.
+    pub fn in_module(mut self, path: &str) -> AssemblyError {
+        if self.module.is_none() {
+            self.module = Some(String::from(path));
+        }
+        return self;
+    }
+
+    /// Translates the step of this error using the provided map; this is used when errors are
+    /// reported for a token stream derived from the original one (e.g. with procedures inlined).
+    pub fn map_step(mut self, steps: &[usize]) -> AssemblyError {
+        if self.module.is_none() && self.location.is_none() && self.step < steps.len() {
+            self.step = steps[self.step];
+        }
+        return self;
+    }
+
+    /// Attaches location of the offending token in the `source` to this error; `tokens` must
+    /// be the tokens from which the step of this error was counted.
+    pub fn with_source(mut self, source: &str, tokens: &[Token]) -> AssemblyError {
+        if self.module.is_none() && self.location.is_none() && self.step < tokens.len() {
+            let location = tokens[self.step].location;
+            self.snippet = String::from(get_line(source, location.line));
+            self.location = Some(location);
+        }
+        return self;
+    }
+
     // PUBLIC ACCESSORS
     // --------------------------------------------------------------------------------------------
     pub fn message(&self) -> &String {
@@ -172,20 +439,56 @@ impl AssemblyError {
     pub fn step(&self) -> usize {
         return self.step;
     }
+
+    /// Returns line and column of the offending token, if known.
+    pub fn location(&self) -> Option<SourceLocation> {
+        return self.location;
+    }
+
+    /// Returns the source line which contains the offending token, if known.
+    pub fn snippet(&self) -> &str {
+        return &self.snippet;
+    }
+
+    /// Returns the path of the imported module in which the error occurred, if the error did
+    /// not occur in the compiled source itself.
+    pub fn module(&self) -> Option<&str> {
+        return self.module.as_ref().map(|path| path.as_str());
+    }
 }
 
 
 // COMMON TRAIT IMPLEMENTATIONS
 // ================================================================================================
 
-impl sp_std::fmt::Debug for AssemblyError {
-    fn fmt(&self, f: &mut sp_std::fmt::Formatter<'_>) -> sp_std::fmt::Result {
-        write!(f, "assembly error at {}: {}", self.step, self.message)
+impl fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let module = match &self.module {
+            Some(path) => format!(" in module '{}'", path),
+            None => String::new(),
+        };
+        match self.location {
+            Some(location) => {
+                writeln!(f, "assembly error{} at {}:{}: {}", module, location.line, location.col, self.message)?;
+                // print the offending line with a marker under the offending token
+                let line_number = format!("{}", location.line);
+                let padding = " ".repeat(line_number.len());
+                let marker_length = sp_std::cmp::max(self.op.chars().count(), 1);
+                // tabs are copied from the offending line so that the marker stays aligned
+                let indent: String = self.snippet.chars().take(location.col - 1)
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+                writeln!(f, "{} |", padding)?;
+                writeln!(f, "{} | {}", line_number, self.snippet)?;
+                write!(f, "{} | {}{}", padding, indent, "^".repeat(marker_length))
+            },
+            None => write!(f, "assembly error{} at token {}: {}", module, self.step, self.message),
+        }
     }
 }
 
-impl sp_std::fmt::Display for AssemblyError {
-    fn fmt(&self, f: &mut sp_std::fmt::Formatter<'_>) -> sp_std::fmt::Result {
-        write!(f, "assembly error at {}: {}", self.step, self.message)
+impl fmt::Debug for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return fmt::Display::fmt(self, f);
     }
-}
\ No newline at end of file
+}
@@ -6,7 +6,33 @@ mod parsers;
 use parsers::*;
 
 mod errors;
-use errors::{ AssemblyError };
+pub use errors::{ AssemblyError };
+
+mod constants;
+use constants::{ resolve_constants };
+
+mod procedures;
+use procedures::{ parse_procedures, validate_procedures, inline_calls };
+
+mod modules;
+use modules::{ ModuleLoader };
+pub use modules::{ ModuleResolver, MemoryResolver };
+#[cfg(feature = "std")]
+pub use modules::{ FileSystemResolver };
+
+mod tokenizer;
+use tokenizer::{ tokenize };
+pub use tokenizer::{ SourceLocation };
+
+mod disassembler;
+pub use disassembler::{ disassemble };
+
+mod optimizer;
+pub use optimizer::{ optimize };
+
+mod source_map;
+use source_map::{ SourceBlock, flatten_source_blocks };
+pub use source_map::{ SourceMap, SourceInstruction };
 
 #[cfg(test)]
 mod tests;
@@ -16,11 +42,86 @@ type HintMap = HashMap<usize, OpHint>;
 // ASSEMBLY COMPILER
 // ================================================================================================
 
-/// Compiles provided assembly code into a program.
+/// Compiles provided assembly code into a program. Line comments start with `#`, and block
+/// comments are enclosed between `#[` and `]#`; errors point to the line and column of the
+/// offending token.
+///
+/// Constants can be declared before the program body as `const.NAME=expression`; parameters of
+/// instructions can be constant expressions, e.g. `push.NAME`, `push.2^64`, or `push.MODULUS-1`.
+///
+/// Procedures can be defined before the program body as `proc.name ... end`; every call to a
+/// procedure (`exec.name`) is replaced with the body of the procedure.
+///
+/// Programs compiled with this function cannot import modules; use `compile_with_resolver()`
+/// to compile programs which contain `use` directives.
 pub fn compile(source: &str) -> Result<Program, AssemblyError> {
+    return compile_with_resolver(source, &MemoryResolver::new());
+}
 
-    // break assembly string into tokens
-    let tokens: Vec<&str> = source.split_whitespace().collect();
+/// Compiles provided assembly code into a program and optimizes the program with `optimize()`;
+/// the resulting program produces the same outputs as the program returned by `compile()`
+/// (unless that program fails with a stack overflow or underflow), but has a different hash
+/// and usually a shorter execution trace.
+pub fn compile_optimized(source: &str) -> Result<Program, AssemblyError> {
+    return Ok(optimize(&compile(source)?));
+}
+
+/// Compiles provided assembly code into a program, fetching source code of modules imported
+/// with `use.path::to::module` directives from the `resolver`.
+///
+/// Imports must precede procedure definitions and the program body. Procedures defined in a
+/// module with `export.name ... end` can be called as `exec.module::name`, where `module` is
+/// the last component of the module path; procedures defined with `proc.name` are private to
+/// the module. Modules can import other modules, but import cycles are not allowed.
+pub fn compile_with_resolver<R: ModuleResolver + ?Sized>(source: &str, resolver: &R) -> Result<Program, AssemblyError> {
+    return compile_with_source_map(source, resolver).map(|(program, _)| program);
+}
+
+/// Compiles provided assembly code in the same way as `compile_with_resolver()`, and also
+/// returns a source map which maps every operation of the program back to the assembly
+/// instruction which produced it.
+pub fn compile_with_source_map<R: ModuleResolver + ?Sized>(source: &str, resolver: &R) -> Result<(Program, SourceMap), AssemblyError> {
+
+    // break assembly string into tokens, skipping over comments
+    let tokens = tokenize(source)?;
+    let texts: Vec<&str> = tokens.iter().map(|token| token.text).collect();
+
+    // replace constant expressions with their values; steps map each resulting token back to
+    // its index in the original token stream
+    let (resolved, steps) = resolve_constants(&texts).map_err(|err| err.with_source(source, &tokens))?;
+    let ops: Vec<&str> = resolved.iter().map(|token| token.as_ref()).collect();
+
+    let mut loader = ModuleLoader::new(resolver);
+    let (program, spans) = compile_tokens(&ops, &mut loader)
+        .map_err(|err| err.map_step(&steps).with_source(source, &tokens))?;
+    return Ok((program, SourceMap::new(spans, &steps, &tokens)));
+}
+
+/// Compiles a program from a stream of tokens; errors refer to tokens by their index. Also
+/// returns indexes of tokens which produced operations of every span in program order.
+fn compile_tokens<R: ModuleResolver + ?Sized>(tokens: &[&str], loader: &mut ModuleLoader<R>) -> Result<(Program, Vec<Vec<Option<usize>>>), AssemblyError> {
+
+    // load imported modules, then read procedure definitions which precede the program body,
+    // and make sure they are valid
+    let (imports, start) = loader.load_imports(tokens, 0)?;
+    let (procedures, start) = parse_procedures(tokens, start)?;
+    validate_procedures(tokens, &procedures, &imports)?;
+
+    // inline all procedure calls in the program body; steps map each inlined token back to
+    // its index in the original token stream
+    let mut ops = Vec::new();
+    let mut steps = Vec::new();
+    inline_calls(tokens, start..tokens.len(), &procedures, &imports, &mut Vec::new(), &mut ops, &mut steps)?;
+
+    let (program, source_blocks) = compile_program(&ops).map_err(|err| err.map_step(&steps))?;
+    let mut spans = Vec::new();
+    flatten_source_blocks(&source_blocks, &steps, &mut spans);
+    return Ok((program, spans));
+}
+
+/// Compiles a program body which starts with `begin` and ends with `end`; also returns indexes
+/// of tokens which produced operations of every program block.
+fn compile_program(tokens: &[&str]) -> Result<(Program, Vec<SourceBlock>), AssemblyError> {
 
     // perform basic validation
     if tokens.len() == 0 {
@@ -30,12 +131,13 @@ pub fn compile(source: &str) -> Result<Program, AssemblyError> {
         return Err(AssemblyError::invalid_program_start(tokens[0]));
     }
     else if tokens[tokens.len() - 1] != "end" {
-        return Err(AssemblyError::invalid_program_end(tokens[tokens.len() - 1]));
+        return Err(AssemblyError::invalid_program_end(tokens[tokens.len() - 1], tokens.len() - 1));
     }
 
     // read the program from the token stream
     let mut root_blocks = Vec::new();
-    let i = parse_branch(&mut root_blocks, &tokens, 0)?;
+    let mut source_blocks = Vec::new();
+    let i = parse_branch(&mut root_blocks, &mut source_blocks, tokens, 0)?;
     let root = Group::new(root_blocks);
 
     // make sure there is nothing left after the last token
@@ -44,15 +146,16 @@ pub fn compile(source: &str) -> Result<Program, AssemblyError> {
     }
 
     // build and return the program
-    return Ok(Program::new(root));
+    return Ok((Program::new(root), source_blocks));
 }
 
 // PARSER FUNCTIONS
 // ================================================================================================
 
 /// Parses a single program block from the `token` stream, and appends this block to the `parent`
-/// list of blocks.
-fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) -> Result<usize, AssemblyError> {
+/// list of blocks; indexes of tokens which produced operations of the block are appended to the
+/// `sources` list.
+fn parse_block(parent: &mut Vec<ProgramBlock>, sources: &mut Vec<SourceBlock>, tokens: &[&str], mut i: usize) -> Result<usize, AssemblyError> {
 
     // read the block header
     let head: Vec<&str> = tokens[i].split(".").collect();
@@ -66,8 +169,10 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
             }
             // then parse the body of the block, add the new block to the parent, and return
             let mut body = Vec::new();
-            i = parse_branch(&mut body, tokens, i)?;
+            let mut body_sources = Vec::new();
+            i = parse_branch(&mut body, &mut body_sources, tokens, i)?;
             parent.push(Group::new_block(body));
+            sources.push(SourceBlock::Group(body_sources));
             return Ok(i + 1);
         },
         "if" => {
@@ -77,14 +182,17 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
             }
 
             // parse the body of the true branch
+            let head_step = i;
             let mut t_branch = Vec::new();
-            i = parse_branch(&mut t_branch, tokens, i)?;
+            let mut t_sources = Vec::new();
+            i = parse_branch(&mut t_branch, &mut t_sources, tokens, i)?;
 
             // if the false branch is present, parse it as well; otherwise
             // create an empty false branch
             let mut f_branch = Vec::new();
+            let mut f_sources = Vec::new();
             if tokens[i] == "else" {
-                i = parse_branch(&mut f_branch, tokens, i)?;
+                i = parse_branch(&mut f_branch, &mut f_sources, tokens, i)?;
             }
             else {
                 f_branch.push(Span::new_block(vec![
@@ -93,10 +201,15 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
                     OpCode::Noop, OpCode::Noop,   OpCode::Noop, OpCode::Noop,
                     OpCode::Noop, OpCode::Noop,   OpCode::Noop,
                 ]));
+                // the implicit false branch fails if the condition is not binary
+                let mut steps = vec![Some(head_step); 2];
+                steps.resize(BASE_CYCLE_LENGTH - 1, None);
+                f_sources.push(SourceBlock::Span(steps));
             }
 
             // create a Switch block, add it to the parent, and return
             parent.push(Switch::new_block(t_branch, f_branch));
+            sources.push(SourceBlock::Switch(t_sources, f_sources));
             return Ok(i + 1);
         },
         "repeat" => {
@@ -108,13 +221,16 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
 
             // parse loop body
             let mut body_template = Vec::new();
-            i = parse_branch(&mut body_template, tokens, i)?;
+            let mut sources_template = Vec::new();
+            i = parse_branch(&mut body_template, &mut sources_template, tokens, i)?;
 
             // duplicate loop body as many times as needed
-            let body = repeat_block_sequence(body_template, num_iterations);
+            let body = repeat_block_sequence(body_template, num_iterations, ProgramBlock::is_span, merge_spans);
+            let body_sources = repeat_block_sequence(sources_template, num_iterations, SourceBlock::is_span, SourceBlock::merge);
 
             // create a Group block with all iterations expanded, and return
             parent.push(Group::new_block(body));
+            sources.push(SourceBlock::Group(body_sources));
             return Ok(i + 1);
         },
         "while" => {
@@ -124,8 +240,10 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
             }
             // then parse the body of the block, add the new block to the parent, and return
             let mut body = Vec::new();
-            i = parse_branch(&mut body, tokens, i)?;
+            let mut body_sources = Vec::new();
+            i = parse_branch(&mut body, &mut body_sources, tokens, i)?;
             parent.push(Loop::new_block(body));
+            sources.push(SourceBlock::Loop(body_sources));
             return Ok(i + 1);
         },
         _ => return Err(AssemblyError::invalid_block_head(&head, i)),
@@ -133,8 +251,9 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
 }
 
 /// Builds a body of a program block by parsing tokens from the stream and transforming
-/// them into program blocks.
-fn parse_branch(body: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) -> Result<usize, AssemblyError> {
+/// them into program blocks; indexes of tokens which produced operations of these blocks are
+/// appended to `sources`.
+fn parse_branch(body: &mut Vec<ProgramBlock>, sources: &mut Vec<SourceBlock>, tokens: &[&str], mut i: usize) -> Result<usize, AssemblyError> {
 
     // determine starting instructions of the branch based on branch head
     let mut head: Vec<&str> = tokens[i].split(".").collect();
@@ -153,6 +272,9 @@ fn parse_branch(body: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
     };
     let mut op_hints: HintMap = HashMap::new();
 
+    // starting instructions are attributed to the branch head
+    let mut op_steps = vec![Some(i); op_codes.len()];
+
     // save first step to check for empty branches
     let first_step = i;
     i += 1;
@@ -164,8 +286,8 @@ fn parse_branch(body: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
         i = match op[0] {
             "block" | "if" | "repeat" | "while" => {
                 let force_span = body.len() == 0;
-                add_span(body, &mut op_codes, &mut op_hints, force_span);
-                parse_block(body, tokens, i)?
+                add_span(body, sources, &mut op_codes, &mut op_hints, &mut op_steps, force_span);
+                parse_block(body, sources, tokens, i)?
             },
             "else" => {
                 if head[0] != "if" {
@@ -174,17 +296,21 @@ fn parse_branch(body: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
                 else if i - first_step < 2 {
                     return Err(AssemblyError::empty_block(&head, first_step));
                 }
-                add_span(body, &mut op_codes, &mut op_hints, false);
+                add_span(body, sources, &mut op_codes, &mut op_hints, &mut op_steps, false);
                 return Ok(i);
             },
             "end" => {
                 if i - first_step < 2 {
                     return Err(AssemblyError::empty_block(&head, first_step));
                 }
-                add_span(body, &mut op_codes, &mut op_hints, false);
+                add_span(body, sources, &mut op_codes, &mut op_hints, &mut op_steps, false);
                 return Ok(i);
             },
-            _ => parse_op_token(op, &mut op_codes, &mut op_hints, i)?
+            _ => {
+                let next = parse_op_token(op, &mut op_codes, &mut op_hints, i)?;
+                op_steps.resize(op_codes.len(), Some(i));
+                next
+            },
         };
     }
 
@@ -205,7 +331,7 @@ fn parse_op_token(op: Vec<&str>, op_codes: &mut Vec<OpCode>, op_hints: &mut Hint
     // based on the instruction, invoke the correct parser for the operation
     match op[0] {
         "noop"   => parse_noop(op_codes, &op, step),
-        "assert" => parse_assert(op_codes, &op, step),
+        "assert" => parse_assert(op_codes, op_hints, &op, step),
 
         "push"   => parse_push(op_codes, op_hints, &op, step),
         "read"   => parse_read(op_codes, &op, step),
@@ -231,6 +357,10 @@ fn parse_op_token(op: Vec<&str>, op_codes: &mut Vec<OpCode>, op_hints: &mut Hint
         "ne"     => parse_ne(op_codes, op_hints, &op, step),
         "gt"     => parse_gt(op_codes, op_hints, &op, step),
         "lt"     => parse_lt(op_codes, op_hints, &op, step),
+        "lte"    => parse_lte(op_codes, op_hints, &op, step),
+        "gte"    => parse_gte(op_codes, op_hints, &op, step),
+        "min"    => parse_min(op_codes, op_hints, &op, step),
+        "max"    => parse_max(op_codes, op_hints, &op, step),
         "rc"     => parse_rc(op_codes, op_hints, &op, step),
         "isodd"  => parse_isodd(op_codes, op_hints, &op, step),
 
@@ -251,7 +381,14 @@ fn parse_op_token(op: Vec<&str>, op_codes: &mut Vec<OpCode>, op_hints: &mut Hint
 // ================================================================================================
 
 /// Adds a new Span block to a program block body based on currently parsed instructions.
-fn add_span(body: &mut Vec<ProgramBlock>, op_codes: &mut Vec<OpCode>, op_hints: &mut HintMap, force: bool) {
+fn add_span(
+    body    : &mut Vec<ProgramBlock>,
+    sources : &mut Vec<SourceBlock>,
+    op_codes: &mut Vec<OpCode>,
+    op_hints: &mut HintMap,
+    op_steps: &mut Vec<Option<usize>>,
+    force   : bool)
+{
 
     // if there were no instructions in the current span, don't do anything
     if op_codes.len() == 0 && !force { return };
@@ -261,19 +398,25 @@ fn add_span(body: &mut Vec<ProgramBlock>, op_codes: &mut Vec<OpCode>, op_hints:
     let pad_length = BASE_CYCLE_LENGTH - (span_op_codes.len() % BASE_CYCLE_LENGTH) - 1;
     span_op_codes.resize(span_op_codes.len() + pad_length, OpCode::Noop);
 
-    // add a new Span block to the body
+    // add a new Span block to the body; padding is not attributed to any instruction
     body.push(ProgramBlock::Span(Span::new(span_op_codes, op_hints.clone())));
+    let mut span_op_steps = op_steps.clone();
+    span_op_steps.resize(span_op_steps.len() + pad_length, None);
+    sources.push(SourceBlock::Span(span_op_steps));
 
-    // clear op_codes and op_hints for the next Span block
+    // clear op_codes, op_hints, and op_steps for the next Span block
     op_codes.clear();
     op_hints.clear();
+    op_steps.clear();
 }
 
-fn repeat_block_sequence(template: Vec<ProgramBlock>, num_iterations: usize) -> Vec<ProgramBlock> {
+/// Repeats a sequence of blocks; if the sequence ends with a span, the span is merged with the
+/// first span of the next iteration. Works with both program blocks and their source blocks.
+fn repeat_block_sequence<T: Clone>(template: Vec<T>, num_iterations: usize, is_span: fn(&T) -> bool, merge: fn(&T, &T) -> T) -> Vec<T> {
     let mut body = Vec::with_capacity(template.len() * num_iterations);
 
     let last_idx = template.len() - 1;
-    if !template[last_idx].is_span() {
+    if !is_span(&template[last_idx]) {
         for _ in 0..num_iterations {
             body.extend_from_slice(&template);
         }
@@ -282,7 +425,7 @@ fn repeat_block_sequence(template: Vec<ProgramBlock>, num_iterations: usize) ->
         body.extend_from_slice(&template);
         for _ in 1..num_iterations {
             let last_idx = body.len() - 1;
-            body[last_idx] = merge_spans(&body[last_idx], &template[0]);
+            body[last_idx] = merge(&body[last_idx], &template[0]);
             body.extend_from_slice(&template[1..]);
         }
     }
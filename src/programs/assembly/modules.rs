@@ -0,0 +1,210 @@
+use alloc::string::{ String, ToString };
+use hashbrown::HashMap;
+use sp_std::vec::Vec;
+use super::{ AssemblyError, tokenize, resolve_constants };
+use super::procedures::{ parse_procedures, validate_procedures, inline_calls };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Provides source code of assembly modules imported with `use.path::to::module` directives.
+pub trait ModuleResolver {
+    /// Returns source code of the module at the specified path (e.g. `std::merkle`), or None
+    /// if the module does not exist.
+    fn resolve(&self, path: &str) -> Option<String>;
+}
+
+/// Module resolver which keeps source code of all modules in memory.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryResolver {
+    modules: HashMap<String, String>,
+}
+
+/// Module resolver which reads module `a::b::c` from file `a/b/c.dasm` relative to a root
+/// directory.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct FileSystemResolver {
+    root: std::path::PathBuf,
+}
+
+/// Procedures imported into a compilation unit keyed by their qualified names (`module::proc`);
+/// bodies of imported procedures have all calls to other procedures already inlined.
+pub type Imports = HashMap<String, Vec<String>>;
+
+/// Loads and compiles imported modules; every module is compiled at most once.
+pub struct ModuleLoader<'r, R: ModuleResolver + ?Sized> {
+    resolver    : &'r R,
+    modules     : HashMap<String, Vec<(String, Vec<String>)>>,
+    stack       : Vec<String>,
+}
+
+// RESOLVER IMPLEMENTATIONS
+// ================================================================================================
+impl MemoryResolver {
+
+    pub fn new() -> MemoryResolver {
+        return MemoryResolver { modules: HashMap::new() };
+    }
+
+    /// Adds source code of a module at the specified path (e.g. `std::merkle`), replacing
+    /// previously added source code for the same path.
+    pub fn insert(&mut self, path: &str, source: &str) {
+        self.modules.insert(String::from(path), String::from(source));
+    }
+}
+
+impl ModuleResolver for MemoryResolver {
+    fn resolve(&self, path: &str) -> Option<String> {
+        return self.modules.get(path).cloned();
+    }
+}
+
+#[cfg(feature = "std")]
+impl FileSystemResolver {
+
+    /// Returns a resolver which reads modules from files relative to the `root` directory.
+    pub fn new<P: Into<std::path::PathBuf>>(root: P) -> FileSystemResolver {
+        return FileSystemResolver { root: root.into() };
+    }
+}
+
+#[cfg(feature = "std")]
+impl ModuleResolver for FileSystemResolver {
+    fn resolve(&self, path: &str) -> Option<String> {
+        let mut file = self.root.clone();
+        for segment in path.split("::") {
+            file.push(segment);
+        }
+        file.set_extension("dasm");
+        return std::fs::read_to_string(file).ok();
+    }
+}
+
+// MODULE LOADER IMPLEMENTATION
+// ================================================================================================
+impl <'r, R: ModuleResolver + ?Sized> ModuleLoader<'r, R> {
+
+    pub fn new(resolver: &'r R) -> ModuleLoader<'r, R> {
+        return ModuleLoader { resolver, modules: HashMap::new(), stack: Vec::new() };
+    }
+
+    /// Reads `use.path::to::module` directives starting at token `start`, and loads exported
+    /// procedures of all imported modules; procedures are namespaced by the last component of
+    /// the module path. Returns imported procedures and the index of the first token after
+    /// the last directive.
+    pub fn load_imports(&mut self, tokens: &[&str], start: usize) -> Result<(Imports, usize), AssemblyError> {
+        let mut imports = Imports::new();
+        let mut aliases: Vec<&str> = Vec::new();
+
+        let mut i = start;
+        while i < tokens.len() {
+            let op: Vec<&str> = tokens[i].split(".").collect();
+            if op[0] != "use" { break; }
+
+            let path = read_module_path(&op, i)?;
+            let alias = path.rsplit("::").next().unwrap_or(path);
+            if aliases.contains(&alias) {
+                return Err(AssemblyError::duplicate_import(alias, &op, i));
+            }
+            aliases.push(alias);
+
+            self.load_module(path, &op, i)?;
+            for (name, body) in self.modules[path].iter() {
+                imports.insert(format!("{}::{}", alias, name), body.clone());
+            }
+            i += 1;
+        }
+
+        return Ok((imports, i));
+    }
+
+    /// Compiles the module at the specified path unless it has been compiled already.
+    fn load_module(&mut self, path: &str, op: &[&str], step: usize) -> Result<(), AssemblyError> {
+        if self.modules.contains_key(path) {
+            return Ok(());
+        }
+        if self.stack.iter().any(|p| p == path) {
+            let cycle = self.stack.iter().map(|p| p.as_str()).collect::<Vec<&str>>();
+            return Err(AssemblyError::import_cycle(path, &cycle, op, step));
+        }
+        let source = match self.resolver.resolve(path) {
+            Some(source) => source,
+            None => return Err(AssemblyError::module_not_found(path, op, step)),
+        };
+
+        self.stack.push(path.to_string());
+        let exports = self.compile_module(&source).map_err(|err| err.in_module(path))?;
+        self.stack.pop();
+
+        self.modules.insert(path.to_string(), exports);
+        return Ok(());
+    }
+
+    /// Compiles module source code into a list of exported procedures.
+    fn compile_module(&mut self, source: &str) -> Result<Vec<(String, Vec<String>)>, AssemblyError> {
+        let tokens = tokenize(source)?;
+        let texts: Vec<&str> = tokens.iter().map(|token| token.text).collect();
+        let (resolved, steps) = resolve_constants(&texts).map_err(|err| err.with_source(source, &tokens))?;
+        let ops: Vec<&str> = resolved.iter().map(|token| token.as_ref()).collect();
+
+        return self.compile_module_tokens(&ops)
+            .map_err(|err| err.map_step(&steps).with_source(source, &tokens));
+    }
+
+    fn compile_module_tokens(&mut self, tokens: &[&str]) -> Result<Vec<(String, Vec<String>)>, AssemblyError> {
+        let (imports, start) = self.load_imports(tokens, 0)?;
+        let (procedures, end) = parse_procedures(tokens, start)?;
+        if end < tokens.len() {
+            let op: Vec<&str> = tokens[end].split(".").collect();
+            if op[0] == "use" {
+                return Err(AssemblyError::misplaced_import(&op, end));
+            }
+            return Err(AssemblyError::invalid_module_body(tokens[end], end));
+        }
+        validate_procedures(tokens, &procedures, &imports)?;
+
+        // inline all calls in exported procedures; bodies of all exported procedures are inlined
+        // into the same buffer, so that together they cannot exceed the inlining limit
+        let mut exports = Vec::new();
+        let mut ops = Vec::new();
+        let mut steps = Vec::new();
+        for procedure in procedures.iter().filter(|procedure| procedure.exported) {
+            let start = ops.len();
+            let mut call_stack = vec![procedure.name];
+            inline_calls(tokens, procedure.body.clone(), &procedures, &imports, &mut call_stack, &mut ops, &mut steps)?;
+            exports.push((procedure.name.to_string(), ops[start..].iter().map(|op| op.to_string()).collect()));
+        }
+
+        return Ok(exports);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Reads module path from `use.path::to::module` directive; every component of the path must
+/// start with a letter or an underscore, and may contain only letters, digits, and underscores.
+fn read_module_path<'a>(op: &[&'a str], step: usize) -> Result<&'a str, AssemblyError> {
+    if op.len() == 1 {
+        return Err(AssemblyError::missing_param(op, step));
+    }
+    else if op.len() > 2 {
+        return Err(AssemblyError::extra_param(op, step));
+    }
+
+    if !op[1].split("::").all(is_identifier) {
+        return Err(AssemblyError::invalid_param(op, step));
+    }
+    return Ok(op[1]);
+}
+
+/// Returns true if the name starts with a letter or an underscore, and contains only letters,
+/// digits, and underscores.
+pub fn is_identifier(name: &str) -> bool {
+    return match name.chars().next() {
+        Some(c) => (c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        None => false,
+    };
+}
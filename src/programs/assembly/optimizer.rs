@@ -0,0 +1,165 @@
+use sp_std::{ cmp, vec::Vec };
+use crate::PUSH_OP_ALIGNMENT;
+use super::{ Program, ProgramBlock, Span, Group, Switch, Loop, OpCode, OpHint, HintMap, BASE_CYCLE_LENGTH };
+use super::disassembler::{ PROGRAM_PREFIX, TRUE_BRANCH_PREFIX, FALSE_BRANCH_PREFIX, LOOP_BODY_PREFIX };
+
+// CONSTANTS
+// ================================================================================================
+
+/// Sequences of operations which leave the stack in the same state as before they are executed.
+const IDENTITIES: [&[OpCode]; 10] = [
+    &[OpCode::Dup,   OpCode::Drop],
+    &[OpCode::Dup2,  OpCode::Drop,  OpCode::Drop],
+    &[OpCode::Dup4,  OpCode::Drop4],
+    &[OpCode::Pad2,  OpCode::Drop,  OpCode::Drop],
+    &[OpCode::Swap,  OpCode::Swap],
+    &[OpCode::Swap2, OpCode::Swap2],
+    &[OpCode::Swap4, OpCode::Swap4],
+    &[OpCode::Neg,   OpCode::Neg],
+    &[OpCode::Roll4; 4],
+    &[OpCode::Roll8; 8],
+];
+
+/// Length of the longest sequence which can be removed by a single rewrite.
+const MAX_RULE_LENGTH: usize = 8;
+
+// OPTIMIZER
+// ================================================================================================
+
+/// Returns a program which leaves the stack in the same state as the original program, but
+/// usually executes in fewer steps. Within every span, sequences of operations which do not
+/// change the stack (e.g. `dup drop`, `swap swap`, or `push.0 add`) are removed, and the
+/// remaining operations are re-packed with the minimum number of NOOPs needed for alignment.
+///
+/// Operations which can fail on the values they are executed against (e.g. `assert` or `not`)
+/// and operations which read secret inputs are never removed. However, removed sequences can
+/// fail on stack depth: `dup drop` overflows a full stack, and `swap swap` underflows a stack
+/// with fewer than 2 items. So, an optimized program may succeed where the original program
+/// fails with a stack overflow or underflow; this cannot happen for programs which pass
+/// `Program::analyze_stack()`. The hash of the optimized program differs from the original hash.
+pub fn optimize(program: &Program) -> Program {
+    let root = optimize_blocks(program.root().body(), &PROGRAM_PREFIX);
+    return Program::new(Group::new(root));
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Optimizes a sequence of blocks; `prefix` specifies operations which the first span of the
+/// sequence must start with.
+fn optimize_blocks(blocks: &[ProgramBlock], prefix: &[OpCode]) -> Vec<ProgramBlock> {
+    let mut result = Vec::with_capacity(blocks.len());
+    for (i, block) in blocks.iter().enumerate() {
+        match block {
+            ProgramBlock::Span(span) => {
+                // a sequence of blocks must start with a span, but spans between other blocks
+                // can be dropped if all their operations were optimized away
+                let prefix_length = if i == 0 { prefix.len() } else { 0 };
+                let ops = optimize_span(span, prefix_length);
+                if i == 0 || ops.len() > 0 {
+                    result.push(ProgramBlock::Span(pack_ops(&ops)));
+                }
+            },
+            ProgramBlock::Group(block) => {
+                result.push(Group::new_block(optimize_blocks(block.body(), &[])));
+            },
+            ProgramBlock::Switch(block) => {
+                let true_branch = optimize_blocks(block.true_branch(), &TRUE_BRANCH_PREFIX);
+                let false_branch = optimize_blocks(block.false_branch(), &FALSE_BRANCH_PREFIX);
+                result.push(Switch::new_block(true_branch, false_branch));
+            },
+            ProgramBlock::Loop(block) => {
+                result.push(Loop::new_block(optimize_blocks(block.body(), &LOOP_BODY_PREFIX)));
+            },
+        }
+    }
+    return result;
+}
+
+/// Returns operations of the span with NOOPs and redundant sequences removed; every operation
+/// is returned together with its hint and its position in the original span. The first
+/// `prefix_length` operations are left as is.
+fn optimize_span(span: &Span, prefix_length: usize) -> Vec<(OpCode, OpHint, usize)> {
+    let mut ops = Vec::with_capacity(span.length());
+    for i in 0..span.length() {
+        let (op_code, op_hint) = span.get_op(i);
+        if i >= prefix_length && op_code == OpCode::Noop && op_hint == OpHint::None {
+            continue;
+        }
+        ops.push((op_code, op_hint, i));
+    }
+
+    // after every rewrite, step back far enough to catch sequences which the rewrite completed
+    let mut i = prefix_length;
+    while i < ops.len() {
+        match find_rewrite(&ops[i..]) {
+            Some(range) => {
+                ops.drain((i + range.0)..(i + range.1));
+                i = cmp::max(i.saturating_sub(MAX_RULE_LENGTH - 1), prefix_length);
+            },
+            None => i += 1,
+        }
+    }
+
+    return ops;
+}
+
+/// If the operations start with a sequence which can be simplified, returns the range of
+/// operations (relative to the start of the sequence) which can be removed.
+fn find_rewrite(ops: &[(OpCode, OpHint, usize)]) -> Option<(usize, usize)> {
+    let op_codes: Vec<OpCode> = ops.iter()
+        .take(MAX_RULE_LENGTH)
+        .take_while(|&&(op_code, op_hint, _)| op_hint == OpHint::None || op_code == OpCode::Push)
+        .map(|&(op_code, _, _)| op_code)
+        .collect();
+
+    for identity in IDENTITIES.iter() {
+        if op_codes.starts_with(identity) {
+            return Some((0, identity.len()));
+        }
+    }
+
+    return match (op_codes.get(0), op_codes.get(1)) {
+        // pushing a value and dropping it right away does nothing
+        (Some(OpCode::Push), Some(OpCode::Drop)) => Some((0, 2)),
+        // adding 0 or multiplying by 1 does not change the top of the stack
+        (Some(OpCode::Push), Some(OpCode::Add)) if ops[0].1.value() == 0 => Some((0, 2)),
+        (Some(OpCode::Push), Some(OpCode::Mul)) if ops[0].1.value() == 1 => Some((0, 2)),
+        // the top two items are equal after DUP, so swapping them does nothing
+        (Some(OpCode::Dup), Some(OpCode::Swap)) => Some((1, 2)),
+        _ => None,
+    };
+}
+
+/// Builds a span from the operations inserting as few NOOPs as possible: PUSH operations must
+/// be placed on steps which are multiples of 8, and RESCR operations must keep their positions
+/// within a cycle because round constants depend on them.
+fn pack_ops(ops: &[(OpCode, OpHint, usize)]) -> Span {
+    let mut op_codes = Vec::with_capacity(ops.len());
+    let mut op_hints = HintMap::new();
+    for &(op_code, op_hint, position) in ops.iter() {
+        match op_code {
+            OpCode::Push => {
+                while op_codes.len() % PUSH_OP_ALIGNMENT != 0 {
+                    op_codes.push(OpCode::Noop);
+                }
+            },
+            OpCode::RescR => {
+                while op_codes.len() % BASE_CYCLE_LENGTH != position % BASE_CYCLE_LENGTH {
+                    op_codes.push(OpCode::Noop);
+                }
+            },
+            _ => (),
+        }
+        if op_hint != OpHint::None {
+            op_hints.insert(op_codes.len(), op_hint);
+        }
+        op_codes.push(op_code);
+    }
+
+    // spans must be one operation short of a multiple of cycle length
+    while op_codes.len() % BASE_CYCLE_LENGTH != BASE_CYCLE_LENGTH - 1 {
+        op_codes.push(OpCode::Noop);
+    }
+    return Span::new(op_codes, op_hints);
+}
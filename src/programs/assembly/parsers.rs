@@ -1,4 +1,4 @@
-use crate::{ math::field };
+use crate::{ math::field, processor::opcodes::{ MIN_PMPATH_DEPTH, MAX_PMPATH_DEPTH } };
 use super::{ AssemblyError, HintMap, OpCode, OpHint };
 use sp_std::{vec, vec::Vec};
 
@@ -19,9 +19,13 @@ pub fn parse_noop(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result
     return Ok(true);
 }
 
-/// Appends either ASSERT or ASSERTEQ operations to the program.
-pub fn parse_assert(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
-    if op.len() > 2 {
+/// Appends either ASSERT or ASSERTEQ operations to the program, or a sequence of operations
+/// which asserts that the top value on the stack is within a range.
+pub fn parse_assert(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() > 1 && op[1] == "range" {
+        return parse_assert_range(program, hints, op, step);
+    }
+    else if op.len() > 2 {
         return Err(AssemblyError::extra_param(op, step));
     }
     else if op.len() == 1 {
@@ -32,7 +36,7 @@ pub fn parse_assert(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Resu
     }
     else {
         return Err(AssemblyError::invalid_param_reason(op, step,
-            format!("parameter {} is invalid; allowed values are: [eq]", op[1])));
+            format!("parameter {} is invalid; allowed values are: [eq, range]", op[1])));
     }
     
     return Ok(true);
@@ -273,30 +277,8 @@ pub fn parse_ne(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], ste
 pub fn parse_gt(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
     // n is the number of bits sufficient to represent each value; if either of the
     // values does not fit into n bits, the operation fill fail.
-    let n = read_param(op, step)?;
-    if n < 4 || n > 128 {
-        return Err(AssemblyError::invalid_param_reason(op, step,
-            format!("parameter {} is invalid; value must be between 4 and 128", n)))
-    }
-
-    // prepare the stack
-    program.extend_from_slice(&[OpCode::Pad2, OpCode::Pad2, OpCode::Pad2, OpCode::Dup]);
-    let power_of_two = u128::pow(2, n - 1);
-    append_push_op(program, hints, power_of_two);
-
-    // add a hint indicating that value comparison is about to start
-    hints.insert(program.len(), OpHint::CmpStart(n));
-
-    // append CMP operations
-    program.resize(program.len() + (n as usize), OpCode::Cmp);
-
-    // compare binary aggregation values with the original values, and drop everything
-    // but the GT value from the stack
-    program.extend_from_slice(&[
-        OpCode::Drop4,    OpCode::Pad2,     OpCode::Swap4, OpCode::Roll4,
-        OpCode::AssertEq, OpCode::AssertEq, OpCode::Roll4, OpCode::Dup,
-        OpCode::Drop4
-    ]);
+    let n = read_cmp_param(op, step)?;
+    append_gt_ops(program, hints, n);
     return Ok(true);
 }
 
@@ -305,29 +287,104 @@ pub fn parse_gt(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], ste
 pub fn parse_lt(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
     // n is the number of bits sufficient to represent each value; if either of the
     // values does not fit into n bits, the operation fill fail.
-    let n = read_param(op, step)?;
+    let n = read_cmp_param(op, step)?;
+    append_lt_ops(program, hints, n);
+    return Ok(true);
+}
+
+/// Appends a sequence of operations to the program to determine whether the top value on the
+/// stack is less than or equal to the following value.
+pub fn parse_lte(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let n = read_cmp_param(op, step)?;
+
+    // a <= b is the same as !(a > b)
+    append_gt_ops(program, hints, n);
+    program.push(OpCode::Not);
+    return Ok(true);
+}
+
+/// Appends a sequence of operations to the program to determine whether the top value on the
+/// stack is greater than or equal to the following value.
+pub fn parse_gte(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let n = read_cmp_param(op, step)?;
+
+    // a >= b is the same as !(a < b)
+    append_lt_ops(program, hints, n);
+    program.push(OpCode::Not);
+    return Ok(true);
+}
+
+/// Appends a sequence of operations to the program to replace the top two values on the stack
+/// with the smaller of them.
+pub fn parse_min(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let n = read_cmp_param(op, step)?;
+
+    // compare copies of the values to keep the originals on the stack
+    program.push(OpCode::Dup2);
+    append_lt_ops(program, hints, n);
+
+    // move the LT value under the original values, and select the 1st value if it is smaller
+    program.extend_from_slice(&[OpCode::Dup, OpCode::Swap2, OpCode::Choose, OpCode::Swap, OpCode::Drop]);
+    return Ok(true);
+}
+
+/// Appends a sequence of operations to the program to replace the top two values on the stack
+/// with the greater of them.
+pub fn parse_max(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let n = read_cmp_param(op, step)?;
+
+    // compare copies of the values to keep the originals on the stack
+    program.push(OpCode::Dup2);
+    append_gt_ops(program, hints, n);
+
+    // move the GT value under the original values, and select the 1st value if it is greater
+    program.extend_from_slice(&[OpCode::Dup, OpCode::Swap2, OpCode::Choose, OpCode::Swap, OpCode::Drop]);
+    return Ok(true);
+}
+
+/// Appends a sequence of operations to the program to make sure that the top value on the
+/// stack is between the specified lower and upper bounds (both inclusive); the value is
+/// removed from the stack.
+fn parse_assert_range(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    // the instruction has the form assert.range.n.a.b
+    if op.len() < 5 {
+        return Err(AssemblyError::missing_param(op, step));
+    }
+    else if op.len() > 5 {
+        return Err(AssemblyError::extra_param(op, step));
+    }
+
+    // n is the number of bits sufficient to represent the value and both bounds
+    let n = match op[2].parse::<u32>() {
+        Ok(n) => n,
+        Err(_) => return Err(invalid_value(op, op[2], step))
+    };
     if n < 4 || n > 128 {
         return Err(AssemblyError::invalid_param_reason(op, step,
             format!("parameter {} is invalid; value must be between 4 and 128", n)))
     }
 
-    // prepare the stack
-    program.extend_from_slice(&[OpCode::Pad2, OpCode::Pad2, OpCode::Pad2, OpCode::Dup]);
-    let power_of_two = u128::pow(2, n - 1);
-    append_push_op(program, hints, power_of_two);
-
-    // add a hint indicating that value comparison is about to start
-    hints.insert(program.len(), OpHint::CmpStart(n));
+    let lower_bound = parse_value(op, op[3], step)?;
+    let upper_bound = parse_value(op, op[4], step)?;
+    if lower_bound > upper_bound {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("lower bound {} is greater than upper bound {}", lower_bound, upper_bound)));
+    }
+    if n < 128 && upper_bound >> n != 0 {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("upper bound {} cannot be represented with {} bits", upper_bound, n)));
+    }
 
-    // append CMP operations
-    program.resize(program.len() + (n as usize), OpCode::Cmp);
+    // make sure the value is not less than the lower bound, i.e. lower_bound > value is false
+    program.push(OpCode::Dup);
+    append_push_op(program, hints, lower_bound);
+    append_gt_ops(program, hints, n);
+    program.extend_from_slice(&[OpCode::Not, OpCode::Assert]);
 
-    // compare binary aggregation values with the original values, and drop everything
-    // but the LT value from the stack
-    program.extend_from_slice(&[
-        OpCode::Drop4,    OpCode::Pad2,     OpCode::Swap4, OpCode::Roll4,
-        OpCode::AssertEq, OpCode::AssertEq, OpCode::Dup,   OpCode::Drop4
-    ]);
+    // make sure the value is not greater than the upper bound, i.e. upper_bound < value is false
+    append_push_op(program, hints, upper_bound);
+    append_lt_ops(program, hints, n);
+    program.extend_from_slice(&[OpCode::Not, OpCode::Assert]);
     return Ok(true);
 }
 
@@ -488,9 +545,9 @@ pub fn parse_smpath(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Resu
 /// path for a tree of depth n. Leaf index is expected to be 3rd item from the top of the stack.
 pub fn parse_pmpath(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
     let n = read_param(op, step)?;
-    if n < 2 || n > 256 {
+    if n < MIN_PMPATH_DEPTH || n > MAX_PMPATH_DEPTH {
         return Err(AssemblyError::invalid_param_reason(op, step,
-            format!("parameter {} is invalid; value must be between 2 and 256", n)))
+            format!("parameter {} is invalid; value must be between {} and {}", n, MIN_PMPATH_DEPTH, MAX_PMPATH_DEPTH)))
     }
 
     // add a hint indicating that pmpath macro is about to begin
@@ -541,6 +598,61 @@ pub fn parse_pmpath(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str],
 // HELPER FUNCTIONS
 // ================================================================================================
 
+/// Appends a sequence of operations which replaces the top two values on the stack with 1 if
+/// the 1st value is greater than the 2nd, and with 0 otherwise; both values must fit into n bits.
+fn append_gt_ops(program: &mut Vec<OpCode>, hints: &mut HintMap, n: u32) {
+    // prepare the stack
+    program.extend_from_slice(&[OpCode::Pad2, OpCode::Pad2, OpCode::Pad2, OpCode::Dup]);
+    let power_of_two = u128::pow(2, n - 1);
+    append_push_op(program, hints, power_of_two);
+
+    // add a hint indicating that value comparison is about to start
+    hints.insert(program.len(), OpHint::CmpStart(n));
+
+    // append CMP operations
+    program.resize(program.len() + (n as usize), OpCode::Cmp);
+
+    // compare binary aggregation values with the original values, and drop everything
+    // but the GT value from the stack
+    program.extend_from_slice(&[
+        OpCode::Drop4,    OpCode::Pad2,     OpCode::Swap4, OpCode::Roll4,
+        OpCode::AssertEq, OpCode::AssertEq, OpCode::Roll4, OpCode::Dup,
+        OpCode::Drop4
+    ]);
+}
+
+/// Appends a sequence of operations which replaces the top two values on the stack with 1 if
+/// the 1st value is less than the 2nd, and with 0 otherwise; both values must fit into n bits.
+fn append_lt_ops(program: &mut Vec<OpCode>, hints: &mut HintMap, n: u32) {
+    // prepare the stack
+    program.extend_from_slice(&[OpCode::Pad2, OpCode::Pad2, OpCode::Pad2, OpCode::Dup]);
+    let power_of_two = u128::pow(2, n - 1);
+    append_push_op(program, hints, power_of_two);
+
+    // add a hint indicating that value comparison is about to start
+    hints.insert(program.len(), OpHint::CmpStart(n));
+
+    // append CMP operations
+    program.resize(program.len() + (n as usize), OpCode::Cmp);
+
+    // compare binary aggregation values with the original values, and drop everything
+    // but the LT value from the stack
+    program.extend_from_slice(&[
+        OpCode::Drop4,    OpCode::Pad2,     OpCode::Swap4, OpCode::Roll4,
+        OpCode::AssertEq, OpCode::AssertEq, OpCode::Dup,   OpCode::Drop4
+    ]);
+}
+
+/// Reads the number of bits for a comparison operation; the number must be between 4 and 128.
+fn read_cmp_param(op: &[&str], step: usize) -> Result<u32, AssemblyError> {
+    let n = read_param(op, step)?;
+    if n < 4 || n > 128 {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; value must be between 4 and 128", n)))
+    }
+    return Ok(n);
+}
+
 fn read_param(op: &[&str], step: usize) -> Result<u32, AssemblyError> {
     if op.len() == 1 {
         // if no parameters were provided, assume parameter value 1
@@ -573,18 +685,22 @@ fn read_value(op: &[&str], step: usize) -> Result<u128, AssemblyError> {
         return Err(AssemblyError::extra_param(op, step));
     }
 
-    let result = if op[1].starts_with("0x") {
+    return parse_value(op, op[1], step);
+}
+
+fn parse_value(op: &[&str], param: &str, step: usize) -> Result<u128, AssemblyError> {
+    let result = if param.starts_with("0x") {
         // parse hexadecimal number
-        match u128::from_str_radix(&op[1][2..], 16) {
+        match u128::from_str_radix(&param[2..], 16) {
             Ok(i) => i,
-            Err(_) => return Err(AssemblyError::invalid_param(op, step))
+            Err(_) => return Err(invalid_value(op, param, step))
         }
     }
     else {
         // parse decimal number
-        match u128::from_str_radix(&op[1], 10) {
+        match u128::from_str_radix(param, 10) {
             Ok(i) => i,
-            Err(_) => return Err(AssemblyError::invalid_param(op, step))
+            Err(_) => return Err(invalid_value(op, param, step))
         }
     };
 
@@ -595,4 +711,10 @@ fn read_value(op: &[&str], step: usize) -> Result<u128, AssemblyError> {
     }
 
     return Ok(result);
-}
\ No newline at end of file
+}
+
+/// Returns an error for an instruction parameter which could not be parsed; unlike
+/// `AssemblyError::invalid_param()`, the parameter does not need to be the first one.
+fn invalid_value(op: &[&str], param: &str, step: usize) -> AssemblyError {
+    return AssemblyError::invalid_param_reason(op, step, format!("parameter '{}' is invalid", param));
+}
@@ -0,0 +1,245 @@
+use sp_std::{ ops::Range, vec, vec::Vec };
+use super::{ AssemblyError, parse_block };
+use super::modules::{ Imports, is_identifier };
+
+// CONSTANTS
+// ================================================================================================
+
+/// Maximum number of tokens which can be produced by inlining procedure calls; every call is
+/// replaced with a copy of the procedure body, so without a limit a few nested calls could
+/// make the compiler exhaust memory.
+pub const MAX_INLINED_TOKENS: usize = 1 << 20;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A named sequence of tokens defined with `proc.name ... end` (or `export.name ... end` for
+/// procedures which can be called from other modules) before the program body.
+pub struct Procedure<'a> {
+    pub name    : &'a str,
+    pub header  : usize,
+    pub body    : Range<usize>,
+    pub exported: bool,
+}
+
+// PROCEDURE PARSERS
+// ================================================================================================
+
+/// Reads procedure definitions from the token stream starting at token `start`; returns the
+/// list of defined procedures and the index of the first token after the last procedure.
+pub fn parse_procedures<'a>(tokens: &[&'a str], start: usize) -> Result<(Vec<Procedure<'a>>, usize), AssemblyError> {
+    let mut procedures: Vec<Procedure> = Vec::new();
+
+    let mut i = start;
+    while i < tokens.len() {
+        let head: Vec<&str> = tokens[i].split(".").collect();
+        if head[0] != "proc" && head[0] != "export" { break; }
+
+        let name = read_proc_name(&head, i, false)?;
+        if find_procedure(&procedures, name).is_some() {
+            return Err(AssemblyError::duplicate_proc(name, i));
+        }
+
+        let end = match find_block_end(tokens, i) {
+            Some(end) => end,
+            None => return Err(AssemblyError::unmatched_proc(name, i)),
+        };
+
+        procedures.push(Procedure { name, header: i, body: (i + 1)..end, exported: head[0] == "export" });
+        i = end + 1;
+    }
+
+    return Ok((procedures, i));
+}
+
+/// Makes sure the body of every procedure is a valid program block, even if the procedure
+/// is never called; this also detects recursion in procedures which are never called. Bodies
+/// are validated without inlining calls, so every procedure is examined only once.
+pub fn validate_procedures<'a>(tokens: &[&'a str], procedures: &[Procedure<'a>], imports: &'a Imports) -> Result<(), AssemblyError> {
+    for procedure in procedures.iter() {
+        // wrap procedure body into a block, and map synthetic tokens to procedure header and end;
+        // calls are replaced with NOOPs since they do not affect the structure of the block
+        let mut ops = vec!["block"];
+        let mut steps = vec![procedure.header];
+        for i in procedure.body.clone() {
+            let op: Vec<&str> = tokens[i].split(".").collect();
+            match op[0] {
+                "exec" if op.len() == 2 && op[1].contains("::") => {
+                    let name = read_proc_name(&op, i, true)?;
+                    if imports.get(name).is_none() {
+                        return Err(AssemblyError::undefined_proc(name, i));
+                    }
+                    ops.push("noop");
+                },
+                "exec" => {
+                    let name = read_proc_name(&op, i, false)?;
+                    if find_procedure(procedures, name).is_none() {
+                        return Err(AssemblyError::undefined_proc(name, i));
+                    }
+                    ops.push("noop");
+                },
+                "proc" | "export" => return Err(AssemblyError::misplaced_proc(&op, i)),
+                "use" => return Err(AssemblyError::misplaced_import(&op, i)),
+                _ => ops.push(tokens[i]),
+            }
+            steps.push(i);
+        }
+        ops.push("end");
+        steps.push(procedure.body.end);
+
+        let mut blocks = Vec::new();
+        parse_block(&mut blocks, &mut Vec::new(), &ops, 0).map_err(|err| err.map_step(&steps))?;
+    }
+
+    // follow calls from every procedure; procedures which were already checked are skipped
+    let mut checked = vec![false; procedures.len()];
+    for (index, procedure) in procedures.iter().enumerate() {
+        if !checked[index] {
+            let mut call_stack = vec![procedure.name];
+            check_recursion(tokens, procedures, index, &mut call_stack, &mut checked)?;
+        }
+    }
+    return Ok(());
+}
+
+/// Copies tokens from the specified range into `ops` replacing every `exec.name` instruction
+/// with the body of the named procedure, and every `exec.module::name` instruction with the
+/// body of the imported procedure; for every token appended to `ops`, the index of the token
+/// in the original stream is appended to `steps` (tokens of imported procedures are mapped
+/// to the index of the `exec` instruction). Fails if `ops` would grow beyond
+/// `MAX_INLINED_TOKENS` tokens.
+pub fn inline_calls<'a>(
+    tokens      : &[&'a str],
+    range       : Range<usize>,
+    procedures  : &[Procedure<'a>],
+    imports     : &'a Imports,
+    call_stack  : &mut Vec<&'a str>,
+    ops         : &mut Vec<&'a str>,
+    steps       : &mut Vec<usize>) -> Result<(), AssemblyError>
+{
+    for i in range {
+        let op: Vec<&str> = tokens[i].split(".").collect();
+        match op[0] {
+            "exec" if op.len() == 2 && op[1].contains("::") => {
+                let name = read_proc_name(&op, i, true)?;
+                let body = match imports.get(name) {
+                    Some(body) => body,
+                    None => return Err(AssemblyError::undefined_proc(name, i)),
+                };
+                if ops.len() + body.len() > MAX_INLINED_TOKENS {
+                    return Err(AssemblyError::inlining_limit_exceeded(MAX_INLINED_TOKENS, i, tokens[i]));
+                }
+                for token in body.iter() {
+                    ops.push(token.as_str());
+                    steps.push(i);
+                }
+            },
+            "exec" => {
+                let name = read_proc_name(&op, i, false)?;
+                let procedure = match find_procedure(procedures, name) {
+                    Some(procedure) => procedure,
+                    None => return Err(AssemblyError::undefined_proc(name, i)),
+                };
+                if call_stack.contains(&name) {
+                    return Err(AssemblyError::recursive_proc(name, call_stack, i));
+                }
+
+                call_stack.push(name);
+                inline_calls(tokens, procedure.body.clone(), procedures, imports, call_stack, ops, steps)?;
+                call_stack.pop();
+            },
+            "proc" | "export" => return Err(AssemblyError::misplaced_proc(&op, i)),
+            "use" => return Err(AssemblyError::misplaced_import(&op, i)),
+            _ => {
+                if ops.len() >= MAX_INLINED_TOKENS {
+                    return Err(AssemblyError::inlining_limit_exceeded(MAX_INLINED_TOKENS, i, tokens[i]));
+                }
+                ops.push(tokens[i]);
+                steps.push(i);
+            }
+        }
+    }
+    return Ok(());
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Follows local calls made by the procedure at `index`, and fails if any of them leads back
+/// to a procedure on the `call_stack`; procedures marked as checked are not followed again.
+/// Procedure bodies must have been validated.
+fn check_recursion<'a>(
+    tokens      : &[&'a str],
+    procedures  : &[Procedure<'a>],
+    index       : usize,
+    call_stack  : &mut Vec<&'a str>,
+    checked     : &mut Vec<bool>) -> Result<(), AssemblyError>
+{
+    for i in procedures[index].body.clone() {
+        let op: Vec<&str> = tokens[i].split(".").collect();
+        if op[0] != "exec" || op[1].contains("::") { continue; }
+
+        let name = op[1];
+        if call_stack.contains(&name) {
+            return Err(AssemblyError::recursive_proc(name, call_stack, i));
+        }
+
+        let callee = procedures.iter().position(|procedure| procedure.name == name).unwrap();
+        if !checked[callee] {
+            call_stack.push(name);
+            check_recursion(tokens, procedures, callee, call_stack, checked)?;
+            call_stack.pop();
+        }
+    }
+    checked[index] = true;
+    return Ok(());
+}
+
+fn find_procedure<'a, 'b>(procedures: &'b [Procedure<'a>], name: &str) -> Option<&'b Procedure<'a>> {
+    return procedures.iter().find(|procedure| procedure.name == name);
+}
+
+/// Returns the index of the `end` token which closes the block started at `start`; returns
+/// None if the block is not closed before the program body or another procedure begins.
+fn find_block_end(tokens: &[&str], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for i in (start + 1)..tokens.len() {
+        let head = tokens[i].split(".").next().unwrap_or("");
+        match head {
+            "block" | "if" | "repeat" | "while" => depth += 1,
+            "end" => {
+                if depth == 0 { return Some(i); }
+                depth -= 1;
+            },
+            "begin" | "proc" | "export" | "use" => return None,
+            _ => (),
+        }
+    }
+    return None;
+}
+
+/// Reads procedure name from `proc.name`, `export.name`, or `exec.name` instruction; the name
+/// must be a valid identifier. If `qualified` is true, the name must have the form
+/// `module::name`, where both parts are valid identifiers.
+fn read_proc_name<'a>(op: &[&'a str], step: usize, qualified: bool) -> Result<&'a str, AssemblyError> {
+    if op.len() == 1 {
+        return Err(AssemblyError::missing_param(op, step));
+    }
+    else if op.len() > 2 {
+        return Err(AssemblyError::extra_param(op, step));
+    }
+
+    let name = op[1];
+    let valid = if qualified {
+        let parts: Vec<&str> = name.split("::").collect();
+        parts.len() == 2 && parts.iter().all(|&part| is_identifier(part))
+    }
+    else {
+        is_identifier(name)
+    };
+    if !valid {
+        return Err(AssemblyError::invalid_param(op, step));
+    }
+
+    return Ok(name);
+}
@@ -0,0 +1,143 @@
+use alloc::string::{ String, ToString };
+use hashbrown::HashMap;
+use sp_std::vec::Vec;
+use super::{ SourceLocation, tokenizer::Token };
+use crate::programs::blocks::OpLocation;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Maps operations of a compiled program back to the assembly instructions which produced
+/// them. Operations produced by macro instructions (e.g. `gt.N` or `smpath.N`) map to the macro
+/// instruction, operations inlined from procedures map to the instruction in the procedure
+/// body (or to `exec` instruction for procedures imported from other modules), and operations
+/// which start branches map to the instruction which opened the block. NOOPs added to pad
+/// spans are not mapped.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    instructions: Vec<SourceInstruction>,
+    spans       : Vec<Vec<Option<usize>>>,
+}
+
+/// An instruction in assembly source code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceInstruction {
+    pub token   : String,
+    pub location: SourceLocation,
+}
+
+/// Indexes of tokens which produced operations of program blocks; mirrors the structure of
+/// program blocks built by the parser.
+#[derive(Clone)]
+pub enum SourceBlock {
+    Span(Vec<Option<usize>>),
+    Group(Vec<SourceBlock>),
+    Switch(Vec<SourceBlock>, Vec<SourceBlock>),
+    Loop(Vec<SourceBlock>),
+}
+
+// SOURCE MAP IMPLEMENTATION
+// ================================================================================================
+impl SourceMap {
+
+    /// Builds a source map from token indexes of every span operation; spans must be listed in
+    /// program order (see `OpLocation`), and `steps` maps token indexes to `tokens`.
+    pub(super) fn new(spans: Vec<Vec<Option<usize>>>, steps: &[usize], tokens: &[Token]) -> SourceMap {
+        let mut instructions = Vec::new();
+        let mut instruction_idx: HashMap<usize, usize> = HashMap::new();
+
+        // every token is stored once, no matter how many operations it produced
+        let mut result = Vec::with_capacity(spans.len());
+        for span in spans.into_iter() {
+            let mut ops = Vec::with_capacity(span.len());
+            for step in span.into_iter() {
+                let idx = match step.map(|step| steps[step]) {
+                    Some(step) => match instruction_idx.get(&step) {
+                        Some(&idx) => Some(idx),
+                        None => {
+                            instructions.push(SourceInstruction {
+                                token   : tokens[step].text.to_string(),
+                                location: tokens[step].location,
+                            });
+                            instruction_idx.insert(step, instructions.len() - 1);
+                            Some(instructions.len() - 1)
+                        },
+                    },
+                    None => None,
+                };
+                ops.push(idx);
+            }
+            result.push(ops);
+        }
+
+        return SourceMap { instructions, spans: result };
+    }
+
+    /// Returns the instruction which produced the operation at the specified location, or None
+    /// if the operation was added by the assembler for alignment.
+    pub fn get(&self, location: OpLocation) -> Option<&SourceInstruction> {
+        return match self.spans.get(location.span).and_then(|span| span.get(location.op)) {
+            Some(&Some(idx)) => Some(&self.instructions[idx]),
+            _ => None,
+        };
+    }
+
+    /// Returns the instruction which produced the operation executed at the specified step of
+    /// an execution trace; `locations` must be the locations of operations at every step of the
+    /// trace as returned by `trace_locations()`.
+    pub fn get_step(&self, locations: &[Option<OpLocation>], step: usize) -> Option<&SourceInstruction> {
+        return match locations.get(step) {
+            Some(&Some(location)) => self.get(location),
+            _ => None,
+        };
+    }
+
+    /// Returns the number of spans in the program.
+    pub fn num_spans(&self) -> usize {
+        return self.spans.len();
+    }
+}
+
+// SOURCE BLOCK IMPLEMENTATION
+// ================================================================================================
+impl SourceBlock {
+
+    pub fn is_span(&self) -> bool {
+        return match self {
+            SourceBlock::Span(_) => true,
+            _ => false,
+        };
+    }
+
+    /// Merges two spans in the same way as `Span::merge()` does, i.e. with a NOOP between them.
+    pub fn merge(span1: &SourceBlock, span2: &SourceBlock) -> SourceBlock {
+        return match (span1, span2) {
+            (SourceBlock::Span(steps1), SourceBlock::Span(steps2)) => {
+                let mut steps = steps1.clone();
+                steps.push(None);
+                steps.extend_from_slice(steps2);
+                SourceBlock::Span(steps)
+            },
+            _ => panic!("only spans can be merged"),
+        };
+    }
+}
+
+/// Lists token indexes of operations for every span in program order, replacing every index
+/// with the one at the corresponding position in `steps`.
+pub fn flatten_source_blocks(blocks: &[SourceBlock], steps: &[usize], result: &mut Vec<Vec<Option<usize>>>) {
+    for block in blocks.iter() {
+        match block {
+            SourceBlock::Span(span) => {
+                result.push(span.iter().map(|step| step.map(|step| steps[step])).collect());
+            },
+            SourceBlock::Group(body) | SourceBlock::Loop(body) => {
+                flatten_source_blocks(body, steps, result);
+            },
+            SourceBlock::Switch(true_branch, false_branch) => {
+                flatten_source_blocks(true_branch, steps, result);
+                flatten_source_blocks(false_branch, steps, result);
+            },
+        }
+    }
+}
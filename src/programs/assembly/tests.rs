@@ -400,4 +400,463 @@ fn repeat_2_blocks_with_suffix() {
     end";
 
     assert_eq!(expected, format!("{:?}", program));
-}
\ No newline at end of file
+}
+
+// COMMENTS
+// ================================================================================================
+#[test]
+fn line_and_block_comments() {
+    let source = "
+    # computes 1 + 2
+    begin
+        push.1 push.2   # push operands
+        #[ operands are now
+           on top of the stack ]#
+        add#[ inline ]#
+    end # trailing comment";
+    let program = super::compile(source).unwrap();
+    let expected = super::compile("begin push.1 push.2 add end").unwrap();
+    assert_eq!(format!("{:?}", expected), format!("{:?}", program));
+}
+
+#[test]
+fn unmatched_block_comment() {
+    let source = "begin push.1\n  #[ push.2\nadd end";
+    let err = super::compile(source).err().unwrap();
+    assert_eq!(Some(super::SourceLocation { line: 2, col: 3 }), err.location());
+    assert_eq!("  #[ push.2", err.snippet());
+}
+
+// ERROR LOCATIONS
+// ================================================================================================
+#[test]
+fn error_location() {
+    let source = "begin\n    push.1\n    pushh.2 add\nend";
+    let err = super::compile(source).err().unwrap();
+    assert_eq!(Some(super::SourceLocation { line: 3, col: 5 }), err.location());
+    assert_eq!("    pushh.2 add", err.snippet());
+
+    let expected = "\
+        assembly error at 3:5: instruction pushh.2 is invalid\n  \
+         |\n\
+        3 |     pushh.2 add\n  \
+         |     ^^^^^^^";
+    assert_eq!(expected, format!("{}", err));
+    assert_eq!(expected, format!("{:?}", err));
+
+    // the marker is aligned with the offending token when the line is indented with tabs
+    let source = "begin\n\t\tpushh.2 add\nend";
+    let err = super::compile(source).err().unwrap();
+    assert_eq!(Some(super::SourceLocation { line: 2, col: 3 }), err.location());
+    assert!(format!("{}", err).ends_with("2 | \t\tpushh.2 add\n  | \t\t^^^^^^^"));
+
+    // location is reported even when comments precede the offending token
+    let source = "begin #[ a\ncomment ]# add else end";
+    let err = super::compile(source).err().unwrap();
+    assert_eq!(Some(super::SourceLocation { line: 2, col: 16 }), err.location());
+
+    // errors without source tokens have no location
+    let err = super::compile("# nothing here").err().unwrap();
+    assert_eq!(None, err.location());
+}
+
+// PROCEDURES
+// ================================================================================================
+#[test]
+fn procedure_calls() {
+    let source = "
+    proc.add_three
+        push.3 add
+    end
+    proc.add_six
+        exec.add_three exec.add_three
+    end
+    begin
+        read exec.add_six
+        if.true
+            exec.add_three
+        end
+    end";
+    let program = super::compile(source).unwrap();
+
+    let expected = super::compile("
+    begin
+        read push.3 add push.3 add
+        if.true
+            push.3 add
+        end
+    end").unwrap();
+    assert_eq!(format!("{:?}", expected), format!("{:?}", program));
+}
+
+#[test]
+fn procedure_errors() {
+    // undefined procedure
+    let err = super::compile("begin exec.foo end").err().unwrap();
+    assert_eq!("procedure 'foo' is not defined", err.message().as_str());
+    assert_eq!(Some(super::SourceLocation { line: 1, col: 7 }), err.location());
+
+    // duplicate procedure
+    let err = super::compile("proc.foo add end proc.foo mul end begin exec.foo end").err().unwrap();
+    assert_eq!("procedure 'foo' is already defined", err.message().as_str());
+    assert_eq!(3, err.step());
+
+    // direct and indirect recursion is detected even in procedures which are never called
+    let err = super::compile("proc.foo add exec.foo end begin add end").err().unwrap();
+    assert_eq!("procedure 'foo' is called recursively: foo -> foo", err.message().as_str());
+
+    let err = super::compile("proc.foo exec.bar end proc.bar exec.foo end begin add end").err().unwrap();
+    assert_eq!("procedure 'foo' is called recursively: foo -> bar -> foo", err.message().as_str());
+    assert_eq!(4, err.step());
+
+    // procedure without end, and procedure defined inside program body
+    let err = super::compile("proc.foo add begin add end").err().unwrap();
+    assert_eq!("procedure 'foo' without matching end", err.message().as_str());
+
+    let err = super::compile("begin add end proc.foo add end").err().unwrap();
+    assert_eq!("procedure proc.foo must be defined before program begin", err.message().as_str());
+
+    // errors inside procedure bodies point to the procedure source
+    let err = super::compile("proc.foo\n  add pushh.1\nend\nbegin exec.foo end").err().unwrap();
+    assert_eq!(Some(super::SourceLocation { line: 2, col: 7 }), err.location());
+
+    // every procedure calls the previous one twice, so inlining doubles the program size
+    let mut source = String::from("proc.p0 add end ");
+    for i in 1..32 {
+        source.push_str(&format!("proc.p{} exec.p{} exec.p{} end ", i, i - 1, i - 1));
+    }
+    let err = super::compile(&format!("{} begin exec.p31 end", source)).err().unwrap();
+    assert_eq!(format!("inlining procedure calls produces more than {} instructions", super::procedures::MAX_INLINED_TOKENS),
+        err.message().as_str());
+
+    // such procedures are still valid if they are never called
+    assert!(super::compile(&format!("{} begin add end", source)).is_ok());
+}
+
+// CONSTANTS
+// ================================================================================================
+#[test]
+fn constants_and_expressions() {
+    let source = "
+    const.THRESHOLD=18
+    const.LIMIT=THRESHOLD*2+1
+    const.N=3
+    begin
+        push.THRESHOLD push.LIMIT push.2^64 push.MODULUS-1 push.(N+1)*0x10
+        repeat.N
+            dup.N-1 drop
+        end
+    end";
+    let program = super::compile(source).unwrap();
+
+    let expected = super::compile("
+    begin
+        push.18 push.37 push.18446744073709551616
+        push.340282366920938463463374557953744961536 push.64
+        repeat.3
+            dup.2 drop
+        end
+    end").unwrap();
+    assert_eq!(format!("{:?}", expected), format!("{:?}", program));
+}
+
+#[test]
+fn constant_errors() {
+    // values must be valid field elements
+    let err = super::compile("begin push.MODULUS end").err().unwrap();
+    assert_eq!(1, err.step());
+    assert!(err.message().contains("must be smaller than"));
+
+    let err = super::compile("begin push.2^128 end").err().unwrap();
+    assert_eq!("malformed instruction push: exponentiation overflow", err.message().as_str());
+
+    let err = super::compile("begin push.1-2 end").err().unwrap();
+    assert_eq!("malformed instruction push: subtraction underflow", err.message().as_str());
+
+    // undefined and duplicate constants
+    let err = super::compile("begin push.FOO end").err().unwrap();
+    assert_eq!("malformed instruction push: constant 'FOO' is not defined", err.message().as_str());
+
+    let err = super::compile("const.A=1 const.A=2 begin push.A end").err().unwrap();
+    assert_eq!("constant 'A' is already defined", err.message().as_str());
+    assert_eq!(Some(super::SourceLocation { line: 1, col: 11 }), err.location());
+
+    // constants must be declared before use and before program begins
+    let err = super::compile("const.A=B const.B=1 begin push.A end").err().unwrap();
+    assert_eq!("malformed instruction const: constant 'B' is not defined", err.message().as_str());
+
+    let err = super::compile("begin const.A=1 push.A end").err().unwrap();
+    assert_eq!("constant const.A=1 must be declared before program begin", err.message().as_str());
+
+    // errors after constant declarations point to the right token
+    let err = super::compile("const.A=1\nbegin push.A pushh end").err().unwrap();
+    assert_eq!(Some(super::SourceLocation { line: 2, col: 14 }), err.location());
+}
+
+// MODULES
+// ================================================================================================
+#[test]
+fn module_imports() {
+    let mut resolver = super::MemoryResolver::new();
+    resolver.insert("std::math", "
+    const.N=3
+    export.add_six
+        exec.add_three exec.add_three
+    end
+    proc.add_three
+        push.N add
+    end");
+    resolver.insert("std::logic", "
+    use.std::math
+    export.add_twelve
+        exec.math::add_six exec.math::add_six
+    end");
+
+    let source = "
+    use.std::math
+    use.std::logic
+    proc.add_eighteen
+        exec.math::add_six exec.logic::add_twelve
+    end
+    begin
+        read exec.add_eighteen
+    end";
+    let program = super::compile_with_resolver(source, &resolver).unwrap();
+
+    let expected = super::compile("
+    begin
+        read push.3 add push.3 add
+        push.3 add push.3 add push.3 add push.3 add
+    end").unwrap();
+    assert_eq!(format!("{:?}", expected), format!("{:?}", program));
+
+    // private procedures are not visible outside of the module
+    let err = super::compile_with_resolver("use.std::math begin exec.math::add_three end", &resolver).err().unwrap();
+    assert_eq!("procedure 'math::add_three' is not defined", err.message().as_str());
+    assert_eq!(Some(super::SourceLocation { line: 1, col: 21 }), err.location());
+
+    // modules cannot be imported without a resolver
+    let err = super::compile("use.std::math begin exec.math::add_six end").err().unwrap();
+    assert_eq!("module 'std::math' could not be found", err.message().as_str());
+}
+
+#[test]
+fn module_errors() {
+    let mut resolver = super::MemoryResolver::new();
+    resolver.insert("a", "use.b export.foo add end");
+    resolver.insert("b", "use.a export.bar add end");
+    resolver.insert("std::math", "export.foo add end");
+    resolver.insert("ext::math", "export.foo mul end");
+    resolver.insert("bad", "export.foo\n  add pushh\nend");
+    resolver.insert("program", "export.foo add end begin add end");
+
+    // import cycles are detected at the import which closes the cycle
+    let err = super::compile_with_resolver("use.a begin exec.a::foo end", &resolver).err().unwrap();
+    assert_eq!("module 'a' is imported cyclically: a -> b -> a", err.message().as_str());
+    assert_eq!(Some("b"), err.module());
+    assert_eq!(Some(super::SourceLocation { line: 1, col: 1 }), err.location());
+
+    // two modules cannot be imported under the same name
+    let err = super::compile_with_resolver("use.std::math use.ext::math begin add end", &resolver).err().unwrap();
+    assert_eq!("module 'math' is already imported", err.message().as_str());
+    assert_eq!(1, err.step());
+
+    // errors inside modules point to the module source
+    let err = super::compile_with_resolver("use.bad begin exec.bad::foo end", &resolver).err().unwrap();
+    assert_eq!(Some("bad"), err.module());
+    assert_eq!(Some(super::SourceLocation { line: 2, col: 7 }), err.location());
+
+    // modules cannot have a program body, and imports cannot follow procedures
+    let err = super::compile_with_resolver("use.program begin exec.program::foo end", &resolver).err().unwrap();
+    assert_eq!("a module may contain only imports, constants, and procedures, but found 'begin'", err.message().as_str());
+
+    let err = super::compile_with_resolver("proc.foo add end use.std::math begin add end", &resolver).err().unwrap();
+    assert_eq!("import use.std::math must precede procedure definitions and program begin", err.message().as_str());
+
+    // exported procedures are subject to the same inlining limit as the program
+    let mut source = String::from("export.p0 add end ");
+    for i in 1..32 {
+        source.push_str(&format!("export.p{} exec.p{} exec.p{} end ", i, i - 1, i - 1));
+    }
+    resolver.insert("huge", &source);
+    let err = super::compile_with_resolver("use.huge begin exec.huge::p0 end", &resolver).err().unwrap();
+    assert_eq!(Some("huge"), err.module());
+    assert_eq!(format!("inlining procedure calls produces more than {} instructions", super::procedures::MAX_INLINED_TOKENS),
+        err.message().as_str());
+}
+
+// COMPARISON INSTRUCTIONS
+// ================================================================================================
+#[test]
+fn comparison_instructions() {
+    // lte and gte negate results of gt and lt
+    let program = super::compile("begin lte.8 end").unwrap();
+    let expected = super::compile("begin gt.8 not end").unwrap();
+    assert_eq!(expected.hash(), program.hash());
+
+    let program = super::compile("begin gte.8 end").unwrap();
+    let expected = super::compile("begin lt.8 not end").unwrap();
+    assert_eq!(expected.hash(), program.hash());
+
+    // range assertion compares the value with both bounds
+    let program = super::compile("begin assert.range.8.3.10 end").unwrap();
+    let expected = super::compile("begin dup push.3 gt.8 not assert push.10 lt.8 not assert end").unwrap();
+    assert_eq!(expected.hash(), program.hash());
+}
+
+#[test]
+fn comparison_instruction_errors() {
+    let err = super::compile("begin min.130 end").err().unwrap();
+    assert_eq!("malformed instruction min: parameter 130 is invalid; value must be between 4 and 128", err.message().as_str());
+
+    let err = super::compile("begin assert.range.8.3 end").err().unwrap();
+    assert_eq!("malformed instruction assert: parameter is missing", err.message().as_str());
+
+    let err = super::compile("begin assert.range.8.x.10 end").err().unwrap();
+    assert_eq!("malformed instruction assert: parameter 'x' is invalid", err.message().as_str());
+
+    let err = super::compile("begin assert.range.8.10.3 end").err().unwrap();
+    assert_eq!("malformed instruction assert: lower bound 10 is greater than upper bound 3", err.message().as_str());
+
+    let err = super::compile("begin assert.range.8.3.256 end").err().unwrap();
+    assert_eq!("malformed instruction assert: upper bound 256 cannot be represented with 8 bits", err.message().as_str());
+}
+
+// DISASSEMBLER
+// ================================================================================================
+#[test]
+fn disassemble_round_trip() {
+    let source = "
+    begin
+        push.3 push.5 add read.ab read eq ne
+        hash.1 hash.3 dup.3 pick.2 pad.7 drop.3 sub div
+        if.true
+            gt.32 lt.16 lte.8 gte.16 min.32 max.8
+        else
+            rc.16 isodd.8
+        end
+        while.true
+            smpath.3 read.ab pmpath.4
+        end
+        repeat.3
+            noop mul
+        end
+        if.true add end
+        block block swap.2 end end
+    end";
+    let program = super::compile(source).unwrap();
+    let disassembled = program.to_assembly().unwrap();
+    let recompiled = super::compile(&disassembled).unwrap();
+    assert_eq!(program.hash(), recompiled.hash());
+
+    // macro instructions are folded back, and alignment NOOPs are omitted
+    for instruction in [
+        "hash.1", "hash.3", "gt.32", "lt.16", "lte.8", "gte.16", "min.32", "max.8", "rc.16", "isodd.8",
+        "smpath.3", "pmpath.4", "ne"
+    ].iter() {
+        assert!(disassembled.split_whitespace().any(|token| token == *instruction), "{} not found", instruction);
+    }
+
+    let program = super::compile("begin push.3 push.5 add end").unwrap();
+    assert_eq!("begin\n    push.3 push.5 add\nend", program.to_assembly().unwrap());
+}
+
+#[test]
+fn disassemble_inexpressible_op() {
+    use crate::programs::blocks::{ Group, Span };
+    use crate::processor::OpCode;
+
+    let mut op_codes = vec![OpCode::Begin, OpCode::CSwap2];
+    op_codes.resize(15, OpCode::Noop);
+    let program = crate::programs::Program::new(Group::new(vec![Span::new_block(op_codes)]));
+
+    let err = program.to_assembly().err().unwrap();
+    assert_eq!("operation cswap2 at step 1 of a span cannot be expressed in assembly", err.message().as_str());
+}
+
+// SOURCE MAPS
+// ================================================================================================
+#[test]
+fn source_map() {
+    use crate::programs::blocks::OpLocation;
+
+    let source = "\
+const.X=5
+proc.double dup add end
+begin
+    push.X exec.double
+    gt.8
+    if.true mul end
+    repeat.2 neg end
+end";
+    let (program, source_map) = super::compile_with_source_map(source, &super::MemoryResolver::new()).unwrap();
+    let num_spans: usize = program.root().body().iter().map(|block| block.span_count()).sum();
+    assert_eq!(4, num_spans);
+    assert_eq!(num_spans, source_map.num_spans());
+
+    let token = |span, op| source_map.get(OpLocation { span, op })
+        .map(|instruction| (instruction.token.as_str(), instruction.location.line, instruction.location.col));
+
+    // instructions are reported as written in the source, including procedure bodies
+    assert_eq!(Some(("begin", 3, 1)), token(0, 0));
+    assert_eq!(Some(("push.X", 4, 5)), token(0, 8));
+    assert_eq!(Some(("dup", 2, 13)), token(0, 9));
+    assert_eq!(Some(("add", 2, 17)), token(0, 10));
+    assert_eq!(Some(("gt.8", 5, 5)), token(0, 11));
+
+    // branch prefixes map to the block head, and padding is not mapped
+    assert_eq!(Some(("if.true", 6, 5)), token(1, 0));
+    assert_eq!(Some(("mul", 6, 13)), token(1, 1));
+    assert_eq!(None, token(1, 14));
+    assert_eq!(Some(("if.true", 6, 5)), token(2, 1));
+
+    // iterations of repeat blocks map to the same instruction
+    assert_eq!(Some(("neg", 7, 14)), token(3, 0));
+    assert_eq!(None, token(3, 15));
+    assert_eq!(Some(("neg", 7, 14)), token(3, 16));
+    assert_eq!(None, token(4, 0));
+}
+
+#[test]
+fn source_map_trace_steps() {
+    let source = "begin push.5 push.1 if.true push.2 else push.3 end add end";
+    let (program, source_map) = super::compile_with_source_map(source, &super::MemoryResolver::new()).unwrap();
+    let locations = crate::trace_locations(&program, &crate::ProgramInputs::none()).unwrap();
+
+    let instruction = source_map.get_step(&locations, 16).unwrap();
+    assert_eq!("push.1", instruction.token);
+    assert_eq!(super::SourceLocation { line: 1, col: 14 }, instruction.location);
+
+    // only the true branch is executed
+    let tokens: sp_std::vec::Vec<&str> = (0..locations.len())
+        .filter_map(|step| source_map.get_step(&locations, step))
+        .map(|instruction| instruction.token.as_str())
+        .collect();
+    assert!(tokens.contains(&"push.2"));
+    assert!(!tokens.contains(&"push.3"));
+    assert!(tokens.contains(&"add"));
+}
+
+// OPTIMIZER
+// ================================================================================================
+#[test]
+fn optimize_spans() {
+    // redundant sequences are removed, and remaining operations are re-packed
+    let program = super::compile("begin push.3 dup drop swap swap push.0 add push.1 mul push.4 drop neg neg dup swap add end").unwrap();
+    let expected = super::compile("begin push.3 dup add end").unwrap();
+    assert_eq!(expected.hash(), super::optimize(&program).hash());
+
+    // hashing rounds keep their alignment
+    let program = super::compile("begin push.1 dup drop hash.4 swap.2 swap.2 end").unwrap();
+    let expected = super::compile("begin push.1 hash.4 end").unwrap();
+    assert_eq!(expected.hash(), super::optimize(&program).hash());
+
+    // spans which become empty are removed, but branches keep their prefixes
+    let source = "begin push.1 if.true dup drop end roll.4 roll.4 roll.4 roll.4 while.true swap swap end end";
+    let expected = super::compile("begin push.1 if.true noop end while.true noop end end").unwrap();
+    assert_eq!(expected.hash(), super::compile_optimized(source).unwrap().hash());
+
+    // operations which may fail are not removed
+    let program = super::compile("begin not not inv inv end").unwrap();
+    assert_eq!(program.hash(), super::optimize(&program).hash());
+}
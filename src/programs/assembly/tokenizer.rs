@@ -0,0 +1,103 @@
+use super::AssemblyError;
+use sp_std::vec::Vec;
+
+// CONSTANTS
+// ================================================================================================
+const COMMENT_CHAR       : char = '#';
+const BLOCK_COMMENT_START: &str = "#[";
+const BLOCK_COMMENT_END  : &str = "]#";
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Location of a token in assembly source code; both line and column numbers start at 1.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub line    : usize,
+    pub col     : usize,
+}
+
+/// A single whitespace-delimited token of assembly source code.
+#[derive(Copy, Clone, Debug)]
+pub struct Token<'a> {
+    pub text    : &'a str,
+    pub location: SourceLocation,
+}
+
+// TOKENIZER
+// ================================================================================================
+
+/// Breaks assembly source code into tokens, skipping over comments. Line comments start with
+/// `#` and run until the end of the line; block comments start with `#[` and end with `]#`.
+pub fn tokenize(source: &str) -> Result<Vec<Token<'_>>, AssemblyError> {
+    let mut tokens = Vec::new();
+    let mut in_block_comment: Option<SourceLocation> = None;
+
+    for (line_idx, line) in source.lines().enumerate() {
+        let mut rest = line;
+        let mut offset = 0;
+
+        while rest.len() > 0 {
+            // if we are inside a block comment, skip everything until the end of the comment
+            if in_block_comment.is_some() {
+                match rest.find(BLOCK_COMMENT_END) {
+                    Some(end) => {
+                        let skip = end + BLOCK_COMMENT_END.len();
+                        offset += skip;
+                        rest = &rest[skip..];
+                        in_block_comment = None;
+                        continue;
+                    },
+                    None => break,
+                }
+            }
+
+            // skip leading whitespace
+            let trimmed = rest.trim_start();
+            offset += rest.len() - trimmed.len();
+            rest = trimmed;
+            if rest.len() == 0 { break; }
+
+            // handle comments
+            if rest.starts_with(BLOCK_COMMENT_START) {
+                in_block_comment = Some(SourceLocation { line: line_idx + 1, col: get_column(line, offset) });
+                offset += BLOCK_COMMENT_START.len();
+                rest = &rest[BLOCK_COMMENT_START.len()..];
+                continue;
+            }
+            else if rest.starts_with(COMMENT_CHAR) {
+                break;
+            }
+
+            // read the token until the next whitespace or comment
+            let end = rest.find(|c: char| c.is_whitespace() || c == COMMENT_CHAR)
+                .unwrap_or(rest.len());
+            tokens.push(Token {
+                text    : &rest[..end],
+                location: SourceLocation { line: line_idx + 1, col: get_column(line, offset) },
+            });
+            offset += end;
+            rest = &rest[end..];
+        }
+    }
+
+    // make sure all block comments were closed
+    if let Some(location) = in_block_comment {
+        return Err(AssemblyError::unmatched_comment(location, get_line(source, location.line)));
+    }
+
+    return Ok(tokens);
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns the source line with the specified number (starting at 1).
+pub fn get_line(source: &str, line: usize) -> &str {
+    return source.lines().nth(line - 1).unwrap_or("");
+}
+
+/// Converts a byte offset within a line into a column number (starting at 1).
+fn get_column(line: &str, offset: usize) -> usize {
+    return line[..offset].chars().count() + 1;
+}
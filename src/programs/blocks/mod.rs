@@ -45,18 +45,31 @@ pub struct Span {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Group {
     body        : Vec<ProgramBlock>,
+    spans       : usize,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Switch {
     t_branch    : Vec<ProgramBlock>,
     f_branch    : Vec<ProgramBlock>,
+    t_spans     : usize,
+    f_spans     : usize,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Loop {
     body        : Vec<ProgramBlock>,
     skip        : Vec<ProgramBlock>,
+    spans       : usize,
+}
+
+/// Location of an operation in a program. Spans are numbered in the order in which they appear
+/// in the program (spans of true branches precede spans of false branches); skip blocks of
+/// loops are not numbered.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OpLocation {
+    pub span    : usize,
+    pub op      : usize,
 }
 
 // PROGRAM BLOCK IMPLEMENTATION
@@ -71,6 +84,16 @@ impl ProgramBlock {
         };
     }
 
+    /// Returns the number of spans in this block and all of its nested blocks, excluding skip
+    /// blocks of loops; the count is computed once when the block is created.
+    pub fn span_count(&self) -> usize {
+        return match self {
+            ProgramBlock::Span(_) => 1,
+            ProgramBlock::Group(block) => block.spans,
+            ProgramBlock::Switch(block) => block.t_spans + block.f_spans,
+            ProgramBlock::Loop(block) => block.spans,
+        };
+    }
 }
 
 impl sp_std::fmt::Debug for ProgramBlock {
@@ -199,7 +222,8 @@ impl Group {
 
     pub fn new(body: Vec<ProgramBlock>) -> Group {
         validate_block_list(&body, &[]);
-        return Group { body };
+        let spans = count_spans(&body);
+        return Group { body, spans };
     }
 
     pub fn new_block(body: Vec<ProgramBlock>) -> ProgramBlock {
@@ -238,8 +262,10 @@ impl Switch {
         validate_block_list(&true_branch, &[OpCode::Assert]);
         validate_block_list(&false_branch, &[OpCode::Not, OpCode::Assert]);
         return Switch {
+            t_spans     : count_spans(&true_branch),
+            f_spans     : count_spans(&false_branch),
             t_branch    : true_branch,
-            f_branch    : false_branch
+            f_branch    : false_branch,
         };
     }
 
@@ -251,6 +277,12 @@ impl Switch {
         return &self.t_branch;
     }
 
+    /// Returns the number of spans in the true branch; spans of the false branch are numbered
+    /// after these spans.
+    pub fn true_branch_span_count(&self) -> usize {
+        return self.t_spans;
+    }
+
     pub fn true_branch_hash(&self) -> u128 {
         return hash_seq(&self.t_branch, &BLOCK_SUFFIX, BLOCK_SUFFIX_OFFSET);
     }
@@ -294,7 +326,8 @@ impl Loop {
         let skip_block = Span::from_instructions(LOOP_SKIP_BLOCK.to_vec());
         let skip = vec![ProgramBlock::Span(skip_block)];
 
-        return Loop { body, skip };
+        let spans = count_spans(&body);
+        return Loop { body, skip, spans };
     }
 
     pub fn new_block(body: Vec<ProgramBlock>) -> ProgramBlock {
@@ -340,6 +373,10 @@ impl sp_std::fmt::Debug for Loop {
 
 // HELPER FUNCTIONS
 // ================================================================================================
+fn count_spans(blocks: &[ProgramBlock]) -> usize {
+    return blocks.iter().map(|block| block.span_count()).sum();
+}
+
 fn validate_block_list(blocks: &Vec<ProgramBlock>, starts_with: &[OpCode]) {
 
     assert!(blocks.len() > 0, "a sequence of blocks must contain at least one block");
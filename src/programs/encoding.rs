@@ -0,0 +1,332 @@
+use codec::{ Encode, Decode, Input, Output, Compact };
+use hashbrown::HashMap;
+use sp_std::{ fmt, vec::Vec };
+use crate::{ math::field, MAX_CONTEXT_DEPTH, PUSH_OP_ALIGNMENT };
+use super::{ Program, ProgramBlock, Span, Group, Switch, Loop, OpCode, OpHint, BASE_CYCLE_LENGTH };
+
+// CONSTANTS
+// ================================================================================================
+
+/// Every encoded program starts with these bytes.
+pub const PROGRAM_MAGIC: [u8; 4] = *b"DVMP";
+
+/// Version of the encoding format written by `Program::to_bytes()`.
+pub const PROGRAM_FORMAT_VERSION: u8 = 1;
+
+const SPAN_TAG  : u8 = 0;
+const GROUP_TAG : u8 = 1;
+const SWITCH_TAG: u8 = 2;
+const LOOP_TAG  : u8 = 3;
+
+const EQ_START_TAG     : u8 = 0;
+const RC_START_TAG     : u8 = 1;
+const CMP_START_TAG    : u8 = 2;
+const PMPATH_START_TAG : u8 = 3;
+const PUSH_VALUE_TAG   : u8 = 4;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Describes the reason why a program could not be decoded from bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgramDecodingError {
+    /// The bytes do not start with the program magic number.
+    InvalidMagic,
+    /// The program was encoded with a version of the format which is not supported.
+    UnsupportedVersion(u8),
+    /// The bytes end before the program is fully decoded, or contain extra bytes at the end.
+    DecodingFailed,
+    /// The decoded block tree does not describe a valid program.
+    MalformedProgram(&'static str),
+    /// Hash of the decoded program does not match the hash stored in the header.
+    HashMismatch,
+}
+
+// PROGRAM ENCODING
+// ================================================================================================
+
+/// Encodes a program as follows:
+/// * magic number (4 bytes) and format version (1 byte);
+/// * program hash (32 bytes);
+/// * body of the root block encoded as a list of blocks.
+///
+/// A list of blocks is encoded as the number of blocks (compact) followed by the blocks; every
+/// block starts with a tag byte. Spans contain the number of operations (compact), op codes
+/// (1 byte each), and the list of non-empty hints; groups and loops contain their bodies, and
+/// switches contain both of their branches. Loop skip blocks are not encoded.
+impl Encode for Program {
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&PROGRAM_MAGIC);
+        dest.push_byte(PROGRAM_FORMAT_VERSION);
+        dest.write(self.hash());
+        encode_blocks(self.root().body(), dest);
+    }
+}
+
+/// Decodes a program and makes sure the block tree is valid and the hash of the decoded
+/// program matches the hash in the header; see `Program::from_bytes()` for typed errors.
+impl Decode for Program {
+
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        return decode_program(input).map_err(|err| err.as_str().into());
+    }
+}
+
+impl Program {
+
+    /// Serializes the program into a versioned binary format which is also its SCALE encoding.
+    /// Operation hints (e.g. bit counts of `rc` and `gt` instructions) are included in the
+    /// encoding, but are not covered by the program hash.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        return self.encode();
+    }
+
+    /// Deserializes a program from bytes produced by `to_bytes()`. Returns an error if the
+    /// bytes are not a valid encoding of a program, or if the hash of the decoded program does
+    /// not match the hash stored with it (e.g. because the program was tampered with).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Program, ProgramDecodingError> {
+        let mut input = bytes;
+        let program = decode_program(&mut input)?;
+        if input.len() != 0 {
+            return Err(ProgramDecodingError::DecodingFailed);
+        }
+        return Ok(program);
+    }
+}
+
+// ENCODING HELPERS
+// ================================================================================================
+
+fn encode_blocks<T: Output + ?Sized>(blocks: &[ProgramBlock], dest: &mut T) {
+    Compact(blocks.len() as u32).encode_to(dest);
+    for block in blocks.iter() {
+        match block {
+            ProgramBlock::Span(span) => {
+                dest.push_byte(SPAN_TAG);
+                encode_span(span, dest);
+            },
+            ProgramBlock::Group(block) => {
+                dest.push_byte(GROUP_TAG);
+                encode_blocks(block.body(), dest);
+            },
+            ProgramBlock::Switch(block) => {
+                dest.push_byte(SWITCH_TAG);
+                encode_blocks(block.true_branch(), dest);
+                encode_blocks(block.false_branch(), dest);
+            },
+            ProgramBlock::Loop(block) => {
+                dest.push_byte(LOOP_TAG);
+                encode_blocks(block.body(), dest);
+            },
+        }
+    }
+}
+
+fn encode_span<T: Output + ?Sized>(span: &Span, dest: &mut T) {
+    Compact(span.length() as u32).encode_to(dest);
+    let mut hints = Vec::new();
+    for i in 0..span.length() {
+        let (op_code, op_hint) = span.get_op(i);
+        dest.push_byte(op_code as u8);
+        if op_hint != OpHint::None {
+            hints.push((i, op_hint));
+        }
+    }
+
+    // hints are written in the order of their steps
+    Compact(hints.len() as u32).encode_to(dest);
+    for (step, hint) in hints.into_iter() {
+        Compact(step as u32).encode_to(dest);
+        match hint {
+            OpHint::EqStart => dest.push_byte(EQ_START_TAG),
+            OpHint::RcStart(n) => { dest.push_byte(RC_START_TAG); n.encode_to(dest); },
+            OpHint::CmpStart(n) => { dest.push_byte(CMP_START_TAG); n.encode_to(dest); },
+            OpHint::PmpathStart(n) => { dest.push_byte(PMPATH_START_TAG); n.encode_to(dest); },
+            OpHint::PushValue(value) => { dest.push_byte(PUSH_VALUE_TAG); value.encode_to(dest); },
+            OpHint::None => (),
+        }
+    }
+}
+
+// DECODING HELPERS
+// ================================================================================================
+
+fn decode_program<I: Input>(input: &mut I) -> Result<Program, ProgramDecodingError> {
+    let mut magic = [0u8; 4];
+    read_bytes(input, &mut magic)?;
+    if magic != PROGRAM_MAGIC {
+        return Err(ProgramDecodingError::InvalidMagic);
+    }
+
+    let version = read_byte(input)?;
+    if version != PROGRAM_FORMAT_VERSION {
+        return Err(ProgramDecodingError::UnsupportedVersion(version));
+    }
+
+    let mut hash = [0u8; 32];
+    read_bytes(input, &mut hash)?;
+
+    // the first span of the program must start with BEGIN operation
+    let body = decode_blocks(input, &[OpCode::Begin], 0)?;
+    let program = Program::new(Group::new(body));
+    if *program.hash() != hash {
+        return Err(ProgramDecodingError::HashMismatch);
+    }
+    return Ok(program);
+}
+
+/// Decodes a list of blocks making sure that the blocks can be used to build a valid program;
+/// `prefix` specifies operations which the first span of the list must start with.
+fn decode_blocks<I: Input>(input: &mut I, prefix: &[OpCode], depth: usize) -> Result<Vec<ProgramBlock>, ProgramDecodingError> {
+    if depth > MAX_CONTEXT_DEPTH {
+        return Err(ProgramDecodingError::MalformedProgram("blocks are nested too deeply"));
+    }
+
+    let num_blocks = read_compact(input)?;
+    if num_blocks == 0 {
+        return Err(ProgramDecodingError::MalformedProgram("a sequence of blocks is empty"));
+    }
+
+    let mut blocks = Vec::new();
+    for i in 0..num_blocks {
+        let block = match read_byte(input)? {
+            SPAN_TAG => {
+                if blocks.last().map_or(false, |block: &ProgramBlock| block.is_span()) {
+                    return Err(ProgramDecodingError::MalformedProgram("a span follows another span"));
+                }
+                let span = decode_span(input)?;
+                if i == 0 && !span.starts_with(prefix) {
+                    return Err(ProgramDecodingError::MalformedProgram("a sequence of blocks starts with an invalid prefix"));
+                }
+                ProgramBlock::Span(span)
+            },
+            GROUP_TAG => Group::new_block(decode_blocks(input, &[], depth + 1)?),
+            SWITCH_TAG => {
+                let true_branch = decode_blocks(input, &[OpCode::Assert], depth + 1)?;
+                let false_branch = decode_blocks(input, &[OpCode::Not, OpCode::Assert], depth + 1)?;
+                Switch::new_block(true_branch, false_branch)
+            },
+            LOOP_TAG => Loop::new_block(decode_blocks(input, &[OpCode::Assert], depth + 1)?),
+            _ => return Err(ProgramDecodingError::MalformedProgram("unknown block type")),
+        };
+
+        if i == 0 && !block.is_span() {
+            return Err(ProgramDecodingError::MalformedProgram("a sequence of blocks does not start with a span"));
+        }
+        blocks.push(block);
+    }
+    return Ok(blocks);
+}
+
+fn decode_span<I: Input>(input: &mut I) -> Result<Span, ProgramDecodingError> {
+    let length = read_compact(input)?;
+    if length % BASE_CYCLE_LENGTH != BASE_CYCLE_LENGTH - 1 {
+        return Err(ProgramDecodingError::MalformedProgram("span length is not one less than a multiple of 16"));
+    }
+
+    // the length is not trusted, so operations are not pre-allocated
+    let mut op_codes = Vec::new();
+    for _ in 0..length {
+        let op_byte = read_byte(input)?;
+        match OpCode::from_u8(op_byte) {
+            Some(op_code) => op_codes.push(op_code),
+            None => return Err(ProgramDecodingError::MalformedProgram("unknown operation")),
+        }
+    }
+
+    let num_hints = read_compact(input)?;
+    let mut hints = HashMap::new();
+    let mut next_step = 0;
+    for _ in 0..num_hints {
+        // steps must be strictly increasing so that every program has a single encoding
+        let step = read_compact(input)?;
+        if step < next_step || step >= length {
+            return Err(ProgramDecodingError::MalformedProgram("hint step is out of order or out of bounds"));
+        }
+        next_step = step + 1;
+
+        let hint = match read_byte(input)? {
+            EQ_START_TAG => OpHint::EqStart,
+            RC_START_TAG => OpHint::RcStart(read_u32(input)?),
+            CMP_START_TAG => OpHint::CmpStart(read_u32(input)?),
+            PMPATH_START_TAG => OpHint::PmpathStart(read_u32(input)?),
+            PUSH_VALUE_TAG => {
+                let value = u128::decode(input).map_err(|_| ProgramDecodingError::DecodingFailed)?;
+                if value >= field::MODULUS {
+                    return Err(ProgramDecodingError::MalformedProgram("push value is not a valid field element"));
+                }
+                OpHint::PushValue(value)
+            },
+            _ => return Err(ProgramDecodingError::MalformedProgram("unknown operation hint")),
+        };
+        // hints are not covered by the program hash, so they are held to the assembler's bounds
+        if !hint.is_valid() {
+            return Err(ProgramDecodingError::MalformedProgram("hint value is out of bounds"));
+        }
+        hints.insert(step, hint);
+    }
+
+    // push values must be provided for PUSH operations only, and PUSH operations must be aligned
+    for (i, &op_code) in op_codes.iter().enumerate() {
+        let is_push_value = match hints.get(&i) {
+            Some(OpHint::PushValue(_)) => true,
+            _ => false,
+        };
+        if (op_code == OpCode::Push) != is_push_value {
+            return Err(ProgramDecodingError::MalformedProgram("push value does not match a PUSH operation"));
+        }
+        if op_code == OpCode::Push && i % PUSH_OP_ALIGNMENT != 0 {
+            return Err(ProgramDecodingError::MalformedProgram("PUSH operation is not aligned"));
+        }
+    }
+
+    return Ok(Span::new(op_codes, hints));
+}
+
+fn read_byte<I: Input>(input: &mut I) -> Result<u8, ProgramDecodingError> {
+    return input.read_byte().map_err(|_| ProgramDecodingError::DecodingFailed);
+}
+
+fn read_bytes<I: Input>(input: &mut I, dest: &mut [u8]) -> Result<(), ProgramDecodingError> {
+    return input.read(dest).map_err(|_| ProgramDecodingError::DecodingFailed);
+}
+
+fn read_u32<I: Input>(input: &mut I) -> Result<u32, ProgramDecodingError> {
+    return u32::decode(input).map_err(|_| ProgramDecodingError::DecodingFailed);
+}
+
+fn read_compact<I: Input>(input: &mut I) -> Result<usize, ProgramDecodingError> {
+    return match Compact::<u32>::decode(input) {
+        Ok(value) => Ok(value.0 as usize),
+        Err(_) => Err(ProgramDecodingError::DecodingFailed),
+    };
+}
+
+// COMMON TRAIT IMPLEMENTATIONS
+// ================================================================================================
+
+impl ProgramDecodingError {
+
+    /// Returns a static description of the error; used to report errors through SCALE decoding.
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            ProgramDecodingError::InvalidMagic          => "invalid program magic number",
+            ProgramDecodingError::UnsupportedVersion(_) => "unsupported program format version",
+            ProgramDecodingError::DecodingFailed        => "program could not be decoded",
+            ProgramDecodingError::MalformedProgram(reason) => reason,
+            ProgramDecodingError::HashMismatch          => "program hash does not match program body",
+        };
+    }
+}
+
+impl fmt::Display for ProgramDecodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProgramDecodingError::UnsupportedVersion(version) =>
+                write!(f, "unsupported program format version {}; expected {}", version, PROGRAM_FORMAT_VERSION),
+            ProgramDecodingError::MalformedProgram(reason) => write!(f, "malformed program: {}", reason),
+            _ => write!(f, "{}", self.as_str()),
+        }
+    }
+}
@@ -12,6 +12,12 @@ use blocks::{ ProgramBlock, Span, Group, Switch, Loop };
 mod inputs;
 pub use inputs::{ ProgramInputs };
 
+mod analysis;
+pub use analysis::{ StackAnalysis, StackWarning, StackError, DepthRange, analyze_stack };
+
+mod encoding;
+pub use encoding::{ ProgramDecodingError, PROGRAM_MAGIC, PROGRAM_FORMAT_VERSION };
+
 mod hashing;
 use hashing::{ hash_op, hash_acc, hash_seq };
 use serde::{Serialize, Deserialize};
@@ -60,6 +66,19 @@ impl Program {
     pub fn hash(&self) -> &[u8; 32] {
         return &self.hash;
     }
+
+    /// Computes the range of possible stack depths before every instruction of the program when
+    /// it is executed with `num_inputs` public inputs; returns an error if the program could
+    /// underflow or overflow the stack. See `analyze_stack()` for details.
+    pub fn analyze_stack(&self, num_inputs: usize) -> Result<StackAnalysis, StackError> {
+        return analyze_stack(self, num_inputs);
+    }
+
+    /// Converts the program into assembly source code which compiles into a program with
+    /// the same hash; see `assembly::disassemble()` for details.
+    pub fn to_assembly(&self) -> Result<alloc::string::String, assembly::AssemblyError> {
+        return assembly::disassemble(self);
+    }
 }
 
 impl sp_std::fmt::Debug for Program {
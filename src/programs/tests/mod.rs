@@ -1,6 +1,6 @@
 use crate::utils::{ as_bytes };
 use crate::processor::{ OpCode };
-use super::{ Program, ProgramBlock, Span, Group, Switch, Loop };
+use super::{ Program, ProgramBlock, Span, Group, Switch, Loop, StackError, StackWarning, DepthRange, assembly };
 
 mod utils;
 use utils::{ traverse, close_block };
@@ -145,6 +145,76 @@ fn simple_loop() {
     assert_eq!(111, step);
 }
 
+// STACK ANALYSIS
+// ================================================================================================
+
+#[test]
+fn stack_analysis() {
+    let program = assembly::compile("begin push.1 push.2 add end").unwrap();
+    let analysis = program.analyze_stack(0).unwrap();
+    assert_eq!(DepthRange { min: 0, max: 0 }, analysis.depths()[0]);
+    assert_eq!(2, analysis.max_depth());
+    assert_eq!(DepthRange { min: 1, max: 1 }, analysis.final_depth());
+    assert_eq!(0, analysis.warnings().len());
+
+    // the program underflows the stack without inputs, but not with 2 inputs
+    let program = assembly::compile("begin add end").unwrap();
+    let err = program.analyze_stack(0).err().unwrap();
+    assert_eq!(StackError::Underflow { step: 1, op_code: OpCode::Add, depth: 0, min_depth: 2 }, err);
+    assert_eq!(DepthRange { min: 1, max: 1 }, program.analyze_stack(2).unwrap().final_depth());
+
+    // 17 instructions which push 2 values each overflow the stack
+    let source = format!("begin {} end", vec!["pad.2"; 17].join(" "));
+    let program = assembly::compile(&source).unwrap();
+    let err = program.analyze_stack(0).err().unwrap();
+    assert_eq!(StackError::Overflow { step: 17, op_code: OpCode::Pad2, depth: 34, max_depth: 32 }, err);
+}
+
+#[test]
+fn stack_analysis_loops() {
+    // loop body grows the stack with every iteration, and so eventually overflows it
+    let program = assembly::compile("begin while.true push.1 push.1 end end").unwrap();
+    match program.analyze_stack(1).err().unwrap() {
+        StackError::Overflow { op_code: OpCode::Push, depth: 33, .. } => (),
+        err => panic!("unexpected error: {:?}", err),
+    }
+
+    // loop body shrinks the stack with every iteration, and so eventually underflows it
+    let program = assembly::compile("begin while.true drop end end").unwrap();
+    match program.analyze_stack(3).err().unwrap() {
+        StackError::Underflow { op_code: OpCode::Drop, depth: 0, min_depth: 1, .. } => (),
+        err => panic!("unexpected error: {:?}", err),
+    }
+
+    // a balanced loop inside a loop which grows the stack does not hide the overflow
+    let program = assembly::compile("begin while.true push.1 push.1 push.1 while.true push.0 end end end").unwrap();
+    assert!(match program.analyze_stack(1) { Err(StackError::Overflow { .. }) => true, _ => false });
+
+    // a balanced loop keeps the depth range of its entry
+    let program = assembly::compile("begin while.true push.1 add push.0 end end").unwrap();
+    let analysis = program.analyze_stack(2).unwrap();
+    assert_eq!(DepthRange { min: 1, max: 1 }, analysis.final_depth());
+}
+
+#[test]
+fn stack_analysis_warnings() {
+    // branches leave the stack at different depths
+    let program = assembly::compile("begin if.true push.1 else noop end end").unwrap();
+    let analysis = program.analyze_stack(2).unwrap();
+    assert_eq!(DepthRange { min: 1, max: 2 }, analysis.final_depth());
+    match analysis.warnings() {
+        [StackWarning::UnbalancedSwitch { true_depth, false_depth, .. }] => {
+            assert_eq!(DepthRange { min: 2, max: 2 }, *true_depth);
+            assert_eq!(DepthRange { min: 1, max: 1 }, *false_depth);
+        },
+        warnings => panic!("unexpected warnings: {:?}", warnings),
+    }
+
+    // a balanced loop produces no warnings
+    let program = assembly::compile("begin while.true push.1 add push.0 end end").unwrap();
+    assert_eq!(0, program.analyze_stack(2).unwrap().warnings().len());
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 fn build_first_block(op_code: OpCode, length: usize) -> ProgramBlock {
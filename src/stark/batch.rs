@@ -0,0 +1,145 @@
+use hashbrown::HashMap;
+use super::{ StarkProof, VerificationError, verifier };
+use super::constraints::{ EvaluatorParams, parse_program_hash };
+use sp_std::vec::Vec;
+
+#[cfg(feature = "std")]
+use crossbeam_utils::thread;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Maximum number of trace shapes for which evaluator parameters are kept between batches;
+/// trace shapes come from untrusted proofs, so the cache must not grow without bound. When
+/// the limit is exceeded, parameters for the least recently used shapes are dropped.
+pub const MAX_CACHED_SHAPES: usize = 8;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Verifies many proofs of execution of the same program. Data which depends only on the program
+/// (or on the shape of the execution trace) is computed once and is re-used for all proofs
+/// verified by the same batch verifier.
+pub struct BatchVerifier {
+    program_hash : [u8; 32],
+    hash_elements: Vec<u128>,
+    params       : HashMap<TraceShape, (EvaluatorParams, usize)>,
+    num_batches  : usize,
+    num_threads  : usize,
+}
+
+/// A tuple of public inputs, outputs, and the proof to be verified against them.
+pub type BatchItem<'a> = (&'a [u128], &'a [u128], &'a StarkProof);
+
+/// Trace length, extension factor, context depth, loop depth, and stack depth of a trace.
+type TraceShape = (usize, usize, usize, usize, usize);
+
+// BATCH VERIFIER IMPLEMENTATION
+// ================================================================================================
+impl BatchVerifier {
+
+    /// Returns a new batch verifier for the program with the specified hash; proofs will be
+    /// verified in at most `num_threads` threads.
+    pub fn new(program_hash: &[u8; 32], num_threads: usize) -> BatchVerifier {
+        assert!(num_threads > 0, "number of threads must be greater than zero");
+        return BatchVerifier {
+            program_hash : *program_hash,
+            hash_elements: parse_program_hash(program_hash),
+            params       : HashMap::new(),
+            num_batches  : 0,
+            num_threads  : num_threads,
+        };
+    }
+
+    /// Returns hash of the program for which proofs are verified.
+    pub fn program_hash(&self) -> &[u8; 32] {
+        return &self.program_hash;
+    }
+
+    /// Returns the number of trace shapes for which evaluator parameters are cached.
+    #[cfg(test)]
+    pub fn num_cached_shapes(&self) -> usize {
+        return self.params.len();
+    }
+
+    /// Verifies all provided proofs, and returns verification results in the same order as the
+    /// proofs were provided; each result is the same as the one `stark::verify()` would return.
+    pub fn verify(&mut self, items: &[BatchItem]) -> Vec<Result<bool, VerificationError>> {
+
+        // 1 ----- validate all proofs and build parameters for previously unseen trace shapes ----
+        let mut results = Vec::with_capacity(items.len());
+        let mut shapes = HashMap::new();
+        for &(_, _, proof) in items.iter() {
+            match proof.validate() {
+                Ok(_) => {
+                    shapes.entry(get_trace_shape(proof)).or_insert(proof);
+                    results.push(Ok(false));
+                },
+                Err(err) => results.push(Err(err.into())),
+            }
+        }
+
+        // mark all shapes used by this batch with the number of the batch
+        self.num_batches += 1;
+        for (shape, proof) in shapes.into_iter() {
+            let entry = self.params.entry(shape).or_insert_with(|| (EvaluatorParams::from_proof(proof), 0));
+            entry.1 = self.num_batches;
+        }
+
+        // 2 ----- verify well-formed proofs in parallel ------------------------------------------
+        let batch_size = (items.len() + self.num_threads - 1) / self.num_threads;
+        if self.num_threads == 1 || batch_size <= 1 {
+            self.verify_items(items, &mut results);
+        }
+        else {
+            self.verify_items_in_parallel(items, &mut results, batch_size);
+        }
+
+        // 3 ----- drop parameters for the least recently used shapes beyond the cache limit -----
+        while self.params.len() > MAX_CACHED_SHAPES {
+            let (&shape, _) = self.params.iter().min_by_key(|(_, &(_, last_batch))| last_batch).unwrap();
+            self.params.remove(&shape);
+        }
+
+        return results;
+    }
+
+    // HELPER FUNCTIONS
+    // --------------------------------------------------------------------------------------------
+
+    #[cfg(feature = "std")]
+    fn verify_items_in_parallel(&self, items: &[BatchItem], results: &mut [Result<bool, VerificationError>], batch_size: usize) {
+        thread::scope(|s| {
+            for (items, results) in items.chunks(batch_size).zip(results.chunks_mut(batch_size)) {
+                s.spawn(move |_| self.verify_items(items, results));
+            }
+        }).unwrap();
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn verify_items_in_parallel(&self, items: &[BatchItem], results: &mut [Result<bool, VerificationError>], _batch_size: usize) {
+        self.verify_items(items, results);
+    }
+
+    /// Verifies items which passed validation; results for malformed proofs are left untouched.
+    fn verify_items(&self, items: &[BatchItem], results: &mut [Result<bool, VerificationError>]) {
+        for (&(inputs, outputs, proof), result) in items.iter().zip(results.iter_mut()) {
+            if result.is_err() { continue; }
+            let (params, _) = &self.params[&get_trace_shape(proof)];
+            *result = verifier::verify_with_params(
+                &self.program_hash, &self.hash_elements, inputs, outputs, proof, params);
+        }
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn get_trace_shape(proof: &StarkProof) -> TraceShape {
+    return (
+        proof.trace_length(),
+        proof.options().extension_factor(),
+        proof.ctx_depth(),
+        proof.loop_depth(),
+        proof.stack_depth(),
+    );
+}
@@ -17,8 +17,8 @@ pub struct ConstraintTable {
 // CONSTRAINT TABLE IMPLEMENTATION
 // ================================================================================================
 impl ConstraintTable {
-    pub fn new(trace: &TraceTable, trace_root: &[u8; 32], inputs: &[u128], outputs: &[u128]) -> ConstraintTable {
-        let evaluator = ConstraintEvaluator::from_trace(trace, trace_root, inputs, outputs);
+    pub fn new(trace: &TraceTable, seed: &[u8; 32], inputs: &[u128], outputs: &[u128]) -> ConstraintTable {
+        let evaluator = ConstraintEvaluator::from_trace(trace, seed, inputs, outputs);
         let evaluation_domain_size = evaluator.domain_size();
         return ConstraintTable {
             evaluator       : evaluator,
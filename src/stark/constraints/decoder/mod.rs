@@ -56,6 +56,7 @@ pub const NUM_STATIC_DECODER_CONSTRAINTS: usize =
 
 // TYPES AND INTERFACES
 // ================================================================================================
+#[derive(Clone)]
 pub struct Decoder {
     ctx_depth           : usize,
     loop_depth          : usize,
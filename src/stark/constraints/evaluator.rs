@@ -29,11 +29,30 @@ pub struct Evaluator {
     b_degree_adj    : u128,
 }
 
+/// Parts of a constraint evaluator which depend only on the shape of the execution trace and
+/// not on the proof itself. Building these requires extending periodic constants, so when many
+/// proofs of the same shape are verified, the parameters can be built once and re-used.
+#[derive(Clone)]
+pub struct EvaluatorParams {
+    decoder         : Decoder,
+    stack           : Stack,
+    trace_length    : usize,
+    extension_factor: usize,
+    ctx_depth       : usize,
+    loop_depth      : usize,
+    stack_depth     : usize,
+    t_constraint_num: usize,
+    t_degree_groups : Vec<(u128, Vec<usize>)>,
+    b_degree_adj    : u128,
+}
+
 // EVALUATOR IMPLEMENTATION
 // ================================================================================================
 impl Evaluator {
 
-    pub fn from_trace(trace: &TraceTable, trace_root: &[u8; 32], inputs: &[u128], outputs: &[u128]) -> Evaluator
+    /// Builds a constraint evaluator for the execution trace; coefficients for the linear
+    /// combination of constraints are derived from the `seed`.
+    pub fn from_trace(trace: &TraceTable, seed: &[u8; 32], inputs: &[u128], outputs: &[u128]) -> Evaluator
     {
         let last_state = trace.get_last_state();
         let ctx_depth = trace.ctx_depth();
@@ -64,7 +83,7 @@ impl Evaluator {
         return Evaluator {
             decoder         : decoder,
             stack           : stack,
-            coefficients    : ConstraintCoefficients::new(*trace_root, ctx_depth, loop_depth, stack_depth),
+            coefficients    : ConstraintCoefficients::new(*seed, ctx_depth, loop_depth, stack_depth),
             domain_size     : domain_size,
             extension_factor: extension_factor,
             t_constraint_num: t_constraint_degrees.len(),
@@ -79,38 +98,29 @@ impl Evaluator {
         };
     }
 
-    pub fn from_proof(proof: &StarkProof, program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128]) -> Evaluator
+    /// Builds a constraint evaluator for the proof; `seed` must be the same as the one used
+    /// by the prover to build the constraint evaluator from the execution trace. `params` must
+    /// have been built for the shape of the execution trace described by the proof, and
+    /// `program_hash` must be already parsed into field elements.
+    pub fn from_params(params: &EvaluatorParams, proof: &StarkProof, seed: &[u8; 32], program_hash: &[u128], inputs: &[u128], outputs: &[u128]) -> Evaluator
     {
-        let ctx_depth = proof.ctx_depth();
-        let loop_depth = proof.loop_depth();
-        let stack_depth = proof.stack_depth();
-        let trace_length = proof.trace_length();
-        let extension_factor = proof.options().extension_factor();
-        
-        // instantiate decoder and stack constraint evaluators 
-        let decoder = Decoder::new(trace_length, extension_factor, ctx_depth, loop_depth);
-        let stack = Stack::new(trace_length, extension_factor, stack_depth);
-
-        // build a list of transition constraint degrees
-        let t_constraint_degrees = [
-            decoder.constraint_degrees(), stack.constraint_degrees()
-        ].concat();
+        debug_assert!(params.matches(proof), "evaluator parameters do not match the proof");
 
         return Evaluator {
-            decoder         : decoder,
-            stack           : stack,
-            coefficients    : ConstraintCoefficients::new(*proof.trace_root(), ctx_depth, loop_depth, stack_depth),
+            decoder         : params.decoder.clone(),
+            stack           : params.stack.clone(),
+            coefficients    : ConstraintCoefficients::new(*seed, proof.ctx_depth(), proof.loop_depth(), proof.stack_depth()),
             domain_size     : proof.domain_size(),
-            extension_factor: extension_factor,
-            t_constraint_num: t_constraint_degrees.len(),
-            t_degree_groups : group_transition_constraints(t_constraint_degrees, trace_length),
+            extension_factor: params.extension_factor,
+            t_constraint_num: params.t_constraint_num,
+            t_degree_groups : params.t_degree_groups.clone(),
             t_evaluations   : Vec::new(),
             b_constraint_num: get_boundary_constraint_num(&inputs, &outputs),
-            program_hash    : parse_program_hash(program_hash),
+            program_hash    : program_hash.to_vec(),
             op_count        : proof.op_count(),
             inputs          : inputs.to_vec(),
             outputs         : outputs.to_vec(),
-            b_degree_adj    : get_boundary_constraint_adjustment_degree(trace_length),
+            b_degree_adj    : params.b_degree_adj,
         };
     }
 
@@ -381,6 +391,53 @@ impl Evaluator {
     }
 }
 
+// EVALUATOR PARAMETERS IMPLEMENTATION
+// ================================================================================================
+impl EvaluatorParams {
+
+    /// Builds evaluator parameters for an execution trace of the specified shape.
+    pub fn new(trace_length: usize, extension_factor: usize, ctx_depth: usize, loop_depth: usize, stack_depth: usize) -> EvaluatorParams
+    {
+        // instantiate decoder and stack constraint evaluators 
+        let decoder = Decoder::new(trace_length, extension_factor, ctx_depth, loop_depth);
+        let stack = Stack::new(trace_length, extension_factor, stack_depth);
+
+        // build a list of transition constraint degrees
+        let t_constraint_degrees = [
+            decoder.constraint_degrees(), stack.constraint_degrees()
+        ].concat();
+
+        return EvaluatorParams {
+            decoder, stack,
+            trace_length, extension_factor,
+            ctx_depth, loop_depth, stack_depth,
+            t_constraint_num: t_constraint_degrees.len(),
+            t_degree_groups : group_transition_constraints(t_constraint_degrees, trace_length),
+            b_degree_adj    : get_boundary_constraint_adjustment_degree(trace_length),
+        };
+    }
+
+    /// Builds evaluator parameters for the shape of the execution trace described by the proof.
+    pub fn from_proof(proof: &StarkProof) -> EvaluatorParams {
+        return EvaluatorParams::new(
+            proof.trace_length(),
+            proof.options().extension_factor(),
+            proof.ctx_depth(),
+            proof.loop_depth(),
+            proof.stack_depth());
+    }
+
+    /// Returns true if these parameters were built for the shape of the execution trace
+    /// described by the proof.
+    pub fn matches(&self, proof: &StarkProof) -> bool {
+        return self.trace_length == proof.trace_length()
+            && self.extension_factor == proof.options().extension_factor()
+            && self.ctx_depth == proof.ctx_depth()
+            && self.loop_depth == proof.loop_depth()
+            && self.stack_depth == proof.stack_depth();
+    }
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 fn group_transition_constraints(degrees: Vec<usize>, trace_length: usize) -> Vec<(u128, Vec<usize>)> {
@@ -430,7 +487,7 @@ fn get_transition_constraint_target_degree(trace_length: usize) -> usize {
     return combination_degree + divisor_degree;
 }
 
-fn parse_program_hash(program_hash: &[u8; 32]) -> Vec<u128> {
+pub fn parse_program_hash(program_hash: &[u8; 32]) -> Vec<u128> {
     return vec![
         field::from_bytes(&program_hash[..16]),
         field::from_bytes(&program_hash[16..]),
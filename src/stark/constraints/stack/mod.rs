@@ -44,6 +44,7 @@ const STACK_TRANSITION_DEGREE: usize = 7; // degree for all stack register trans
 
 // TYPES AND INTERFACES
 // ================================================================================================
+#[derive(Clone)]
 pub struct Stack {
     trace_length        : usize,
     cycle_length        : usize,
@@ -0,0 +1,153 @@
+use crate::ExecutionReport;
+use super::{ ProofOptions, SecurityEstimate, TraceState, MAX_CONSTRAINT_DEGREE, fri::MAX_REMAINDER_LENGTH };
+use sp_std::fmt;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Approximate time of a single field multiplication and of a single invocation of a hash
+/// function on a 64-byte input; these are used to convert operation counts into time.
+const NS_PER_FIELD_MULT: u64 = 20;
+const NS_PER_HASH: u64 = 400;
+
+/// Approximate number of field multiplications needed to evaluate transition constraints for
+/// a single register at a single point of the constraint evaluation domain.
+const CONSTRAINT_MULTS_PER_REGISTER: u64 = 24;
+
+/// Size of a field element, of a hash digest, of a SCALE length prefix of a vector with fewer
+/// than 2^14 items, and of the fixed part of a SCALE-encoded proof (commitment roots, trace
+/// info, options, proof-of-work nonce, and top-level length prefixes).
+const ELEMENT_SIZE: usize = 16;
+const DIGEST_SIZE: usize = 32;
+const PREFIX_SIZE: usize = 2;
+const PROOF_HEADER_SIZE: usize = 3 * DIGEST_SIZE + 32;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Predicted cost of proving an execution described by an `ExecutionReport` with specific proof
+/// options. Operation counts follow the steps of the prover; time and memory are derived from
+/// them and are meant for comparing programs and options rather than as exact predictions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CostEstimate {
+    /// Length of the execution trace after it is padded to a power of 2.
+    pub trace_length    : usize,
+    /// Number of registers in the execution trace.
+    pub trace_width     : usize,
+    /// Size of the low-degree extension domain.
+    pub domain_size     : usize,
+    /// Number of field multiplications done by the prover.
+    pub field_mults     : u64,
+    /// Number of hash function invocations done by the prover, including expected proof-of-work.
+    pub hash_calls      : u64,
+    /// Approximate proving time (in milliseconds) derived from the numbers of field
+    /// multiplications and hash function invocations.
+    pub proving_time_ms : u64,
+    /// Peak memory (in bytes) used by the prover to hold the extended trace, the constraint and
+    /// composition evaluations, and the Merkle trees built from them.
+    pub memory_size     : usize,
+    /// Size (in bytes) of the SCALE-encoded proof; this is an upper bound, since authentication
+    /// paths of different queries usually share nodes.
+    pub proof_size      : usize,
+    /// Security level of the proof; this is the same as the security reported for the proof
+    /// once it is generated.
+    pub security        : SecurityEstimate,
+}
+
+// COST ESTIMATE IMPLEMENTATION
+// ================================================================================================
+impl CostEstimate {
+
+    /// Estimates the cost of proving an execution described by the `report` with the specified
+    /// options.
+    pub fn new(report: &ExecutionReport, options: &ProofOptions) -> CostEstimate {
+        let trace_length = report.trace_length();
+
+        let trace_width = TraceState::compute_decoder_width(report.max_ctx_depth(), report.max_loop_depth())
+            + report.max_stack_depth();
+        let domain_size = trace_length * options.extension_factor();
+        let num_queries = options.num_queries();
+
+        let n = trace_length as u64;
+        let d = domain_size as u64;
+        let w = trace_width as u64;
+        let ce = n * MAX_CONSTRAINT_DEGREE as u64;  // size of constraint evaluation domain
+
+        // extend the trace, evaluate constraints, convert evaluations into a polynomial, and
+        // evaluate constraint and composition polynomials over the LDE domain
+        let mut field_mults = w * (fft_mults(n) + fft_mults(d))
+            + ce * w * CONSTRAINT_MULTS_PER_REGISTER
+            + fft_mults(ce) + fft_mults(d)
+            + 2 * w * d + fft_mults(d);
+
+        // commit to the trace (one leaf per step) and to constraint evaluations (two per leaf);
+        // every tree has as many internal nodes as leaves
+        let mut hash_calls = 2 * d + d;
+
+        // every query opens a path in the trace tree and in the constraint tree, and the trace
+        // is also opened at two out-of-domain points
+        let mut proof_size = PROOF_HEADER_SIZE
+            + num_queries * (log2(domain_size) * DIGEST_SIZE + trace_width * ELEMENT_SIZE + 2 * PREFIX_SIZE)
+            + num_queries * (log2(domain_size / 2) * DIGEST_SIZE + 2 * ELEMENT_SIZE + PREFIX_SIZE)
+            + 2 * trace_width * ELEMENT_SIZE;
+
+        // FRI layers reduce the domain by 4 until the remainder is small enough
+        let mut fri_size = domain_size;
+        while fri_size > MAX_REMAINDER_LENGTH {
+            let rows = (fri_size / 4) as u64;
+            field_mults += rows * 16;
+            hash_calls += 2 * rows;
+            proof_size += num_queries * (4 * ELEMENT_SIZE + log2(fri_size / 4) * DIGEST_SIZE + PREFIX_SIZE)
+                + DIGEST_SIZE + 2 * PREFIX_SIZE;
+            fri_size /= 4;
+        }
+        proof_size += fri_size * ELEMENT_SIZE + DIGEST_SIZE;
+
+        // grinding takes 2^grinding_factor attempts on average
+        hash_calls += 1u64 << options.grinding_factor();
+
+        let proving_time_ms = (field_mults * NS_PER_FIELD_MULT + hash_calls * NS_PER_HASH) / 1_000_000;
+
+        // extended trace and its polynomials, LDE domain and twiddles, constraint and composition
+        // evaluations, and Merkle trees of the trace, constraints, and FRI layers
+        let memory_size = trace_width * (domain_size + trace_length) * ELEMENT_SIZE
+            + (domain_size + domain_size / 2) * ELEMENT_SIZE
+            + (ce as usize + domain_size) * ELEMENT_SIZE
+            + 2 * domain_size * DIGEST_SIZE
+            + domain_size * DIGEST_SIZE
+            + domain_size / 2 * DIGEST_SIZE;
+
+        return CostEstimate {
+            trace_length, trace_width, domain_size,
+            field_mults, hash_calls, proving_time_ms,
+            memory_size, proof_size,
+            security: SecurityEstimate::new(options, trace_length),
+        };
+    }
+}
+
+impl fmt::Display for CostEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "trace:                {} steps, {} registers, {} LDE domain",
+            self.trace_length, self.trace_width, self.domain_size)?;
+        writeln!(f, "prover work:          {} field multiplications, {} hash calls",
+            self.field_mults, self.hash_calls)?;
+        writeln!(f, "proving time:         ~{} ms", self.proving_time_ms)?;
+        writeln!(f, "prover memory:        ~{} bytes", self.memory_size)?;
+        writeln!(f, "proof size:           <= {} bytes", self.proof_size)?;
+        return write!(f, "security:             {} bits conjectured, {} bits provable",
+            self.security.conjectured, self.security.provable);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Number of multiplications in an FFT over a domain of size `n`.
+fn fft_mults(n: u64) -> u64 {
+    return n / 2 * (log2(n as usize) as u64);
+}
+
+fn log2(n: usize) -> usize {
+    return n.trailing_zeros() as usize;
+}
@@ -0,0 +1,189 @@
+use sp_std::fmt;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Describes the reason why verification of a STARK proof has failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationError {
+    /// Proof-of-work nonce did not produce a seed with the required number of leading zeros.
+    InsufficientProofOfWork { grinding_factor: u32 },
+    /// The number of executed operations is smaller than the minimum trace length.
+    OpCountTooSmall { op_count: u128, min_op_count: usize },
+    /// Merkle authentication paths for the trace tree did not resolve to the trace root.
+    TraceMerkleMismatch,
+    /// Merkle authentication paths for the constraint tree did not resolve to the constraint root.
+    ConstraintMerkleMismatch,
+    /// Evaluations at the specified query position did not match the values committed to
+    /// at the specified FRI layer.
+    FriLayerMismatch { layer: usize, position: usize },
+    /// The number of evaluations did not match the number of values committed to at the
+    /// specified FRI layer.
+    FriLayerLengthMismatch { layer: usize, expected: usize, actual: usize },
+    /// Merkle authentication paths for the specified FRI layer did not resolve to the layer root.
+    FriLayerMerkleMismatch { layer: usize },
+    /// Remainder value at the specified query position did not match the last FRI layer.
+    FriRemainderMismatch { position: usize },
+    /// Remainder does not contain enough values to describe a polynomial of the expected degree.
+    FriRemainderTooShort { degree: usize, length: usize },
+    /// Remainder values do not describe a polynomial of the expected degree.
+    FriRemainderDegree { degree: usize },
+    /// The proof is malformed and was rejected before any verification step was executed.
+    MalformedProof(ProofValidationError),
+    /// The proof does not satisfy the verifier policy and was rejected before any verification
+    /// step was executed.
+    PolicyViolation(PolicyViolation),
+}
+
+/// Describes the reason why a proof (or its options) were rejected during validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProofValidationError {
+    /// The proof could not be decoded from the provided bytes.
+    DecodingFailed,
+    /// Extension factor (stored as a power of 2) is outside of the supported range.
+    InvalidExtensionFactor(u8),
+    /// Number of queries is outside of the supported range.
+    InvalidNumQueries(u8),
+    /// Grinding factor is greater than the supported maximum.
+    InvalidGrindingFactor(u8),
+    /// Evaluation domain depth is inconsistent with the extension factor or too large.
+    InvalidDomainDepth(u8),
+    /// Context stack depth is greater than the supported maximum.
+    InvalidContextDepth(u8),
+    /// Loop stack depth is greater than the supported maximum.
+    InvalidLoopDepth(u8),
+    /// User stack depth is greater than the supported maximum.
+    InvalidStackDepth(u8),
+    /// Number of executed operations exceeds the length of the execution trace.
+    InvalidOpCount(u32),
+    /// The named vector has an unexpected number of elements.
+    InvalidLength { name: &'static str, expected: usize, actual: usize },
+    /// The named vector contains more elements than allowed.
+    LengthExceeded { name: &'static str, max: usize, actual: usize },
+    /// The named vector contains a value which is not a canonical field element.
+    NonCanonicalFieldElement { name: &'static str },
+    /// Merkle tree depth of the named component is inconsistent with the evaluation domain.
+    InvalidTreeDepth { name: &'static str, expected: u8, actual: u8 },
+}
+
+/// Describes the limit of a verifier policy which a proof has failed to satisfy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyViolation {
+    /// Security level of the proof is lower than the minimum required by the policy.
+    SecurityLevelTooLow { level: u32, min_level: u32 },
+    /// The proof was generated using a hash function not allowed by the policy.
+    HashFunctionNotAllowed,
+    /// Evaluation domain depth of the proof is greater than the maximum allowed by the policy.
+    DomainDepthTooLarge { depth: u8, max_depth: u8 },
+    /// Number of executed operations is greater than the maximum allowed by the policy.
+    OpCountTooLarge { op_count: u128, max_op_count: u128 },
+    /// Context stack depth is greater than the maximum allowed by the policy.
+    ContextDepthTooLarge { depth: usize, max_depth: usize },
+    /// Loop stack depth is greater than the maximum allowed by the policy.
+    LoopDepthTooLarge { depth: usize, max_depth: usize },
+    /// User stack depth is greater than the maximum allowed by the policy.
+    StackDepthTooLarge { depth: usize, max_depth: usize },
+}
+
+// VERIFICATION ERROR IMPLEMENTATION
+// ================================================================================================
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::InsufficientProofOfWork { grinding_factor } =>
+                write!(f, "seed proof-of-work verification failed: expected {} leading zeros", grinding_factor),
+            VerificationError::OpCountTooSmall { op_count, min_op_count } =>
+                write!(f, "verification of minimum operation count failed: expected at least {} operations, but was {}", min_op_count, op_count),
+            VerificationError::TraceMerkleMismatch =>
+                write!(f, "verification of trace Merkle proof failed"),
+            VerificationError::ConstraintMerkleMismatch =>
+                write!(f, "verification of constraint Merkle proof failed"),
+            VerificationError::FriLayerMismatch { layer, position } =>
+                write!(f, "verification of low-degree proof failed: evaluations did not match column value at depth {} for position {}", layer, position),
+            VerificationError::FriLayerLengthMismatch { layer, expected, actual } =>
+                write!(f, "verification of low-degree proof failed: expected {} evaluations at depth {}, but was {}", expected, layer, actual),
+            VerificationError::FriLayerMerkleMismatch { layer } =>
+                write!(f, "verification of low-degree proof failed: verification of Merkle proof failed at layer {}", layer),
+            VerificationError::FriRemainderMismatch { position } =>
+                write!(f, "verification of low-degree proof failed: remainder value at position {} is inconsistent with the last column", position),
+            VerificationError::FriRemainderTooShort { degree, length } =>
+                write!(f, "verification of low-degree proof failed: remainder degree {} is greater than number of remainder values {}", degree, length),
+            VerificationError::FriRemainderDegree { degree } =>
+                write!(f, "verification of low-degree proof failed: remainder is not a valid degree {} polynomial", degree),
+            VerificationError::MalformedProof(err) =>
+                write!(f, "proof is malformed: {}", err),
+            VerificationError::PolicyViolation(err) =>
+                write!(f, "proof does not satisfy verifier policy: {}", err),
+        }
+    }
+}
+
+impl From<ProofValidationError> for VerificationError {
+    fn from(err: ProofValidationError) -> VerificationError {
+        return VerificationError::MalformedProof(err);
+    }
+}
+
+impl From<PolicyViolation> for VerificationError {
+    fn from(err: PolicyViolation) -> VerificationError {
+        return VerificationError::PolicyViolation(err);
+    }
+}
+
+// PROOF VALIDATION ERROR IMPLEMENTATION
+// ================================================================================================
+impl fmt::Display for ProofValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofValidationError::DecodingFailed =>
+                write!(f, "proof could not be decoded"),
+            ProofValidationError::InvalidExtensionFactor(value) =>
+                write!(f, "extension factor 2^{} is not supported", value),
+            ProofValidationError::InvalidNumQueries(value) =>
+                write!(f, "number of queries {} is not supported", value),
+            ProofValidationError::InvalidGrindingFactor(value) =>
+                write!(f, "grinding factor {} is not supported", value),
+            ProofValidationError::InvalidDomainDepth(value) =>
+                write!(f, "domain depth {} is not valid", value),
+            ProofValidationError::InvalidContextDepth(value) =>
+                write!(f, "context depth {} is not valid", value),
+            ProofValidationError::InvalidLoopDepth(value) =>
+                write!(f, "loop depth {} is not valid", value),
+            ProofValidationError::InvalidStackDepth(value) =>
+                write!(f, "stack depth {} is not valid", value),
+            ProofValidationError::InvalidOpCount(value) =>
+                write!(f, "operation count {} exceeds trace length", value),
+            ProofValidationError::InvalidLength { name, expected, actual } =>
+                write!(f, "expected {} to contain {} elements, but was {}", name, expected, actual),
+            ProofValidationError::LengthExceeded { name, max, actual } =>
+                write!(f, "expected {} to contain at most {} elements, but was {}", name, max, actual),
+            ProofValidationError::NonCanonicalFieldElement { name } =>
+                write!(f, "{} contains a value which is not a valid field element", name),
+            ProofValidationError::InvalidTreeDepth { name, expected, actual } =>
+                write!(f, "expected {} Merkle tree depth to be {}, but was {}", name, expected, actual),
+        }
+    }
+}
+
+// POLICY VIOLATION IMPLEMENTATION
+// ================================================================================================
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyViolation::SecurityLevelTooLow { level, min_level } =>
+                write!(f, "security level {} is lower than the required {} bits", level, min_level),
+            PolicyViolation::HashFunctionNotAllowed =>
+                write!(f, "hash function is not allowed"),
+            PolicyViolation::DomainDepthTooLarge { depth, max_depth } =>
+                write!(f, "domain depth {} is greater than the allowed {}", depth, max_depth),
+            PolicyViolation::OpCountTooLarge { op_count, max_op_count } =>
+                write!(f, "operation count {} is greater than the allowed {}", op_count, max_op_count),
+            PolicyViolation::ContextDepthTooLarge { depth, max_depth } =>
+                write!(f, "context depth {} is greater than the allowed {}", depth, max_depth),
+            PolicyViolation::LoopDepthTooLarge { depth, max_depth } =>
+                write!(f, "loop depth {} is greater than the allowed {}", depth, max_depth),
+            PolicyViolation::StackDepthTooLarge { depth, max_depth } =>
+                write!(f, "stack depth {} is greater than the allowed {}", depth, max_depth),
+        }
+    }
+}
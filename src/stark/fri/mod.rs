@@ -1,4 +1,5 @@
 use serde::{ Serialize, Deserialize };
+use codec::{ Encode, Decode };
 use sp_std::vec::Vec;
 
 // RE-EXPORTS
@@ -11,18 +12,18 @@ pub use prover::{ reduce, build_proof };
 mod verifier;
 pub use verifier::{ verify };
 
-const MAX_REMAINDER_LENGTH: usize = 256;
+pub const MAX_REMAINDER_LENGTH: usize = 256;
 
 // TYPES AND INTERFACES
 // ================================================================================================
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
 pub struct FriProof {
     pub layers      : Vec<FriLayer>,
     pub rem_root    : [u8; 32],
     pub rem_values  : Vec<u128>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
 pub struct FriLayer {
     pub root    : [u8; 32],
     pub values  : Vec<[u128; 4]>,
@@ -35,7 +36,7 @@ pub struct FriLayer {
 #[cfg(test)]
 mod tests {
     use crate::math::{ field, polynom };
-    use crate::stark::{ ProofOptions, utils::compute_query_positions };
+    use crate::stark::{ ProofOptions, VerificationError, utils::compute_query_positions };
 
     #[test]
     fn prove_verify() {
@@ -74,8 +75,7 @@ mod tests {
 
         let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
         let result = super::verify(&proof, &sampled_evaluations, &positions, degree - 1, &options);
-        let err_msg = format!("remainder is not a valid degree {} polynomial", 14);
-        assert_eq!(Err(err_msg), result);
+        assert_eq!(Err(VerificationError::FriRemainderDegree { degree: 14 }), result);
 
         // degree too low 2
         let evaluations = build_random_poly_evaluations(domain_size, degree + 1);
@@ -85,14 +85,20 @@ mod tests {
 
         let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
         let result = super::verify(&proof, &sampled_evaluations, &positions, degree, &options);
-        let err_msg = format!("remainder is not a valid degree {} polynomial", 15);
-        assert_eq!(Err(err_msg), result);
+        assert_eq!(Err(VerificationError::FriRemainderDegree { degree: 15 }), result);
 
         // invalid evaluations
+        let mut invalid_evaluations = sampled_evaluations.clone();
+        invalid_evaluations[1] = field::add(invalid_evaluations[1], 1);
+        let result = super::verify(&proof, &invalid_evaluations, &positions, degree, &options);
+        let err = VerificationError::FriLayerMismatch { layer: 0, position: positions[1] };
+        assert_eq!(Err(err), result);
+
+        // missing evaluations
         let sampled_evaluations = sampled_evaluations[1..].to_vec();
         let result = super::verify(&proof, &sampled_evaluations, &positions, degree, &options);
-        let err_msg = format!("evaluations did not match column value at depth 0");
-        assert_eq!(Err(err_msg), result);
+        let err = VerificationError::FriLayerLengthMismatch { layer: 0, expected: positions.len(), actual: positions.len() - 1 };
+        assert_eq!(Err(err), result);
     }
 
     // TODO: add more tests
@@ -1,11 +1,10 @@
 use sp_std::mem;
 use crate::math::{ field, polynom, quartic };
 use crate::crypto::{ MerkleTree, BatchMerkleProof };
-use crate::stark::{ ProofOptions };
+use crate::stark::{ ProofOptions, VerificationError };
 
 use super::{ FriProof, FriLayer, utils };
 use sp_std::vec::Vec;
-use alloc::string::String;
 
 // VERIFIER
 // ================================================================================================
@@ -15,10 +14,11 @@ pub fn verify(
     evaluations : &[u128],
     positions   : &[usize],
     max_degree  : usize,
-    options     : &ProofOptions) -> Result<bool, String>
+    options     : &ProofOptions) -> Result<bool, VerificationError>
 {
 
-    let domain_size = usize::pow(2, proof.layers[0].depth as u32) * 4;
+    // each layer reduces the domain by a factor of 4; this also works for proofs without layers
+    let domain_size = proof.rem_values.len() * usize::pow(4, proof.layers.len() as u32);
     let domain_root = field::get_root_of_unity(domain_size);
 
     // powers of the given root of unity 1, p, p^2, p^3 such that p^4 = 1
@@ -40,14 +40,18 @@ pub fn verify(
 
         let mut augmented_positions = utils::get_augmented_positions(&positions, domain_size);
         let column_values = get_column_values(&layer.values, &positions, &augmented_positions, domain_size);
-        if evaluations != column_values {
-            return Err(format!("evaluations did not match column value at depth {}", depth));
+        if evaluations.len() != column_values.len() {
+            return Err(VerificationError::FriLayerLengthMismatch {
+                layer: depth, expected: column_values.len(), actual: evaluations.len() });
+        }
+        if let Some(i) = find_mismatch(&evaluations, &column_values) {
+            return Err(VerificationError::FriLayerMismatch { layer: depth, position: positions[i] });
         }
 
         // verify Merkle proof for the layer
         let merkle_proof = build_layer_merkle_proof(&layer, options);
         if !MerkleTree::verify_batch(&layer.root, &augmented_positions, &merkle_proof, options.hash_fn()) {
-            return Err(format!("verification of Merkle proof failed at layer {}", depth));
+            return Err(VerificationError::FriLayerMerkleMismatch { layer: depth });
         }
 
         // build a set of x for each row polynomial
@@ -82,7 +86,7 @@ pub fn verify(
     
     for (&position, evaluation) in positions.iter().zip(evaluations) {
         if proof.rem_values[position] != evaluation {
-            return Err(String::from("remainder values are inconsistent with values of the last column"));
+            return Err(VerificationError::FriRemainderMismatch { position });
         }
     }
 
@@ -90,9 +94,12 @@ pub fn verify(
     return verify_remainder(&proof.rem_values, max_degree_plus_1, domain_root, options.extension_factor());
 }
 
-fn verify_remainder(remainder: &[u128], max_degree_plus_1: usize, domain_root: u128, extension_factor: usize) -> Result<bool, String> {
+fn verify_remainder(remainder: &[u128], max_degree_plus_1: usize, domain_root: u128, extension_factor: usize) -> Result<bool, VerificationError> {
     if max_degree_plus_1 > remainder.len() {
-        return Err(String::from("remainder degree is greater than number of remainder values"));
+        return Err(VerificationError::FriRemainderTooShort {
+            degree  : max_degree_plus_1 - 1,
+            length  : remainder.len()
+        });
     }
 
     // exclude points which should be skipped during evaluation
@@ -118,7 +125,7 @@ fn verify_remainder(remainder: &[u128], max_degree_plus_1: usize, domain_root: u
     for i in max_degree_plus_1..positions.len() {
         let p = positions[i];
         if polynom::eval(&poly, domain[p]) != remainder[p] {
-            return Err(format!("remainder is not a valid degree {} polynomial", max_degree_plus_1 - 1));
+            return Err(VerificationError::FriRemainderDegree { degree: max_degree_plus_1 - 1 });
         }
     }
 
@@ -140,6 +147,13 @@ fn get_column_values(values: &Vec<[u128; 4]>, positions: &[usize], augmented_pos
     return result;
 }
 
+/// Returns index of the first value which differs between `evaluations` and `column_values`;
+/// both slices must have the same length.
+fn find_mismatch(evaluations: &[u128], column_values: &[u128]) -> Option<usize> {
+    debug_assert!(evaluations.len() == column_values.len(), "evaluations and column values differ in length");
+    return evaluations.iter().zip(column_values.iter()).position(|(a, b)| a != b);
+}
+
 fn build_layer_merkle_proof(layer: &FriLayer, options: &ProofOptions) -> BatchMerkleProof {
     return BatchMerkleProof {
         values  : utils::hash_values(&layer.values, options.hash_fn()),
@@ -154,6 +168,7 @@ fn build_layer_merkle_proof(layer: &FriLayer, options: &ProofOptions) -> BatchMe
 mod tests {
     
     use crate::math::{ field, polynom };
+    use crate::stark::VerificationError;
 
     #[test]
     fn verify_remainder() {
@@ -176,8 +191,8 @@ mod tests {
         // check against lower degree
         let degree_plus_1 = degree_plus_1 - 1;
         let result = super::verify_remainder(&remainder, degree_plus_1, root, extension_factor);
-        let err_msg = format!("remainder is not a valid degree {} polynomial", degree_plus_1 - 1);
-        assert_eq!(Err(err_msg), result);
+        let err = VerificationError::FriRemainderDegree { degree: degree_plus_1 - 1 };
+        assert_eq!(Err(err), result);
     }
 
 }
\ No newline at end of file
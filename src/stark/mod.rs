@@ -6,6 +6,11 @@ mod verifier;
 mod proof;
 mod fri;
 mod utils;
+mod errors;
+mod batch;
+mod policy;
+mod security;
+mod cost;
 
 pub use trace::{ TraceTable, TraceState };
 
@@ -19,8 +24,13 @@ pub use utils::{
     CompositionCoefficients };
 
 pub use options::ProofOptions;
-pub use proof::{ StarkProof, DeepValues, GenOutput, ProgramAssembly};
+pub use proof::{ StarkProof, TraceInfo, DeepValues, ProofStats, FriLayerStats, GenOutput, ProgramAssembly, MAX_DOMAIN_DEPTH };
 pub use prover::{ prove };
-pub use verifier::{ verify };
+pub use verifier::{ verify, verify_with_policy };
+pub use batch::{ BatchVerifier, BatchItem, MAX_CACHED_SHAPES };
+pub use policy::VerifierPolicy;
+pub use security::{ SecurityEstimate, get_cheapest_options };
+pub use cost::CostEstimate;
+pub use errors::{ VerificationError, ProofValidationError, PolicyViolation };
 
 const MAX_CONSTRAINT_DEGREE : usize = 8;
\ No newline at end of file
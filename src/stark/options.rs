@@ -1,6 +1,7 @@
 use serde::{ Serialize, Deserialize };
-use crate::crypto::{ HashFunction, hash };
-use super::MAX_CONSTRAINT_DEGREE;
+use codec::{ Encode, Decode, Input, Output };
+use crate::crypto::{ HashFunction, hash, get_hash_fn_id, get_hash_fn };
+use super::{ MAX_CONSTRAINT_DEGREE, ProofValidationError };
 
 // CONSTANTS
 // ================================================================================================
@@ -8,10 +9,14 @@ const DEFAULT_EXTENSION_FACTOR: u8 = (MAX_CONSTRAINT_DEGREE * 4) as u8;
 const DEFAULT_NUM_QUERIES     : u8 = 50;
 const DEFAULT_GRINDING_FACTOR : u8 = 20;
 
+pub const MIN_EXTENSION_FACTOR: usize = crate::MIN_EXTENSION_FACTOR;
+pub const MAX_EXTENSION_FACTOR: usize = 256;
+pub const MAX_NUM_QUERIES     : usize = 128;
+pub const MAX_GRINDING_FACTOR : u32 = 32;
+
 // TYPES AND INTERFACES
 // ================================================================================================
 
-// TODO: validate field values on de-serialization
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ProofOptions {
     extension_factor    : u8,   // stored as power of 2
@@ -33,13 +38,20 @@ impl ProofOptions {
         hash_fn          : HashFunction) -> ProofOptions
     {
         assert!(extension_factor.is_power_of_two(), "extension_factor must be a power of 2");
-        assert!(extension_factor >= 16, "extension_factor cannot be smaller than 16");
-        assert!(extension_factor <= 256, "extension_factor cannot be greater than 256");
+        assert!(extension_factor >= MIN_EXTENSION_FACTOR,
+            "extension_factor cannot be smaller than {}", MIN_EXTENSION_FACTOR);
+        assert!(extension_factor <= MAX_EXTENSION_FACTOR,
+            "extension_factor cannot be greater than {}", MAX_EXTENSION_FACTOR);
 
         assert!(num_queries > 0, "num_queries must be greater than 0");
-        assert!(num_queries <= 128, "num_queries cannot be greater than 128");
+        assert!(num_queries <= MAX_NUM_QUERIES,
+            "num_queries cannot be greater than {}", MAX_NUM_QUERIES);
+
+        assert!(grinding_factor <= MAX_GRINDING_FACTOR,
+            "grinding factor cannot be greater than {}", MAX_GRINDING_FACTOR);
 
-        assert!(grinding_factor <= 32, "grinding factor cannot be greater than 32");
+        // options must be serializable, so only hash functions with identifiers are accepted
+        assert!(get_hash_fn_id(hash_fn).is_some(), "unsupported hash function");
 
         return ProofOptions {
             extension_factor    : extension_factor.trailing_zeros() as u8,
@@ -65,6 +77,8 @@ impl ProofOptions {
         return self.hash_fn;
     }
 
+    /// Returns a rough estimate of security level which does not depend on the length of the
+    /// execution trace; use `SecurityEstimate` for conjectured and provable security levels.
     pub fn security_level(&self, optimistic: bool) -> u32 {
         let one_over_rho = (self.extension_factor() / MAX_CONSTRAINT_DEGREE) as u32;
         let security_factor = 31 - one_over_rho.leading_zeros(); // same as log2(one_over_rho)
@@ -77,6 +91,26 @@ impl ProofOptions {
 
         return result;
     }
+
+    /// Checks that the options are within the same bounds as enforced by `ProofOptions::new()`;
+    /// this should be used for options which were de-serialized from untrusted sources.
+    pub fn validate(&self) -> Result<(), ProofValidationError> {
+        let min_extension_factor = MIN_EXTENSION_FACTOR.trailing_zeros() as u8;
+        let max_extension_factor = MAX_EXTENSION_FACTOR.trailing_zeros() as u8;
+        if self.extension_factor < min_extension_factor || self.extension_factor > max_extension_factor {
+            return Err(ProofValidationError::InvalidExtensionFactor(self.extension_factor));
+        }
+
+        if self.num_queries == 0 || self.num_queries as usize > MAX_NUM_QUERIES {
+            return Err(ProofValidationError::InvalidNumQueries(self.num_queries));
+        }
+
+        if self.grinding_factor as u32 > MAX_GRINDING_FACTOR {
+            return Err(ProofValidationError::InvalidGrindingFactor(self.grinding_factor));
+        }
+
+        return Ok(());
+    }
 }
 
 impl Default for ProofOptions {
@@ -92,20 +126,60 @@ impl Default for ProofOptions {
 
 }
 
+// SCALE ENCODING / DECODING
+// ================================================================================================
+
+/// Encodes proof options as 4 bytes: extension factor (as power of 2), number of queries,
+/// grinding factor, and hash function identifier. Decoding rejects values which fail
+/// `validate()`.
+impl Encode for ProofOptions {
+
+    fn size_hint(&self) -> usize {
+        return 4;
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        // ProofOptions can only be built with hash functions which have identifiers; an unknown
+        // identifier would be rejected when the options are decoded
+        let hash_fn_id = get_hash_fn_id(self.hash_fn).unwrap_or(u8::MAX);
+        dest.push_byte(self.extension_factor);
+        dest.push_byte(self.num_queries);
+        dest.push_byte(self.grinding_factor);
+        dest.push_byte(hash_fn_id);
+    }
+}
+
+impl Decode for ProofOptions {
+
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        let extension_factor = input.read_byte()?;
+        let num_queries = input.read_byte()?;
+        let grinding_factor = input.read_byte()?;
+        let hash_fn = get_hash_fn(input.read_byte()?)
+            .ok_or("unsupported hash function")?;
+
+        let options = ProofOptions { extension_factor, num_queries, grinding_factor, hash_fn };
+        if options.validate().is_err() {
+            return Err("invalid proof options".into());
+        }
+        return Ok(options);
+    }
+}
+
 // HASH FUNCTION SERIALIZATION / DE-SERIALIZATION
 // ================================================================================================
 mod hash_fn_serialization {
 
     use serde::{ Serializer, Deserializer, Deserialize, ser, de };
-    use crate::crypto::{ HashFunction, hash };
+    use crate::crypto::{ HashFunction, get_hash_fn_id, get_hash_fn };
 
     pub fn serialize<S>(hf: &HashFunction, s: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer
     {
-        match *hf as usize {
-            f if f == hash::blake3 as usize => s.serialize_u8(0),
-            _ => Err(ser::Error::custom("unsupported hash function"))?
+        match get_hash_fn_id(*hf) {
+            Some(id) => s.serialize_u8(id),
+            None => Err(ser::Error::custom("unsupported hash function"))?
         }
     }
     
@@ -113,9 +187,9 @@ mod hash_fn_serialization {
     where
         D: Deserializer<'de>
     {
-        match Deserialize::deserialize(deserializer)? {
-            0u8 => Ok(hash::blake3),
-            _ => Err(de::Error::custom("unsupported hash function"))
+        match get_hash_fn(Deserialize::deserialize(deserializer)?) {
+            Some(hf) => Ok(hf),
+            None => Err(de::Error::custom("unsupported hash function"))
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,127 @@
+use crate::crypto::{ HashFunction, get_hash_fn_id };
+use crate::{ MAX_CONTEXT_DEPTH, MAX_LOOP_DEPTH, MAX_STACK_DEPTH };
+use super::{ StarkProof, PolicyViolation, proof::MAX_DOMAIN_DEPTH };
+use sp_std::vec::Vec;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Describes which proofs a verifier is willing to accept. Proof options are chosen by the
+/// prover, so without a policy a verifier would accept proofs with arbitrarily low security
+/// or proofs which are arbitrarily expensive to verify.
+#[derive(Clone, Debug)]
+pub struct VerifierPolicy {
+    min_security_level  : u32,
+    hash_fn_ids         : Vec<u8>,
+    max_domain_depth    : u8,
+    max_op_count        : u128,
+    max_ctx_depth       : usize,
+    max_loop_depth      : usize,
+    max_stack_depth     : usize,
+}
+
+// VERIFIER POLICY IMPLEMENTATION
+// ================================================================================================
+impl VerifierPolicy {
+
+    /// Returns a policy which accepts proofs with (conjectured) security level of at least
+    /// `min_security_level` bits; all other limits are set to the maximums supported by the VM
+    /// and the verifier.
+    pub fn new(min_security_level: u32) -> VerifierPolicy {
+        return VerifierPolicy {
+            min_security_level  : min_security_level,
+            hash_fn_ids         : Vec::new(),
+            max_domain_depth    : MAX_DOMAIN_DEPTH as u8,
+            max_op_count        : u32::MAX as u128,
+            max_ctx_depth       : MAX_CONTEXT_DEPTH,
+            max_loop_depth      : MAX_LOOP_DEPTH,
+            max_stack_depth     : MAX_STACK_DEPTH,
+        };
+    }
+
+    /// Restricts the policy to proofs generated with one of the specified hash functions;
+    /// panics if any of the hash functions cannot be used in proofs.
+    pub fn with_hash_fns(mut self, hash_fns: &[HashFunction]) -> VerifierPolicy {
+        assert!(hash_fns.len() > 0, "at least one hash function must be allowed");
+        self.hash_fn_ids = hash_fns.iter()
+            .map(|&hash_fn| get_hash_fn_id(hash_fn).expect("unsupported hash function"))
+            .collect();
+        return self;
+    }
+
+    /// Restricts the policy to proofs with evaluation domain of at most 2^`max_depth` elements;
+    /// the depth cannot be raised above the maximum supported by the verifier.
+    pub fn with_max_domain_depth(mut self, max_depth: u8) -> VerifierPolicy {
+        self.max_domain_depth = sp_std::cmp::min(max_depth, MAX_DOMAIN_DEPTH as u8);
+        return self;
+    }
+
+    /// Restricts the policy to proofs of programs which executed at most `max_op_count` operations.
+    pub fn with_max_op_count(mut self, max_op_count: u128) -> VerifierPolicy {
+        self.max_op_count = max_op_count;
+        return self;
+    }
+
+    /// Restricts the policy to proofs with context stack depth of at most `max_depth`.
+    pub fn with_max_ctx_depth(mut self, max_depth: usize) -> VerifierPolicy {
+        self.max_ctx_depth = max_depth;
+        return self;
+    }
+
+    /// Restricts the policy to proofs with loop stack depth of at most `max_depth`.
+    pub fn with_max_loop_depth(mut self, max_depth: usize) -> VerifierPolicy {
+        self.max_loop_depth = max_depth;
+        return self;
+    }
+
+    /// Restricts the policy to proofs with user stack depth of at most `max_depth`.
+    pub fn with_max_stack_depth(mut self, max_depth: usize) -> VerifierPolicy {
+        self.max_stack_depth = max_depth;
+        return self;
+    }
+
+    pub fn min_security_level(&self) -> u32 {
+        return self.min_security_level;
+    }
+
+    /// Checks the proof against the policy. Only values from the proof header are examined, so
+    /// the check is cheap; however, proof options must be valid before this function is called.
+    pub fn check(&self, proof: &StarkProof) -> Result<(), PolicyViolation> {
+        let options = proof.options();
+
+        let level = options.security_level(true);
+        if level < self.min_security_level {
+            return Err(PolicyViolation::SecurityLevelTooLow { level, min_level: self.min_security_level });
+        }
+
+        if self.hash_fn_ids.len() > 0 {
+            match get_hash_fn_id(options.hash_fn()) {
+                Some(id) if self.hash_fn_ids.contains(&id) => (),
+                _ => return Err(PolicyViolation::HashFunctionNotAllowed),
+            }
+        }
+
+        let depth = proof.trace_info().domain_depth;
+        if depth > self.max_domain_depth {
+            return Err(PolicyViolation::DomainDepthTooLarge { depth, max_depth: self.max_domain_depth });
+        }
+
+        if proof.op_count() > self.max_op_count {
+            return Err(PolicyViolation::OpCountTooLarge { op_count: proof.op_count(), max_op_count: self.max_op_count });
+        }
+
+        if proof.stack_depth() > self.max_stack_depth {
+            return Err(PolicyViolation::StackDepthTooLarge { depth: proof.stack_depth(), max_depth: self.max_stack_depth });
+        }
+
+        if proof.ctx_depth() > self.max_ctx_depth {
+            return Err(PolicyViolation::ContextDepthTooLarge { depth: proof.ctx_depth(), max_depth: self.max_ctx_depth });
+        }
+
+        if proof.loop_depth() > self.max_loop_depth {
+            return Err(PolicyViolation::LoopDepthTooLarge { depth: proof.loop_depth(), max_depth: self.max_loop_depth });
+        }
+
+        return Ok(());
+    }
+}
@@ -1,9 +1,20 @@
 use serde::{ Serialize, Deserialize };
+use codec::{ Encode, Decode };
 use crate::crypto::{ BatchMerkleProof };
-use crate::stark::{ fri::FriProof, TraceState, ProofOptions };
+use crate::math::field;
+use crate::stark::{ fri::{ FriProof, MAX_REMAINDER_LENGTH }, TraceState, ProofOptions, ProofValidationError, SecurityEstimate };
 use crate::utils::{ uninit_vector, as_bytes };
-use sp_std::vec::Vec;
+use crate::{ MIN_TRACE_LENGTH, MAX_CONTEXT_DEPTH, MAX_LOOP_DEPTH, MAX_STACK_DEPTH };
+use sp_std::{ fmt, mem, vec::Vec };
 use alloc::string::String;
+
+// CONSTANTS
+// ================================================================================================
+
+// roots of unity in the field exist for domains of up to 2^40 elements, but query positions
+// are sampled as i32 values, and so the domain cannot have more than 2^30 elements
+pub const MAX_DOMAIN_DEPTH: usize = 30;
+
 // TYPES AND INTERFACES
 // ================================================================================================
 
@@ -21,7 +32,7 @@ pub struct ProgramAssembly{
 }
 
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, Encode, Decode)]
 pub struct StarkProof {
     trace_root          : [u8; 32],
     trace_info          : TraceInfo,
@@ -35,13 +46,13 @@ pub struct StarkProof {
     options             : ProofOptions
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Encode, Decode)]
 pub struct DeepValues {
     pub trace_at_z1     : Vec<u128>,
     pub trace_at_z2     : Vec<u128>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Encode, Decode)]
 pub struct TraceInfo {
     pub domain_depth    : u8,
     pub ctx_depth       : u8,
@@ -50,6 +61,51 @@ pub struct TraceInfo {
     pub op_count        : u32,
 }
 
+/// Breakdown of the size (in bytes) of a SCALE-encoded proof by component.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofStats {
+    pub total_size              : usize,
+    /// Commitment roots, trace info, options, proof-of-work nonce, and length prefixes.
+    pub header_size             : usize,
+    pub trace_nodes_size        : usize,
+    pub trace_evaluations_size  : usize,
+    pub constraint_proof_size   : usize,
+    pub deep_values_size        : usize,
+    pub fri_layers              : Vec<FriLayerStats>,
+    /// Remainder values and the root of their commitment.
+    pub fri_remainder_size      : usize,
+    /// Number of queries specified in proof options; the trace is queried at this many distinct
+    /// positions.
+    pub num_queries             : usize,
+    /// Number of distinct constraint tree leaves opened by the queries.
+    pub num_constraint_queries  : usize,
+    pub security                : SecurityEstimate,
+}
+
+/// Size (in bytes) of a single FRI layer of a SCALE-encoded proof.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FriLayerStats {
+    pub values_size             : usize,
+    /// Merkle authentication paths, the layer root, and the layer depth.
+    pub nodes_size              : usize,
+    /// Number of distinct positions at which the layer was queried.
+    pub num_queries             : usize,
+}
+
+// TRACE INFO IMPLEMENTATION
+// ================================================================================================
+impl TraceInfo {
+    pub fn new(domain_size: usize, ctx_depth: usize, loop_depth: usize, stack_depth: usize, op_count: u128) -> TraceInfo {
+        return TraceInfo {
+            domain_depth        : domain_size.trailing_zeros() as u8,
+            ctx_depth           : ctx_depth as u8,
+            loop_depth          : loop_depth as u8,
+            stack_depth         : stack_depth as u8,
+            op_count            : op_count as u32,
+        };
+    }
+}
+
 // STARK PROOF IMPLEMENTATION
 // ================================================================================================
 impl StarkProof {
@@ -62,20 +118,10 @@ impl StarkProof {
         deep_values         : DeepValues,
         degree_proof        : FriProof,
         pow_nonce           : u64,
-        op_count            : u128,
-        ctx_depth           : usize,
-        loop_depth          : usize,
-        stack_depth         : usize,
+        trace_info          : TraceInfo,
         options             : &ProofOptions ) -> StarkProof
     {
-        let trace_info = TraceInfo {
-            domain_depth        : trace_proof.depth,
-            ctx_depth           : ctx_depth as u8,
-            loop_depth          : loop_depth as u8,
-            stack_depth         : stack_depth as u8,
-            op_count            : op_count as u32,
-        };
-
+        debug_assert!(trace_info.domain_depth == trace_proof.depth, "inconsistent trace domain depth");
         return StarkProof {
             trace_root          : *trace_root,
             trace_info          : trace_info,
@@ -90,6 +136,111 @@ impl StarkProof {
         };
     }
 
+    /// Decodes a SCALE-encoded proof and validates it; returns an error if the bytes cannot be
+    /// decoded or if the decoded proof is malformed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<StarkProof, ProofValidationError> {
+        let mut input = bytes;
+        let proof = match StarkProof::decode(&mut input) {
+            Ok(proof) => proof,
+            Err(_) => return Err(ProofValidationError::DecodingFailed)
+        };
+        if input.len() != 0 {
+            return Err(ProofValidationError::DecodingFailed);
+        }
+        proof.validate()?;
+        return Ok(proof);
+    }
+
+    /// Checks that all values in the proof are consistent with each other and with the proof
+    /// options. This should be done for all proofs received from untrusted sources before
+    /// any other operations are performed with them.
+    pub fn validate(&self) -> Result<(), ProofValidationError> {
+
+        // validate proof options and trace info
+        self.options.validate()?;
+        let num_queries = self.options.num_queries();
+        let extension_factor = self.options.extension_factor();
+
+        let min_domain_depth = (MIN_TRACE_LENGTH * extension_factor).trailing_zeros() as usize;
+        let max_domain_depth = sp_std::cmp::min(MAX_DOMAIN_DEPTH, mem::size_of::<usize>() * 8 - 1);
+        let domain_depth = self.trace_info.domain_depth;
+        if (domain_depth as usize) < min_domain_depth || domain_depth as usize > max_domain_depth {
+            return Err(ProofValidationError::InvalidDomainDepth(domain_depth));
+        }
+
+        if self.trace_info.ctx_depth as usize > MAX_CONTEXT_DEPTH {
+            return Err(ProofValidationError::InvalidContextDepth(self.trace_info.ctx_depth));
+        }
+
+        if self.trace_info.loop_depth as usize > MAX_LOOP_DEPTH {
+            return Err(ProofValidationError::InvalidLoopDepth(self.trace_info.loop_depth));
+        }
+
+        // register traces of the stack are cut to the maximum depth the stack actually reached,
+        // so proofs can have fewer stack registers than the minimum stack depth
+        let stack_depth = self.trace_info.stack_depth as usize;
+        if stack_depth > MAX_STACK_DEPTH {
+            return Err(ProofValidationError::InvalidStackDepth(self.trace_info.stack_depth));
+        }
+
+        if self.trace_info.op_count as usize > self.trace_length() {
+            return Err(ProofValidationError::InvalidOpCount(self.trace_info.op_count));
+        }
+
+        // validate trace queries and DEEP values
+        let trace_width = TraceState::compute_decoder_width(self.ctx_depth(), self.loop_depth())
+            + stack_depth;
+
+        check_length("trace evaluations", self.trace_evaluations.len(), num_queries)?;
+        for row in self.trace_evaluations.iter() {
+            check_length("trace evaluations", row.len(), trace_width)?;
+            check_elements("trace evaluations", row)?;
+        }
+        check_nodes("trace nodes", &self.trace_nodes, num_queries, domain_depth)?;
+
+        check_length("DEEP values", self.deep_values.trace_at_z1.len(), trace_width)?;
+        check_length("DEEP values", self.deep_values.trace_at_z2.len(), trace_width)?;
+        check_elements("DEEP values", &self.deep_values.trace_at_z1)?;
+        check_elements("DEEP values", &self.deep_values.trace_at_z2)?;
+
+        // validate constraint queries; each leaf of the constraint tree contains 2 evaluations
+        check_depth("constraint proof", self.constraint_proof.depth, domain_depth - 1)?;
+        check_max_length("constraint proof values", self.constraint_proof.values.len(), num_queries)?;
+        for leaf in self.constraint_proof.values.iter() {
+            check_elements("constraint proof values", &[field::from_bytes(&leaf[..16]), field::from_bytes(&leaf[16..])])?;
+        }
+        check_nodes("constraint proof nodes", &self.constraint_proof.nodes, num_queries, domain_depth - 1)?;
+
+        // validate FRI proof; each FRI layer reduces the domain by a factor of 4
+        let mut column_length = self.domain_size();
+        let mut expected_depth = domain_depth;
+        let mut num_layers = 0;
+        while column_length > MAX_REMAINDER_LENGTH {
+            column_length = column_length / 4;
+            num_layers += 1;
+        }
+        check_length("FRI layers", self.degree_proof.layers.len(), num_layers)?;
+
+        for layer in self.degree_proof.layers.iter() {
+            expected_depth = expected_depth - 2;
+            check_depth("FRI layer", layer.depth, expected_depth)?;
+            check_max_length("FRI layer values", layer.values.len(), num_queries)?;
+            for values in layer.values.iter() {
+                check_elements("FRI layer values", values)?;
+            }
+            check_nodes("FRI layer nodes", &layer.nodes, num_queries, layer.depth)?;
+        }
+
+        check_length("FRI remainder", self.degree_proof.rem_values.len(), column_length)?;
+        check_elements("FRI remainder", &self.degree_proof.rem_values)?;
+
+        return Ok(());
+    }
+
+    pub fn trace_info(&self) -> &TraceInfo {
+        return &self.trace_info;
+    }
+
     pub fn trace_root(&self) -> &[u8; 32] {
         return &self.trace_root;
     }
@@ -137,6 +288,46 @@ impl StarkProof {
         return self.pow_nonce;
     }
 
+    /// Returns the size of each component of the SCALE-encoded proof, the number of distinct
+    /// query positions, and the security level of the proof; fails if the proof does not pass
+    /// `validate()`.
+    pub fn stats(&self) -> Result<ProofStats, ProofValidationError> {
+        self.validate()?;
+        let security = SecurityEstimate::from_trace_info(&self.options, &self.trace_info)
+            .ok_or(ProofValidationError::InvalidDomainDepth(self.trace_info.domain_depth))?;
+
+        let fri_layers = self.degree_proof.layers.iter().map(|layer| FriLayerStats {
+            values_size : layer.values.encoded_size(),
+            nodes_size  : layer.nodes.encoded_size() + layer.root.len() + layer.depth.encoded_size(),
+            num_queries : layer.values.len(),
+        }).collect::<Vec<_>>();
+
+        let trace_nodes_size = self.trace_nodes.encoded_size();
+        let trace_evaluations_size = self.trace_evaluations.encoded_size();
+        let constraint_proof_size = self.constraint_proof.encoded_size();
+        let deep_values_size = self.deep_values.encoded_size();
+        let fri_remainder_size = self.degree_proof.rem_values.encoded_size() + self.degree_proof.rem_root.len();
+
+        let total_size = self.encoded_size();
+        let component_size = trace_nodes_size + trace_evaluations_size + constraint_proof_size
+            + deep_values_size + fri_remainder_size
+            + fri_layers.iter().map(|l| l.values_size + l.nodes_size).sum::<usize>();
+
+        return Ok(ProofStats {
+            total_size,
+            header_size             : total_size - component_size,
+            trace_nodes_size,
+            trace_evaluations_size,
+            constraint_proof_size,
+            deep_values_size,
+            fri_layers,
+            fri_remainder_size,
+            num_queries             : self.options.num_queries(),
+            num_constraint_queries  : self.constraint_proof.values.len(),
+            security,
+        });
+    }
+
     // TRACE INFO
     // -------------------------------------------------------------------------------------------
     pub fn trace_length(&self) -> usize {
@@ -176,4 +367,66 @@ impl StarkProof {
             self.stack_depth(),
             &self.deep_values.trace_at_z2);
     }
-}
\ No newline at end of file
+}
+
+// PROOF STATS IMPLEMENTATION
+// ================================================================================================
+impl fmt::Display for ProofStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "proof size:           {} bytes", self.total_size)?;
+        writeln!(f, "  header:             {} bytes", self.header_size)?;
+        writeln!(f, "  trace nodes:        {} bytes", self.trace_nodes_size)?;
+        writeln!(f, "  trace evaluations:  {} bytes", self.trace_evaluations_size)?;
+        writeln!(f, "  constraint proof:   {} bytes", self.constraint_proof_size)?;
+        writeln!(f, "  deep values:        {} bytes", self.deep_values_size)?;
+        for (i, layer) in self.fri_layers.iter().enumerate() {
+            writeln!(f, "  FRI layer {:<2}:       {} bytes of values, {} bytes of nodes ({} queries)",
+                i, layer.values_size, layer.nodes_size, layer.num_queries)?;
+        }
+        writeln!(f, "  FRI remainder:      {} bytes", self.fri_remainder_size)?;
+        writeln!(f, "queries:              {} requested, {} constraint leaves",
+            self.num_queries, self.num_constraint_queries)?;
+        return write!(f, "security:             {} bits conjectured, {} bits provable",
+            self.security.conjectured, self.security.provable);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn check_length(name: &'static str, actual: usize, expected: usize) -> Result<(), ProofValidationError> {
+    if actual != expected {
+        return Err(ProofValidationError::InvalidLength { name, expected, actual });
+    }
+    return Ok(());
+}
+
+fn check_max_length(name: &'static str, actual: usize, max: usize) -> Result<(), ProofValidationError> {
+    if actual > max {
+        return Err(ProofValidationError::LengthExceeded { name, max, actual });
+    }
+    return Ok(());
+}
+
+fn check_depth(name: &'static str, actual: u8, expected: u8) -> Result<(), ProofValidationError> {
+    if actual != expected {
+        return Err(ProofValidationError::InvalidTreeDepth { name, expected, actual });
+    }
+    return Ok(());
+}
+
+fn check_elements(name: &'static str, values: &[u128]) -> Result<(), ProofValidationError> {
+    if values.iter().any(|&v| v >= field::M) {
+        return Err(ProofValidationError::NonCanonicalFieldElement { name });
+    }
+    return Ok(());
+}
+
+/// Makes sure batch Merkle proof nodes contain at most one path per query, and that
+/// no path is longer than the depth of the tree.
+fn check_nodes(name: &'static str, nodes: &[Vec<[u8; 32]>], num_queries: usize, depth: u8) -> Result<(), ProofValidationError> {
+    check_max_length(name, nodes.len(), num_queries)?;
+    for path in nodes.iter() {
+        check_max_length(name, path.len(), depth as usize)?;
+    }
+    return Ok(());
+}
@@ -4,7 +4,7 @@ use crate::{
     crypto::MerkleTree,
 };
 use super::{
-    ProofOptions, StarkProof, CompositionCoefficients, DeepValues, fri, utils,
+    ProofOptions, StarkProof, TraceInfo, CompositionCoefficients, DeepValues, fri, utils,
     trace::{ TraceTable, TraceState },
     constraints::{ ConstraintTable, ConstraintPoly },
     MAX_CONSTRAINT_DEGREE,
@@ -16,10 +16,17 @@ use sp_std::vec::Vec;
 // PROVER FUNCTION
 // ================================================================================================
 
-pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options: &ProofOptions) -> StarkProof {
-    // 1 ----- extend execution trace -------------------------------------------------------------
-
+pub fn prove(trace: &mut TraceTable, program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], options: &ProofOptions) -> StarkProof {
+    // 0 ----- initialize public coin with the public statement -----------------------------------
+    let trace_info = TraceInfo::new(
+        trace.domain_size(),
+        trace.ctx_depth(),
+        trace.loop_depth(),
+        trace.stack_depth(),
+        trace.get_last_state().op_counter());
+    let mut coin = utils::PublicCoin::new(program_hash, inputs, outputs, &trace_info, options.hash_fn());
 
+    // 1 ----- extend execution trace -------------------------------------------------------------
 
     // build LDE domain and LDE twiddles (for FFT evaluation over LDE domain)
     let lde_root = field::get_root_of_unity(trace.domain_size());
@@ -37,12 +44,13 @@ pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options:
 
     // 2 ----- build Merkle tree from the extended execution trace ------------------------------------
     let trace_tree = trace.build_merkle_tree(options.hash_fn());
+    coin.absorb(trace_tree.root());
 
 
     // 3 ----- evaluate constraints ---------------------------------------------------------------
     
-    // initialize constraint evaluation table
-    let mut constraints = ConstraintTable::new(&trace, trace_tree.root(), inputs, outputs);
+    // initialize constraint evaluation table; constraint coefficients are drawn from the coin
+    let mut constraints = ConstraintTable::new(&trace, &coin.seed(), inputs, outputs);
 
     // allocate space to hold current and next states for constraint evaluations
     let mut current = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
@@ -82,12 +90,13 @@ pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options:
     // put evaluations into a Merkle tree; 4 evaluations per leaf
     let constraint_evaluations = evaluations_to_leaves(constraint_evaluations);
     let constraint_tree = MerkleTree::new(constraint_evaluations, options.hash_fn());
+    coin.absorb(constraint_tree.root());
 
     // 6 ----- build and evaluate deep composition polynomial -------------------------------------
 
     // combine trace and constraint polynomials into the final deep composition polynomial
-    let seed = constraint_tree.root();
-    let (composition_poly, deep_values) = build_composition_poly(&trace, constraint_poly, seed);
+    let seed = coin.seed();
+    let (composition_poly, deep_values) = build_composition_poly(&trace, constraint_poly, &seed);
 
     // evaluate the composition polynomial over LDE domain
     let mut composed_evaluations = composition_poly;
@@ -114,8 +123,8 @@ pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options:
     }
 
     // derive a seed from the combined roots
-    let mut seed = [0u8; 32];
-    options.hash_fn()(&fri_roots, &mut seed);
+    coin.absorb(&fri_roots);
+    let seed = coin.seed();
 
     // apply proof-of-work to get a new seed
     let (seed, pow_nonce) = utils::find_pow_nonce(seed, &options);
@@ -149,10 +158,7 @@ pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options:
         deep_values,
         fri_proof,
         pow_nonce,
-        trace.get_last_state().op_counter(),
-        trace.ctx_depth(),
-        trace.loop_depth(),
-        trace.stack_depth(),
+        trace_info,
         &options);
 
     return proof;
@@ -0,0 +1,304 @@
+use crate::crypto::{ HashFunction, get_hash_fn_id };
+use crate::math::field;
+use crate::MIN_TRACE_LENGTH;
+use super::{ ProofOptions, TraceInfo, MAX_CONSTRAINT_DEGREE, utils };
+use super::options::{ MIN_EXTENSION_FACTOR, MAX_EXTENSION_FACTOR, MAX_NUM_QUERIES, MAX_GRINDING_FACTOR };
+use sp_std::cmp;
+
+// CONSTANTS
+// ================================================================================================
+
+/// All supported hash functions produce 256-bit digests; so, collision resistance of the
+/// Merkle commitments is at most 128 bits.
+const COLLISION_RESISTANCE: u32 = 128;
+
+/// Grinding contributes to conjectured security only when queries provide at least this many bits.
+const GRINDING_CONTRIBUTION_FLOOR: u32 = 80;
+
+/// Number of out-of-domain points at which the trace is opened (z and z * g).
+const NUM_DEEP_OPENINGS: u128 = 2;
+
+/// Range of values for the proximity parameter m of the list-decoding regime; the value which
+/// yields the highest provable security is selected.
+const MIN_PROXIMITY_PARAMETER: u128 = 3;
+const MAX_PROXIMITY_PARAMETER: u128 = 64;
+
+/// Logarithms are computed as fixed-point numbers with this many fractional bits.
+const FRACTION_BITS: u32 = 16;
+const ONE: i64 = 1 << FRACTION_BITS;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Security level (in bits) of a proof generated with specific options for an execution trace
+/// of a specific length.
+///
+/// * `conjectured` security relies on the ethSTARK conjecture about the soundness of FRI
+///   queries, and is bounded by the size of the field relative to the evaluation domain.
+/// * `provable` security is the round-by-round soundness of the protocol in the list-decoding
+///   regime (Johnson bound), accounting for the DEEP-ALI composition, FRI commit and query
+///   phases (see ethSTARK documentation, eprint 2021/582, and eprint 2022/1216).
+///
+/// Both values are capped by collision resistance of the hash function.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SecurityEstimate {
+    pub conjectured : u32,
+    pub provable    : u32,
+}
+
+// SECURITY ESTIMATE IMPLEMENTATION
+// ================================================================================================
+impl SecurityEstimate {
+
+    /// Estimates security of proofs generated with the specified options for an execution trace
+    /// with `trace_length` steps; `trace_length` must be a power of 2.
+    pub fn new(options: &ProofOptions, trace_length: usize) -> SecurityEstimate {
+        assert!(trace_length.is_power_of_two(), "trace length must be a power of 2");
+        assert!(trace_length >= MIN_TRACE_LENGTH, "trace length must be at least {}", MIN_TRACE_LENGTH);
+
+        let params = ProtocolParams::new(options, trace_length);
+        return SecurityEstimate {
+            conjectured : cmp::min(params.conjectured_security(), COLLISION_RESISTANCE),
+            provable    : cmp::min(params.provable_security(), COLLISION_RESISTANCE),
+        };
+    }
+
+    /// Estimates security of proofs generated with the specified options for an execution trace
+    /// described by `trace_info`; trace info may come from an untrusted proof, so None is returned
+    /// if it does not describe a trace of at least the minimum length for these options.
+    pub fn from_trace_info(options: &ProofOptions, trace_info: &TraceInfo) -> Option<SecurityEstimate> {
+        let domain_size = 1usize.checked_shl(trace_info.domain_depth as u32)?;
+        let trace_length = domain_size / options.extension_factor();
+        if trace_length < MIN_TRACE_LENGTH {
+            return None;
+        }
+        return Some(SecurityEstimate::new(options, trace_length));
+    }
+}
+
+/// Returns the cheapest options which provide at least `target_bits` of security (provable
+/// security if `provable` is true, or conjectured security otherwise) for an execution trace
+/// with `trace_length` steps; returns None if no supported options provide such security, or
+/// if `trace_length`, `grinding_factor`, or `hash_fn` cannot be used in proofs.
+///
+/// The smallest extension factor is preferred since it determines the amount of work done by
+/// the prover; for that extension factor, the smallest number of queries is selected.
+pub fn get_cheapest_options(
+    target_bits     : u32,
+    trace_length    : usize,
+    grinding_factor : u32,
+    hash_fn         : HashFunction,
+    provable        : bool) -> Option<ProofOptions>
+{
+    if !trace_length.is_power_of_two() || trace_length < MIN_TRACE_LENGTH
+        || grinding_factor > MAX_GRINDING_FACTOR || get_hash_fn_id(hash_fn).is_none()
+    {
+        return None;
+    }
+
+    let mut extension_factor = MIN_EXTENSION_FACTOR;
+    while extension_factor <= MAX_EXTENSION_FACTOR {
+        for num_queries in 1..(MAX_NUM_QUERIES + 1) {
+            let options = ProofOptions::new(extension_factor, num_queries, grinding_factor, hash_fn);
+            let estimate = SecurityEstimate::new(&options, trace_length);
+            let bits = if provable { estimate.provable } else { estimate.conjectured };
+            if bits >= target_bits {
+                return Some(options);
+            }
+        }
+        extension_factor *= 2;
+    }
+    return None;
+}
+
+// PROTOCOL PARAMETERS
+// ================================================================================================
+
+/// Logarithms of protocol parameters which affect soundness; all values are fixed-point
+/// numbers with FRACTION_BITS fractional bits.
+struct ProtocolParams {
+    field_bits      : i64,      // log2(|F|)
+    log_domain_size : i64,      // log2(|D|)
+    log_inv_rho     : i64,      // log2(1 / rho), where rho = (deg(composition) + 1) / |D|
+    log_deep_degree : i64,      // log2 of the degree of DEEP composition numerator
+    num_queries     : i64,
+    grinding_factor : i64,
+}
+
+impl ProtocolParams {
+
+    fn new(options: &ProofOptions, trace_length: usize) -> ProtocolParams {
+        let domain_size = (trace_length * options.extension_factor()) as u128;
+        let composition_size = (utils::get_composition_degree(trace_length) + 1) as u128;
+
+        // the DEEP error depends on the maximum degree of transition constraints times the
+        // number of points at which trace polynomials are opened
+        let n = trace_length as u128;
+        let deep_degree = (MAX_CONSTRAINT_DEGREE as u128) * (n + NUM_DEEP_OPENINGS - 1) + (n - 1);
+
+        return ProtocolParams {
+            field_bits      : log2(field::MODULUS),
+            log_domain_size : log2(domain_size),
+            log_inv_rho     : log2(domain_size) - log2(composition_size),
+            log_deep_degree : log2(deep_degree),
+            num_queries     : options.num_queries() as i64,
+            grinding_factor : options.grinding_factor() as i64,
+        };
+    }
+
+    /// Security is min(field security, query security) - 1, where field security is
+    /// log2(|F|) - log2(|D|), and query security is num_queries * log2(1 / rho), plus
+    /// grinding factor when queries alone provide enough security.
+    fn conjectured_security(&self) -> u32 {
+        let field_security = self.field_bits - self.log_domain_size;
+
+        let mut query_security = self.num_queries * self.log_inv_rho;
+        if query_security >= (GRINDING_CONTRIBUTION_FLOOR as i64) * ONE {
+            query_security += self.grinding_factor * ONE;
+        }
+
+        return to_bits(cmp::min(field_security, query_security) - ONE);
+    }
+
+    /// Returns the highest round-by-round soundness over all supported values of the
+    /// proximity parameter m.
+    fn provable_security(&self) -> u32 {
+        let mut result = 0;
+        for m in MIN_PROXIMITY_PARAMETER..(MAX_PROXIMITY_PARAMETER + 1) {
+            result = cmp::max(result, to_bits(self.provable_security_for_m(m)));
+        }
+        return result;
+    }
+
+    fn provable_security_for_m(&self, m: u128) -> i64 {
+        // log2(m + 1/2)
+        let log_m = log2(2 * m + 1) - ONE;
+        // log2(1 / sqrt(rho))
+        let log_inv_sqrt_rho = self.log_inv_rho / 2;
+        // size of the list of codewords within the Johnson bound: L+ = (m + 1/2) / sqrt(rho)
+        let log_list_size = log_m + log_inv_sqrt_rho;
+
+        // ALI error: L+ / |F|; constraints are combined using independent random coefficients
+        let ali = self.field_bits - log_list_size;
+
+        // DEEP error: L+ * (d * (n + k - 1) + (n - 1)) / |F|, where d is the maximum constraint
+        // degree and k is the number of out-of-domain openings
+        let deep = self.field_bits - log_list_size - self.log_deep_degree;
+
+        // FRI commit phase error, dominated by (m + 1/2)^7 * |D|^2 / (2 * rho^(3/2) * |F|)
+        let fri_commit = self.field_bits + ONE
+            - 7 * log_m
+            - (3 * self.log_inv_rho) / 2
+            - 2 * self.log_domain_size;
+
+        // FRI query phase error: alpha^num_queries, where alpha = (1 + 1/2m) * sqrt(rho);
+        // grinding adds proof-of-work bits before queries are drawn
+        let log_inv_alpha = log_inv_sqrt_rho - (log2(2 * m + 1) - log2(2 * m));
+        let fri_query = self.num_queries * log_inv_alpha + self.grinding_factor * ONE;
+
+        return cmp::min(cmp::min(ali, deep), cmp::min(fri_commit, fri_query));
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns log2(x) as a fixed-point number with FRACTION_BITS fractional bits, rounded down.
+fn log2(x: u128) -> i64 {
+    assert!(x > 0, "cannot compute logarithm of zero");
+    let int_part = 127 - x.leading_zeros();
+
+    // normalize x into [1, 2) represented with 63 fractional bits
+    let mut y = if int_part >= 63 { x >> (int_part - 63) } else { x << (63 - int_part) };
+
+    // compute fractional bits one at a time by repeated squaring
+    let mut result = (int_part as i64) << FRACTION_BITS;
+    for i in (0..FRACTION_BITS).rev() {
+        y = (y * y) >> 63;
+        if y >= (1u128 << 64) {
+            y >>= 1;
+            result |= 1 << i;
+        }
+    }
+
+    return result;
+}
+
+/// Converts a fixed-point number of bits into a whole number of bits, rounding down.
+fn to_bits(value: i64) -> u32 {
+    if value <= 0 { return 0; }
+    return (value >> FRACTION_BITS) as u32;
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::crypto::hash;
+    use crate::stark::{ ProofOptions, TraceInfo };
+    use super::{ SecurityEstimate, get_cheapest_options, log2, ONE };
+
+    #[test]
+    fn fixed_point_log2() {
+        assert_eq!(0, log2(1));
+        assert_eq!(10 * ONE, log2(1024));
+        assert_eq!(127 * ONE, log2(1u128 << 127));
+        // log2(3) = 1.58496...
+        assert_eq!(103872, log2(3));
+        assert_eq!(127 * ONE + 103872 - ONE, log2(3u128 << 126));
+    }
+
+    #[test]
+    fn security_estimate() {
+        let options = ProofOptions::default();
+        let estimate = SecurityEstimate::new(&options, 1024);
+        assert!(estimate.provable < estimate.conjectured);
+        assert!(estimate.conjectured <= 128);
+
+        // conjectured security is bounded by the field size relative to the domain size
+        let short = SecurityEstimate::new(&options, 16);
+        let long = SecurityEstimate::new(&options, 1 << 20);
+        assert!(long.conjectured <= short.conjectured);
+        assert!(long.provable < short.provable);
+
+        // more queries never reduce security
+        let options2 = ProofOptions::new(32, 80, 20, hash::blake3);
+        let estimate2 = SecurityEstimate::new(&options2, 1024);
+        assert!(estimate2.conjectured >= estimate.conjectured);
+        assert!(estimate2.provable > estimate.provable);
+
+        // trace info of a proof yields the same estimate as the trace length
+        let trace_info = TraceInfo::new(1024 * 32, 1, 1, 8, 1000);
+        assert_eq!(Some(estimate), SecurityEstimate::from_trace_info(&options, &trace_info));
+
+        // trace info with a domain which does not fit into usize or is too small yields no estimate
+        let mut bad_info = trace_info.clone();
+        bad_info.domain_depth = 200;
+        assert_eq!(None, SecurityEstimate::from_trace_info(&options, &bad_info));
+        bad_info.domain_depth = 8;
+        assert_eq!(None, SecurityEstimate::from_trace_info(&options, &bad_info));
+    }
+
+    #[test]
+    fn cheapest_options() {
+        let options = get_cheapest_options(96, 1024, 16, hash::blake3, false).unwrap();
+        let estimate = SecurityEstimate::new(&options, 1024);
+        assert!(estimate.conjectured >= 96);
+
+        // one query fewer must not be enough for the selected extension factor
+        let options2 = ProofOptions::new(options.extension_factor(), options.num_queries() - 1, 16, hash::blake3);
+        assert!(SecurityEstimate::new(&options2, 1024).conjectured < 96);
+
+        let options = get_cheapest_options(80, 1024, 16, hash::blake3, true).unwrap();
+        assert!(SecurityEstimate::new(&options, 1024).provable >= 80);
+
+        // security beyond collision resistance of the hash function cannot be achieved
+        assert!(get_cheapest_options(129, 1024, 16, hash::blake3, false).is_none());
+
+        // parameters which cannot be used in proofs
+        assert!(get_cheapest_options(96, 1024, 33, hash::blake3, false).is_none());
+        assert!(get_cheapest_options(96, 1000, 16, hash::blake3, false).is_none());
+        assert!(get_cheapest_options(96, 8, 16, hash::blake3, false).is_none());
+    }
+}
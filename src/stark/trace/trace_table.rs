@@ -376,7 +376,7 @@ mod tests {
             ProgramBlock::Span(Span::new(instructions, HashMap::new()))
         ]));
         let inputs = ProgramInputs::from_public(&[1, 0]);
-        let (trace, ctx_depth, loop_depth) = execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = execute(&program, &inputs).unwrap();
         return TraceTable::new(trace, ctx_depth, loop_depth, EXT_FACTOR);
     }
 }
\ No newline at end of file
@@ -17,6 +17,9 @@ pub use coefficients::{ ConstraintCoefficients, CompositionCoefficients };
 mod proof_of_work;
 pub use proof_of_work::{ find_pow_nonce, verify_pow_nonce };
 
+mod public_coin;
+pub use public_coin::{ PublicCoin };
+
 pub fn get_composition_degree(trace_length: usize) -> usize {
     return (MAX_CONSTRAINT_DEGREE - 1) * trace_length - 1;
 }
@@ -1,6 +1,5 @@
 use sp_std::slice;
-use alloc::string::String;
-use crate::stark::{ ProofOptions };
+use crate::stark::{ ProofOptions, VerificationError };
 
 pub fn find_pow_nonce(seed: [u8; 32], options: &ProofOptions) -> ([u8; 32], u64) {
 
@@ -32,7 +31,7 @@ pub fn find_pow_nonce(seed: [u8; 32], options: &ProofOptions) -> ([u8; 32], u64)
     return (result, inputs[4]);
 }
 
-pub fn verify_pow_nonce(seed: [u8; 32], nonce: u64, options: &ProofOptions) -> Result<[u8; 32], String> {
+pub fn verify_pow_nonce(seed: [u8; 32], nonce: u64, options: &ProofOptions) -> Result<[u8; 32], VerificationError> {
 
     let hash = options.hash_fn();
 
@@ -48,7 +47,9 @@ pub fn verify_pow_nonce(seed: [u8; 32], nonce: u64, options: &ProofOptions) -> R
 
     hash(&input_bytes, &mut output_bytes);
     if output[0].trailing_zeros() < options.grinding_factor() {
-        return Err(String::from("seed proof-of-work verification failed"));
+        return Err(VerificationError::InsufficientProofOfWork {
+            grinding_factor: options.grinding_factor()
+        });
     }
 
     let mut result = [0; 32];
@@ -0,0 +1,50 @@
+use codec::Encode;
+use crate::crypto::HashFunction;
+use crate::stark::TraceInfo;
+use sp_std::vec::Vec;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Fiat-Shamir transcript shared by the prover and the verifier. Every value absorbed into the
+/// coin is hashed together with the current seed, so all randomness drawn from the coin depends
+/// on the public statement and on all commitments made up to that point.
+pub struct PublicCoin {
+    hash_fn : HashFunction,
+    seed    : [u8; 32],
+}
+
+// PUBLIC COIN IMPLEMENTATION
+// ================================================================================================
+impl PublicCoin {
+
+    /// Returns a new public coin initialized with the public statement: program hash,
+    /// public inputs, outputs, and info about the shape of the execution trace.
+    pub fn new(
+        program_hash: &[u8; 32],
+        inputs      : &[u128],
+        outputs     : &[u128],
+        trace_info  : &TraceInfo,
+        hash_fn     : HashFunction) -> PublicCoin
+    {
+        let mut coin = PublicCoin { hash_fn, seed: [0u8; 32] };
+        coin.absorb(program_hash);
+        coin.absorb(&inputs.encode());
+        coin.absorb(&outputs.encode());
+        coin.absorb(&trace_info.encode());
+        return coin;
+    }
+
+    /// Updates the seed of the coin to be hash(seed || data).
+    pub fn absorb(&mut self, data: &[u8]) {
+        let mut buf = Vec::with_capacity(self.seed.len() + data.len());
+        buf.extend_from_slice(&self.seed);
+        buf.extend_from_slice(data);
+        (self.hash_fn)(&buf, &mut self.seed);
+    }
+
+    /// Returns the current seed of the coin.
+    pub fn seed(&self) -> [u8; 32] {
+        return self.seed;
+    }
+}
@@ -3,18 +3,60 @@ use crate::{
     crypto::{ MerkleTree },
     MIN_TRACE_LENGTH
 };
-use super::{ StarkProof, TraceState, ConstraintEvaluator, CompositionCoefficients, fri, utils };
-use alloc::string::String;
+use super::{ StarkProof, TraceState, ConstraintEvaluator, CompositionCoefficients, VerificationError, VerifierPolicy, fri, utils };
+use super::constraints::{ EvaluatorParams, parse_program_hash };
 use sp_std::{vec, vec::Vec};
 
 
 // VERIFIER FUNCTION
 // ================================================================================================
 
-pub fn verify(program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> Result<bool, String>
+pub fn verify(program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> Result<bool, VerificationError>
+{
+    // 0 ----- Make sure the proof is well-formed before doing anything else ----------------------
+    proof.validate()?;
+
+    let params = EvaluatorParams::from_proof(proof);
+    return verify_with_params(program_hash, &parse_program_hash(program_hash), inputs, outputs, proof, &params);
+}
+
+/// Same as `verify()`, but rejects proofs which do not satisfy the `policy` before any
+/// expensive verification steps are executed.
+pub fn verify_with_policy(
+    program_hash: &[u8; 32],
+    inputs      : &[u128],
+    outputs     : &[u128],
+    proof       : &StarkProof,
+    policy      : &VerifierPolicy) -> Result<bool, VerificationError>
+{
+    // options must be valid before security level of the proof can be estimated
+    proof.options().validate()?;
+    policy.check(proof)?;
+
+    return verify(program_hash, inputs, outputs, proof);
+}
+
+/// Verifies a proof which has already been validated, using constraint evaluator parameters
+/// built in advance for the shape of the execution trace described by the proof.
+pub(super) fn verify_with_params(
+    program_hash : &[u8; 32],
+    hash_elements: &[u128],
+    inputs       : &[u128],
+    outputs      : &[u128],
+    proof        : &StarkProof,
+    params       : &EvaluatorParams) -> Result<bool, VerificationError>
 {
     let options = proof.options();
     let hash_fn = options.hash_fn();
+
+    // replay the transcript to derive all randomness the prover has used; the public statement
+    // is absorbed first, followed by commitments in the same order as they were made
+    let mut coin = utils::PublicCoin::new(program_hash, inputs, outputs, proof.trace_info(), hash_fn);
+    coin.absorb(proof.trace_root());
+    let constraint_seed = coin.seed();
+    coin.absorb(proof.constraint_root());
+    let composition_seed = coin.seed();
+
     // 1 ----- Verify proof of work and determine query positions ---------------------------------
     let degree_proof = proof.degree_proof();
     let mut fri_roots: Vec<u8> = Vec::new();
@@ -23,45 +65,44 @@ pub fn verify(program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], proof:
     }
     degree_proof.rem_root.iter().for_each(|&v| fri_roots.push(v));
 
-    let mut seed = [0u8; 32];
-    hash_fn(&fri_roots, &mut seed);
-    let seed = match utils::verify_pow_nonce(seed, proof.pow_nonce(), &options) {
-        Ok(seed) => seed,
-        Err(msg) => return Err(msg)
-    };
+    coin.absorb(&fri_roots);
+    let seed = utils::verify_pow_nonce(coin.seed(), proof.pow_nonce(), &options)?;
 
     let t_positions = utils::compute_query_positions(&seed, proof.domain_size(), options);
     let c_positions = utils::map_trace_to_constraint_positions(&t_positions);
 
     // 2 ----- Verify number of operations in the program -----------------------------------------
     if proof.op_count() < MIN_TRACE_LENGTH as u128 {
-        return Err(String::from("Verification of minimum operation count failed"));
+        return Err(VerificationError::OpCountTooSmall {
+            op_count    : proof.op_count(),
+            min_op_count: MIN_TRACE_LENGTH,
+        });
     }
 
     // 3 ----- Verify trace and constraint Merkle proofs ------------------------------------------
     if !MerkleTree::verify_batch(proof.trace_root(), &t_positions, &proof.trace_proof(), hash_fn) {
-        return Err(String::from("verification of trace Merkle proof failed"));
+        return Err(VerificationError::TraceMerkleMismatch);
     }
 
     if !MerkleTree::verify_batch(proof.constraint_root(), &c_positions, &proof.constraint_proof(), hash_fn) {
-        return Err(String::from("verification of constraint Merkle proof failed"));
+        return Err(VerificationError::ConstraintMerkleMismatch);
     }
 
     // 4 ----- Compute constraint evaluations at DEEP point z -------------------------------------
-    // derive DEEP point z from the root of the constraint tree
-    let z = field::prng(*proof.constraint_root());
+    // derive DEEP point z from the transcript after the constraint root was absorbed
+    let z = field::prng(composition_seed);
 
     // evaluate constraints at z
     let constraint_evaluation_at_z = evaluate_constraints(
-        ConstraintEvaluator::from_proof(proof, program_hash, inputs, outputs),
+        ConstraintEvaluator::from_params(params, proof, &constraint_seed, hash_elements, inputs, outputs),
         proof.get_state_at_z1(),
         proof.get_state_at_z2(),
         z
     );
 
     // 5 ----- Compute composition polynomial evaluations -----------------------------------------
-    // derive coefficient for linear combination from the root of constraint tree
-    let coefficients = CompositionCoefficients::new(*proof.constraint_root());
+    // derive coefficient for linear combination from the same seed as the DEEP point
+    let coefficients = CompositionCoefficients::new(composition_seed);
 
     // compute composition values separately for trace and constraints, and then add them together
     let t_composition = compose_registers(&proof, &t_positions, z, &coefficients);
@@ -70,10 +111,7 @@ pub fn verify(program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], proof:
     
     // 6 ----- Verify low-degree proof -------------------------------------------------------------
     let max_degree = utils::get_composition_degree(proof.trace_length());
-    return match fri::verify(&degree_proof, &evaluations, &t_positions, max_degree, options) {
-        Ok(result) => Ok(result),
-        Err(msg) => Err(format!("verification of low-degree proof failed: {}", msg))
-    }
+    return fri::verify(&degree_proof, &evaluations, &t_positions, max_degree, options);
 }
 
 // HELPER FUNCTIONS
@@ -0,0 +1,77 @@
+use crate::{ ProofOptions, StarkProof, BatchVerifier, BatchItem, assembly, crypto::hash, stark::MAX_CACHED_SHAPES };
+use super::super::{ execute, verify, ProgramInputs };
+
+#[test]
+fn batch_verify() {
+    let program = assembly::compile("begin add push.3 mul end").unwrap();
+    let options = ProofOptions::default();
+
+    let mut statements: Vec<(Vec<u128>, Vec<u128>, StarkProof)> = Vec::new();
+    for i in 0..5u128 {
+        let inputs = [i, i + 1];
+        let (outputs, proof) = execute(&program, &ProgramInputs::from_public(&inputs), 1, &options).unwrap();
+        assert_eq!(outputs, [(2 * i + 1) * 3]);
+        statements.push((inputs.to_vec(), outputs, proof));
+    }
+
+    // tamper with outputs of one statement
+    statements[3].1 = vec![42];
+
+    let items = statements.iter()
+        .map(|(inputs, outputs, proof)| (&inputs[..], &outputs[..], proof))
+        .collect::<Vec<BatchItem>>();
+
+    // results must be the same as when verifying each proof individually
+    let expected = items.iter()
+        .map(|&(inputs, outputs, proof)| verify(program.hash(), inputs, outputs, proof))
+        .collect::<Vec<_>>();
+    assert!(expected[3].is_err());
+
+    for &num_threads in [1, 2, 8].iter() {
+        let mut verifier = BatchVerifier::new(program.hash(), num_threads);
+        assert_eq!(expected, verifier.verify(&items));
+
+        // parameters are re-used when the same verifier is used again
+        assert_eq!(expected, verifier.verify(&items));
+    }
+
+    // a proof for a different program does not verify
+    let mut program_hash = *program.hash();
+    program_hash[0] ^= 1;
+    let mut verifier = BatchVerifier::new(&program_hash, 2);
+    assert!(verifier.verify(&items).iter().all(|r| r.is_err()));
+}
+
+#[test]
+fn batch_verify_bounded_cache() {
+    let program = assembly::compile("begin push.1 while.true push.1 read.a end end").unwrap();
+    let options = ProofOptions::new(32, 16, 0, hash::blake3);
+
+    // every loop iteration leaves one more item on the stack, and so every number of iterations
+    // gives a different trace shape
+    let mut statements: Vec<(Vec<u128>, Vec<u128>, StarkProof)> = Vec::new();
+    for num_iterations in 0..(MAX_CACHED_SHAPES + 1) {
+        let mut tape_a = vec![1u128; num_iterations];
+        tape_a.push(0);
+        let inputs = ProgramInputs::new(&[0; 8], &tape_a, &[]);
+        let (outputs, proof) = execute(&program, &inputs, 1, &options).unwrap();
+        statements.push((inputs.get_public_inputs().to_vec(), outputs, proof));
+    }
+
+    let items = statements.iter()
+        .map(|(inputs, outputs, proof)| (&inputs[..], &outputs[..], proof))
+        .collect::<Vec<BatchItem>>();
+
+    // verifying proofs one at a time never caches more than the maximum number of shapes
+    let mut verifier = BatchVerifier::new(program.hash(), 1);
+    for item in items.iter() {
+        assert_eq!(vec![Ok(true)], verifier.verify(&[*item]));
+        assert!(verifier.num_cached_shapes() <= MAX_CACHED_SHAPES);
+    }
+
+    // a single batch can use more shapes than the cache keeps, but the cache is bounded after it
+    assert!(verifier.verify(&items).iter().all(|r| *r == Ok(true)));
+    assert_eq!(MAX_CACHED_SHAPES, verifier.num_cached_shapes());
+    assert_eq!(vec![Ok(true)], verifier.verify(&items[..1]));
+    assert_eq!(MAX_CACHED_SHAPES, verifier.num_cached_shapes());
+}
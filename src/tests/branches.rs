@@ -23,14 +23,14 @@ fn simple_branching() {
 
     // test true branch
     let inputs = ProgramInputs::new(&[], &[1], &[]);
-    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(outputs, [8]);
     let result = verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
     assert_eq!(Ok(true), result);
 
     // test false branch
     let inputs = ProgramInputs::new(&[], &[0], &[]);
-    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(outputs, [15]);
     let result = verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
     assert_eq!(Ok(true), result);
@@ -1,9 +1,20 @@
-use crate::{ ProofOptions, math::field };
+use sp_std::vec::Vec;
+use crate::{ ProofOptions, ExecutionErrorKind, math::field, processor, programs::assembly };
 use super::{
-    build_program, OpCode,
+    build_program, execute_last_state, OpCode,
     super::{ execute, verify, ProgramInputs }
 };
 
+// CONSTANTS
+// ================================================================================================
+
+/// Values at the edges of 8-bit and 128-bit ranges; comparison instructions are tested on all
+/// pairs of values from the same list.
+const BOUNDARY_VALUES_8: [u128; 6] = [0, 1, 127, 128, 254, 255];
+const BOUNDARY_VALUES_128: [u128; 6] = [
+    0, 1, (1 << 127) - 1, 1 << 127, field::MODULUS - 2, field::MODULUS - 1
+];
+
 #[test]
 fn eq_operations() {
     let program = build_program(vec![
@@ -20,7 +31,7 @@ fn eq_operations() {
 
     let expected_result = vec![1, 0, 3];
 
-    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(expected_result, outputs);
 
     let result = verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
@@ -58,7 +69,7 @@ fn cmp_operation() {
     let expected_result = vec![gt, lt, b, a];
 
     // execute the program and make sure results are correct
-    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(expected_result, outputs);
 
     // verify execution proof
@@ -96,7 +107,7 @@ fn binacc_operation() {
     let expected_result = vec![a, a];
 
     // execute the program and make sure results are correct
-    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(expected_result, outputs);
 
     // verify execution proof
@@ -104,6 +115,107 @@ fn binacc_operation() {
     assert_eq!(Ok(true), result);
 }
 
+#[test]
+fn lte_gte_instructions() {
+    for (n, values) in [(8, BOUNDARY_VALUES_8), (128, BOUNDARY_VALUES_128)].iter() {
+        let lte = assembly::compile(&format!("begin lte.{} end", n)).unwrap();
+        let gte = assembly::compile(&format!("begin gte.{} end", n)).unwrap();
+        for &a in values.iter() {
+            for &b in values.iter() {
+                let inputs = ProgramInputs::from_public(&[a, b, 7]);
+                let expected = vec![(a <= b) as u128, 7];
+                assert_eq!(expected, &execute_last_state(&lte, &inputs).user_stack()[..2], "lte.{} failed for {} and {}", n, a, b);
+
+                let expected = vec![(a >= b) as u128, 7];
+                assert_eq!(expected, &execute_last_state(&gte, &inputs).user_stack()[..2], "gte.{} failed for {} and {}", n, a, b);
+            }
+        }
+    }
+}
+
+#[test]
+fn min_max_instructions() {
+    for (n, values) in [(8, BOUNDARY_VALUES_8), (128, BOUNDARY_VALUES_128)].iter() {
+        let min = assembly::compile(&format!("begin min.{} end", n)).unwrap();
+        let max = assembly::compile(&format!("begin max.{} end", n)).unwrap();
+        for &a in values.iter() {
+            for &b in values.iter() {
+                let inputs = ProgramInputs::from_public(&[a, b, 7]);
+                let expected = vec![u128::min(a, b), 7];
+                assert_eq!(expected, &execute_last_state(&min, &inputs).user_stack()[..2], "min.{} failed for {} and {}", n, a, b);
+
+                let expected = vec![u128::max(a, b), 7];
+                assert_eq!(expected, &execute_last_state(&max, &inputs).user_stack()[..2], "max.{} failed for {} and {}", n, a, b);
+            }
+        }
+    }
+}
+
+#[test]
+fn assert_range_instruction() {
+    // the value is removed from the stack when it is within the range
+    let program = assembly::compile("begin assert.range.8.3.10 end").unwrap();
+    for x in 3..11 {
+        let inputs = ProgramInputs::from_public(&[x, 7]);
+        assert_eq!(vec![7, 0], &execute_last_state(&program, &inputs).user_stack()[..2], "assert.range failed for {}", x);
+    }
+
+    // bounds can be at the edges of the value range
+    let program = assembly::compile("begin assert.range.8.0.255 end").unwrap();
+    for &x in BOUNDARY_VALUES_8.iter() {
+        let inputs = ProgramInputs::from_public(&[x, 7]);
+        assert_eq!(vec![7, 0], &execute_last_state(&program, &inputs).user_stack()[..2], "assert.range failed for {}", x);
+    }
+
+    let upper_bound = field::MODULUS - 1;
+    let program = assembly::compile(&format!("begin assert.range.128.0.{} end", upper_bound)).unwrap();
+    for &x in BOUNDARY_VALUES_128.iter() {
+        let inputs = ProgramInputs::from_public(&[x, 7]);
+        assert_eq!(vec![7, 0], &execute_last_state(&program, &inputs).user_stack()[..2], "assert.range failed for {}", x);
+    }
+
+    // a range can consist of a single value
+    let program = assembly::compile("begin assert.range.8.5.5 end").unwrap();
+    assert_eq!(vec![7, 0], &execute_last_state(&program, &ProgramInputs::from_public(&[5, 7])).user_stack()[..2]);
+}
+
+#[test]
+fn assert_range_failures() {
+    let program = assembly::compile("begin assert.range.8.3.10 end").unwrap();
+
+    // values outside of the range fail the assertion
+    let err = processor::execute(&program, &ProgramInputs::from_public(&[2, 7])).err().unwrap();
+    assert_eq!(ExecutionErrorKind::AssertFailed, err.kind());
+
+    let err = processor::execute(&program, &ProgramInputs::from_public(&[11, 7])).err().unwrap();
+    assert_eq!(ExecutionErrorKind::AssertFailed, err.kind());
+
+    // values which cannot be represented with 8 bits fail binary decomposition checks
+    let err = processor::execute(&program, &ProgramInputs::from_public(&[256, 7])).err().unwrap();
+    assert_eq!(ExecutionErrorKind::AssertEqFailed, err.kind());
+}
+
+#[test]
+fn comparison_instructions_execute_verify() {
+    let program = assembly::compile("
+    begin
+        dup.2 lte.8 assert
+        dup.2 gte.8 not assert
+        dup.2 max.8 assert.range.8.9.9
+        min.8 dup assert.range.8.5.5 push.3
+    end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[5, 9]);
+    let num_outputs = 2;
+
+    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options).unwrap();
+    assert_eq!(vec![3, 5], outputs);
+
+    let result = verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 fn build_inputs_for_cmp(a: u128, b: u128, size: usize) -> (Vec<u128>, Vec<u128>) {
@@ -118,4 +230,4 @@ fn build_inputs_for_cmp(a: u128, b: u128, size: usize) -> (Vec<u128>, Vec<u128>)
     inputs_b.reverse();
 
     return (inputs_a, inputs_b);
-}
\ No newline at end of file
+}
@@ -0,0 +1,254 @@
+use codec::{ Encode, Decode };
+use crate::{ ProofOptions, StarkProof, ProofValidationError, VerificationError, Program, ProgramDecodingError, assembly, MIN_STACK_DEPTH };
+use crate::crypto::{ hash, HashFunction, get_hash_fn_id };
+use super::super::{ execute, verify, ProgramInputs };
+
+#[test]
+fn proof_options_scale_round_trip() {
+    let options = ProofOptions::default();
+    let encoded = options.encode();
+    assert_eq!(4, encoded.len());
+
+    let decoded = ProofOptions::decode(&mut &encoded[..]).unwrap();
+    assert_eq!(options.extension_factor(), decoded.extension_factor());
+    assert_eq!(options.num_queries(), decoded.num_queries());
+    assert_eq!(options.grinding_factor(), decoded.grinding_factor());
+    assert_eq!(encoded, decoded.encode());
+
+    // unknown hash function identifier
+    let mut encoded = encoded;
+    encoded[3] = 255;
+    assert!(ProofOptions::decode(&mut &encoded[..]).is_err());
+}
+
+#[test]
+#[should_panic(expected = "unsupported hash function")]
+fn proof_options_unsupported_hash_fn() {
+    fn custom_hash(values: &[u8], result: &mut [u8]) {
+        hash::blake3(values, result);
+    }
+    ProofOptions::new(32, 16, 4, custom_hash);
+}
+
+#[test]
+fn stark_proof_scale_round_trip() {
+    let (program, outputs, proof) = build_proof();
+    assert_eq!(vec![8], outputs);
+
+    // encode and decode the proof
+    let encoded = proof.encode();
+    let decoded = StarkProof::decode(&mut &encoded[..]).unwrap();
+    assert_eq!(encoded, decoded.encode());
+
+    // SCALE encoding should carry the same data as bincode serialization
+    assert_eq!(bincode::serialize(&proof).unwrap(), bincode::serialize(&decoded).unwrap());
+
+    // decoded proof should still verify
+    let result = verify(program.hash(), &[], &outputs, &decoded);
+    assert_eq!(Ok(true), result);
+
+    // truncated proof should fail to decode
+    assert!(StarkProof::decode(&mut &encoded[..(encoded.len() - 1)]).is_err());
+}
+
+#[test]
+fn all_hash_functions_round_trip() {
+    let hash_fns: [(u8, HashFunction); 5] = [
+        (0, hash::blake3), (1, hash::sha3), (2, hash::rescue), (3, hash::poseidon), (4, hash::gmimc),
+    ];
+
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let inputs = ProgramInputs::none();
+
+    for &(id, hash_fn) in hash_fns.iter() {
+        assert_eq!(Some(id), get_hash_fn_id(hash_fn));
+
+        // keep grinding factor and number of queries low since algebraic hashes are slow
+        let options = ProofOptions::new(32, 16, 4, hash_fn);
+        let (outputs, proof) = execute(&program, &inputs, 1, &options).unwrap();
+
+        // serde round trip
+        let proof_bytes = bincode::serialize(&proof).unwrap();
+        let decoded: StarkProof = bincode::deserialize(&proof_bytes).unwrap();
+        assert_eq!(Some(id), get_hash_fn_id(decoded.options().hash_fn()));
+        let result = verify(program.hash(), &[], &outputs, &decoded);
+        assert_eq!(Ok(true), result);
+
+        // SCALE round trip
+        let decoded = StarkProof::from_bytes(&proof.encode()).unwrap();
+        assert_eq!(Some(id), get_hash_fn_id(decoded.options().hash_fn()));
+        let result = verify(program.hash(), &[], &outputs, &decoded);
+        assert_eq!(Ok(true), result);
+    }
+}
+
+#[test]
+fn shallow_stack_proof_round_trip() {
+    // the stack never holds more than 2 items, so the proof has fewer stack registers than
+    // the minimum stack depth
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let (outputs, proof) = execute(&program, &ProgramInputs::none(), 1, &ProofOptions::default()).unwrap();
+    assert!(proof.stack_depth() < MIN_STACK_DEPTH);
+
+    let decoded = StarkProof::from_bytes(&proof.encode()).unwrap();
+    assert_eq!(proof.stack_depth(), decoded.stack_depth());
+    assert_eq!(Ok(true), verify(program.hash(), &[], &outputs, &decoded));
+}
+
+#[test]
+fn stark_proof_validation() {
+    let (program, outputs, proof) = build_proof();
+    let encoded = proof.encode();
+
+    // well-formed proof should pass validation
+    let decoded = StarkProof::from_bytes(&encoded).unwrap();
+    assert_eq!(Ok(()), decoded.validate());
+
+    // trailing bytes and garbage should be rejected
+    let mut extended = encoded.clone();
+    extended.push(0);
+    assert_eq!(Some(ProofValidationError::DecodingFailed), StarkProof::from_bytes(&extended).err());
+    assert!(StarkProof::from_bytes(&[255u8; 64]).is_err());
+
+    // options are encoded in the last 4 bytes; extension factor of 4 is not allowed
+    let mut invalid = encoded.clone();
+    let options_start = invalid.len() - 4;
+    invalid[options_start] = 2;
+    assert!(StarkProof::from_bytes(&invalid).is_err());
+
+    // the same proof de-serialized via serde should be rejected by the verifier
+    let mut invalid = bincode::serialize(&proof).unwrap();
+    let options_start = invalid.len() - 4;
+    invalid[options_start] = 2;
+    let invalid: StarkProof = bincode::deserialize(&invalid).unwrap();
+    let err = ProofValidationError::InvalidExtensionFactor(2);
+    assert_eq!(Err(err.clone()), invalid.validate());
+    let result = verify(program.hash(), &[], &outputs, &invalid);
+    assert_eq!(Err(VerificationError::MalformedProof(err)), result);
+
+    // domain depth (the first byte after trace root) beyond what query sampling supports
+    let mut invalid = encoded.clone();
+    invalid[32] = 31;
+    let err = ProofValidationError::InvalidDomainDepth(31);
+    assert_eq!(Some(err.clone()), StarkProof::from_bytes(&invalid).err());
+    let mut invalid = bincode::serialize(&proof).unwrap();
+    invalid[32] = 31;
+    let invalid: StarkProof = bincode::deserialize(&invalid).unwrap();
+    assert_eq!(Err(VerificationError::MalformedProof(err)), verify(program.hash(), &[], &outputs, &invalid));
+
+    // the last remainder value is followed by a nonce (8 bytes) and options (4 bytes)
+    let mut invalid = encoded.clone();
+    let value_end = invalid.len() - 12;
+    for byte in invalid[(value_end - 16)..value_end].iter_mut() {
+        *byte = 255;
+    }
+    let err = ProofValidationError::NonCanonicalFieldElement { name: "FRI remainder" };
+    assert_eq!(Some(err), StarkProof::from_bytes(&invalid).err());
+}
+
+#[test]
+fn program_bytes_round_trip() {
+    let source = "
+    begin
+        push.3 push.5 add read eq hash.2 gt.32 rc.16
+        if.true smpath.3 else pmpath.2 end
+        while.true dup mul push.0 end
+        repeat.2 block pad.2 drop.2 end end
+    end";
+    let program = assembly::compile(source).unwrap();
+    let bytes = program.to_bytes();
+    assert_eq!(crate::programs::PROGRAM_MAGIC, bytes[..4]);
+    assert_eq!(crate::programs::PROGRAM_FORMAT_VERSION, bytes[4]);
+    assert_eq!(&program.hash()[..], &bytes[5..37]);
+
+    // decoded program has the same structure and hash
+    let decoded = Program::from_bytes(&bytes).unwrap();
+    assert_eq!(program.hash(), decoded.hash());
+    assert_eq!(format!("{:?}", program), format!("{:?}", decoded));
+
+    // the format is also the SCALE encoding of the program
+    assert_eq!(bytes, program.encode());
+    let decoded = Program::decode(&mut &bytes[..]).unwrap();
+    assert_eq!(program.hash(), decoded.hash());
+}
+
+#[test]
+fn program_decoding_errors() {
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let bytes = program.to_bytes();
+
+    let mut invalid = bytes.clone();
+    invalid[0] = b'X';
+    assert_eq!(Some(ProgramDecodingError::InvalidMagic), Program::from_bytes(&invalid).err());
+
+    let mut invalid = bytes.clone();
+    invalid[4] = 2;
+    assert_eq!(Some(ProgramDecodingError::UnsupportedVersion(2)), Program::from_bytes(&invalid).err());
+
+    // truncated and extended programs are rejected
+    assert_eq!(Some(ProgramDecodingError::DecodingFailed), Program::from_bytes(&bytes[..(bytes.len() - 1)]).err());
+    let mut extended = bytes.clone();
+    extended.push(0);
+    assert_eq!(Some(ProgramDecodingError::DecodingFailed), Program::from_bytes(&extended).err());
+
+    // changing the value of the last PUSH (encoded as the last 16 bytes) changes program hash
+    let mut tampered = bytes.clone();
+    let value_start = tampered.len() - 16;
+    tampered[value_start] = 7;
+    assert_eq!(Some(ProgramDecodingError::HashMismatch), Program::from_bytes(&tampered).err());
+    assert!(Program::decode(&mut &tampered[..]).is_err());
+
+    // replacing BEGIN (the first operation after header, block count, tag, and length) is rejected
+    let mut invalid = bytes.clone();
+    invalid[40] = crate::OpCode::Noop as u8;
+    assert!(match Program::from_bytes(&invalid) {
+        Err(ProgramDecodingError::MalformedProgram(_)) => true,
+        _ => false,
+    });
+
+    // hints are not covered by the hash, so out-of-range hint values are rejected when decoding
+    let bytes = assembly::compile("begin rc.16 end").unwrap().to_bytes();
+    let hint = [1, 16, 0, 0, 0];
+    let hint_start = bytes.windows(hint.len()).rposition(|w| w == hint).unwrap();
+    let mut tampered = bytes.clone();
+    tampered[hint_start + 1] = 200;
+    assert_eq!(Some(ProgramDecodingError::MalformedProgram("hint value is out of bounds")),
+        Program::from_bytes(&tampered).err());
+}
+
+#[test]
+fn stark_proof_stats() {
+    let (_, _, proof) = build_proof();
+    let stats = proof.stats().unwrap();
+
+    // component sizes add up to the size of the encoded proof
+    assert_eq!(proof.encode().len(), stats.total_size);
+    let fri_size = stats.fri_layers.iter().map(|l| l.values_size + l.nodes_size).sum::<usize>();
+    assert_eq!(stats.total_size, stats.header_size + stats.trace_nodes_size + stats.trace_evaluations_size
+        + stats.constraint_proof_size + stats.deep_values_size + fri_size + stats.fri_remainder_size);
+
+    assert_eq!(proof.degree_proof().layers.len(), stats.fri_layers.len());
+    assert_eq!(proof.options().num_queries(), stats.num_queries);
+    assert_eq!(proof.trace_evaluations().len(), stats.num_queries);
+    assert!(stats.num_constraint_queries <= stats.num_queries);
+    for layer in stats.fri_layers.iter() {
+        assert!(layer.num_queries <= stats.num_queries);
+    }
+    assert!(stats.security.provable <= stats.security.conjectured);
+
+    // stats are not computed for malformed proofs
+    let mut invalid = bincode::serialize(&proof).unwrap();
+    invalid[32] = 0;
+    let invalid: StarkProof = bincode::deserialize(&invalid).unwrap();
+    assert_eq!(Some(ProofValidationError::InvalidDomainDepth(0)), invalid.stats().err());
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn build_proof() -> (Program, Vec<u128>, StarkProof) {
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::none();
+    let (outputs, proof) = execute(&program, &inputs, 1, &options).unwrap();
+    return (program, outputs, proof);
+}
@@ -0,0 +1,184 @@
+use crate::{
+    Debugger, ProgramInputs, OpCode, ExecutionError, ExecutionErrorKind, ExecutionOptions,
+    ProofOptions, CostEstimate, processor, programs::assembly, stark::TraceState, MAX_OUTPUTS };
+use super::super::{ execute, run, run_with_limits };
+
+// DIFFERENTIAL TESTS
+// ================================================================================================
+
+#[test]
+fn run_matches_execute() {
+    assert_same_result("begin push.3 push.5 add end", &ProgramInputs::none());
+    assert_same_result("begin swap.1 dup.2 drop.1 add pad.2 roll.4 end", &ProgramInputs::from_public(&[1, 2, 3]));
+
+    // conditional blocks
+    let source = "begin read.a if.true push.2 else push.3 end add end";
+    assert_same_result(source, &ProgramInputs::new(&[7], &[1], &[]));
+    assert_same_result(source, &ProgramInputs::new(&[7], &[0], &[]));
+
+    // loops
+    let source = "begin mul read.a while.true dup mul read.a end end";
+    assert_same_result(source, &ProgramInputs::new(&[5, 3], &[0], &[]));
+    assert_same_result(source, &ProgramInputs::new(&[5, 3], &[1, 1, 0], &[]));
+
+    // nested blocks
+    let source = "begin push.1 if.true push.1 while.true block push.0 end end else noop end end";
+    assert_same_result(source, &ProgramInputs::none());
+
+    // operations driven by execution hints
+    assert_same_result("begin gt.8 end", &ProgramInputs::from_public(&[17, 200]));
+    assert_same_result("begin max.128 end", &ProgramInputs::from_public(&[17, 200]));
+    assert_same_result("begin eq end", &ProgramInputs::from_public(&[17, 17]));
+    assert_same_result("begin rc.16 end", &ProgramInputs::from_public(&[300]));
+    assert_same_result("begin hash.2 end", &ProgramInputs::from_public(&[1, 2]));
+}
+
+#[test]
+fn run_fails_like_execute() {
+    assert_same_error("begin push.2 assert end", &ProgramInputs::none());
+    assert_same_error("begin read.a end", &ProgramInputs::none());
+    assert_same_error("begin push.2 if.true noop else noop end end", &ProgramInputs::none());
+    assert_same_error("begin push.1 while.true push.2 end end", &ProgramInputs::none());
+
+    // blocks nested deeper than the maximum context depth
+    let source = format!("begin {} noop {} end", "block ".repeat(16), "end ".repeat(16));
+    assert_same_error(&source, &ProgramInputs::none());
+}
+
+#[test]
+fn execution_report() {
+    let program = assembly::compile("begin push.5 push.1 if.true push.2 else push.3 end add end").unwrap();
+    let result = run(&program, &ProgramInputs::none(), 1).unwrap();
+    let report = result.report();
+
+    assert_eq!(result.cycles(), report.step_count());
+    assert_eq!(1, report.op_count(OpCode::Add));
+    assert_eq!(1, report.op_count(OpCode::Assert));
+    assert_eq!(0, report.op_count(OpCode::Not));
+    assert!(report.op_counts().iter().all(|&(_, count)| count > 0));
+
+    // the conditional block is closed, the span after it is pre-padded, and the root block is closed
+    assert_eq!(3, report.alignment_noops());
+    assert_eq!(1, report.max_ctx_depth());
+    assert_eq!(0, report.max_loop_depth());
+    assert_eq!(0, report.rescr_rounds());
+
+    let program = assembly::compile("begin hash.2 end").unwrap();
+    let result = run(&program, &ProgramInputs::from_public(&[1, 2]), 1).unwrap();
+    assert_eq!(result.report().op_count(OpCode::RescR), result.report().rescr_rounds());
+    assert!(result.report().rescr_rounds() > 0);
+}
+
+#[test]
+fn cost_estimate() {
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let (_, proof) = execute(&program, &ProgramInputs::none(), 1, &ProofOptions::default()).unwrap();
+    let report = run(&program, &ProgramInputs::none(), 1).unwrap().report().clone();
+    let estimate = CostEstimate::new(&report, proof.options());
+
+    // the shape of the trace is predicted exactly
+    assert_eq!(proof.trace_length(), estimate.trace_length);
+    assert_eq!(proof.domain_size(), estimate.domain_size);
+    assert_eq!(proof.stats().unwrap().security, estimate.security);
+
+    // proof size is an upper bound
+    assert!(estimate.proof_size >= proof.stats().unwrap().total_size);
+
+    // more queries make proofs larger, and a larger extension factor makes proving slower
+    let options = ProofOptions::new(proof.options().extension_factor() * 2, proof.options().num_queries() * 2,
+        proof.options().grinding_factor(), proof.options().hash_fn());
+    let larger = CostEstimate::new(&report, &options);
+    assert!(larger.proof_size > estimate.proof_size);
+    assert!(larger.field_mults > estimate.field_mults);
+    assert!(larger.memory_size > estimate.memory_size);
+}
+
+#[test]
+fn execution_limits() {
+    // an infinite loop is stopped by the cycle limit, or by the trace length limit
+    let source = "begin push.1 while.true push.1 end end";
+    let err = assert_same_limit_error(source, &ProgramInputs::none(), &ExecutionOptions::new(100, 1024, 0));
+    assert_eq!(ExecutionErrorKind::CycleLimitExceeded, err.kind());
+    assert_eq!(101, err.step());
+
+    let err = assert_same_limit_error(source, &ProgramInputs::none(), &ExecutionOptions::new(1000, 64, 0));
+    assert_eq!(ExecutionErrorKind::TraceLengthExceeded, err.kind());
+    assert_eq!(64, err.step());
+
+    // secret inputs are checked before the first step
+    let inputs = ProgramInputs::new(&[7], &[1, 2], &[3]);
+    let err = assert_same_limit_error("begin read.a end", &inputs, &ExecutionOptions::new(1000, 64, 2));
+    assert_eq!(ExecutionErrorKind::SecretInputLimitExceeded, err.kind());
+    assert_eq!(1, err.step());
+    assert_eq!(&[7, 0, 0, 0, 0, 0, 0, 0], err.stack_top());
+
+    // values pushed onto the tapes by operation hints are counted against the same limit
+    let inputs = ProgramInputs::from_public(&[5]);
+    let err = assert_same_limit_error("begin rc.16 end", &inputs, &ExecutionOptions::new(1000, 64, 15));
+    assert_eq!(ExecutionErrorKind::SecretInputLimitExceeded, err.kind());
+    assert_eq!(OpCode::BinAcc, err.op_code());
+
+    let program = assembly::compile("begin rc.16 end").unwrap();
+    assert!(run_with_limits(&program, &inputs, 1, &ExecutionOptions::new(1000, 64, 16)).is_ok());
+
+    // a program which stays within the limits is not affected by them
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let inputs = ProgramInputs::new(&[], &[1, 2], &[3]);
+    let result = run(&program, &inputs, 1).unwrap();
+    let cycles = result.cycles();
+    let trace_length = result.report().trace_length();
+
+    let limits = ExecutionOptions::new(cycles, trace_length, 3);
+    assert_eq!(result, run_with_limits(&program, &inputs, 1, &limits).unwrap());
+    assert!(processor::execute_with_limits(&program, &inputs, &limits).is_ok());
+
+    let limits = ExecutionOptions::new(cycles - 1, trace_length, 3);
+    let err = run_with_limits(&program, &inputs, 1, &limits).err().unwrap();
+    assert_eq!(ExecutionErrorKind::CycleLimitExceeded, err.kind());
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Checks that running the program produces the final state of the execution trace.
+fn assert_same_result(source: &str, inputs: &ProgramInputs) {
+    let program = assembly::compile(source).unwrap();
+    let result = run(&program, inputs, MAX_OUTPUTS).unwrap();
+
+    let mut debugger = Debugger::new(&program, inputs, &ExecutionOptions::default());
+    assert!(debugger.error().is_none());
+    let last_step = debugger.last_step();
+    let last_state = debugger.seek(last_step);
+    assert_eq!(last_step, result.cycles());
+
+    // items below the stack depth are 0 in the trace
+    let stack = last_state.user_stack();
+    let depth = result.stack().len();
+    assert_eq!(result.stack(), &stack[..depth]);
+    assert!(stack[depth..].iter().all(|&value| value == 0));
+    assert_eq!(result.outputs(), &stack[..MAX_OUTPUTS]);
+
+    // execution report describes the shape of the execution trace
+    let (trace, ctx_depth, loop_depth) = processor::execute(&program, inputs).unwrap();
+    let report = result.report();
+    assert_eq!(trace[0].len(), report.trace_length());
+    assert_eq!(ctx_depth, report.max_ctx_depth());
+    assert_eq!(loop_depth, report.max_loop_depth());
+    assert_eq!(trace.len(), TraceState::compute_decoder_width(ctx_depth, loop_depth) + report.max_stack_depth());
+}
+
+/// Checks that running the program fails with the same error as executing it.
+fn assert_same_error(source: &str, inputs: &ProgramInputs) {
+    let program = assembly::compile(source).unwrap();
+    let expected = processor::execute(&program, inputs).err().unwrap();
+    assert_eq!(expected, run(&program, inputs, MAX_OUTPUTS).err().unwrap());
+}
+
+/// Checks that running the program with the specified limits fails with the same error as
+/// executing it with these limits, and returns the error.
+fn assert_same_limit_error(source: &str, inputs: &ProgramInputs, limits: &ExecutionOptions) -> ExecutionError {
+    let program = assembly::compile(source).unwrap();
+    let expected = processor::execute_with_limits(&program, inputs, limits).err().unwrap();
+    assert_eq!(expected, run_with_limits(&program, inputs, MAX_OUTPUTS, limits).err().unwrap());
+    return expected;
+}
@@ -1,12 +1,17 @@
-use sp_std::collections::HashMap;
+use hashbrown::HashMap;
 use crate::{
-    ProofOptions, Program, ProgramInputs, OpCode, OpHint,
+    ProofOptions, Program, ProgramInputs, OpCode, OpHint, VerificationError, ExecutionErrorKind, MAX_OUTPUTS,
     blocks::{ ProgramBlock, Span, Group },
-    math::field, utils::hasher
+    math::field, utils::hasher, processor, stark::{ TraceTable, TraceState }
 };
 
+mod batch;
 mod branches;
 mod comparisons;
+mod encoding;
+mod interpreter;
+mod optimizer;
+mod policy;
 
 #[test]
 fn execute_verify() {
@@ -21,7 +26,7 @@ fn execute_verify() {
     let inputs = ProgramInputs::from_public(&[1, 0]);
     let num_outputs = 1;
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(outputs, [3]);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
@@ -41,25 +46,46 @@ fn execute_verify_fail() {
     let inputs = ProgramInputs::from_public(&[1, 0]);
     let num_outputs = 1;
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(outputs, [3]);
 
+    // public statement is bound into the transcript; so, any change to it alters the seed
+    // used for the proof-of-work check, and verification fails early
+    let pow_err = VerificationError::InsufficientProofOfWork { grinding_factor: options.grinding_factor() };
+
     // wrong inputs
     let result = super::verify(program.hash(), &[1, 1], &outputs, &proof);
-    let err_msg = format!("verification of low-degree proof failed: evaluations did not match column value at depth 0");
-    assert_eq!(Err(err_msg), result);
+    assert_eq!(Err(pow_err.clone()), result);
 
     // wrong outputs
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &[5], &proof);
-    let err_msg = format!("verification of low-degree proof failed: evaluations did not match column value at depth 0");
-    assert_eq!(Err(err_msg), result);
+    assert_eq!(Err(pow_err.clone()), result);
 
     // wrong program hash
     let mut program_hash2 = program.hash().clone();
     program_hash2[0] = 1;
     let result = super::verify(&program_hash2, inputs.get_public_inputs(), &outputs, &proof);
-    let err_msg = format!("verification of low-degree proof failed: evaluations did not match column value at depth 0");
-    assert_eq!(Err(err_msg), result);
+    assert_eq!(Err(pow_err.clone()), result);
+}
+
+#[test]
+fn execute_too_many_outputs() {
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Noop, OpCode::Noop,
+    ], &[]);
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[1, 0]);
+
+    let err = super::execute(&program, &inputs, MAX_OUTPUTS + 1, &options).err().unwrap();
+    assert_eq!(ExecutionErrorKind::TooManyOutputs, err.kind());
+    assert_eq!(0, err.step());
+
+    let err = super::run(&program, &inputs, MAX_OUTPUTS + 1).err().unwrap();
+    assert_eq!(ExecutionErrorKind::TooManyOutputs, err.kind());
 }
 
 #[test]
@@ -79,7 +105,7 @@ fn stack_manipulation() {
     let inputs = ProgramInputs::from_public(&[7, 6, 5, 4, 3, 2, 1, 0]);
     let num_outputs = 8;
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(outputs, [46, 19, 4, 11, 0, 11, 0, 6]);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
@@ -100,7 +126,7 @@ fn conditional_operations() {
     let inputs = ProgramInputs::from_public(&[3, 4, 1, 5, 0, 6, 7, 8]);
     let num_outputs = 8;
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(outputs, [5, 6, 7, 8, 0, 0, 0, 0]);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
@@ -122,7 +148,7 @@ fn conditional_operations() {
     let inputs = ProgramInputs::from_public(&[5, 6, 1, 0, 7, 8, 0, 0]);
     let num_outputs = 8;
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(outputs, [7, 8, 0, 0, 0, 0, 0, 0]);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
@@ -140,7 +166,7 @@ fn conditional_operations() {
     let inputs = ProgramInputs::from_public(&[3, 4, 1, 2, 1, 0, 5, 6]);
     let num_outputs = 8;
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(outputs, [3, 4, 5, 6, 1, 2, 0, 0]);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
@@ -148,8 +174,7 @@ fn conditional_operations() {
 }
 
 #[test]
-#[should_panic(expected = "CHOOSE on a non-binary condition at step 2")]
-fn selection_operations_panic() {
+fn selection_operations_error() {
     let program = build_program(vec![
         OpCode::Begin, OpCode::Choose, OpCode::Choose, OpCode::Noop,
         OpCode::Noop,  OpCode::Noop,   OpCode::Noop,   OpCode::Noop,
@@ -161,7 +186,11 @@ fn selection_operations_panic() {
     let inputs = ProgramInputs::from_public(&[3, 4, 2, 5, 0, 6, 7, 8]);
     let num_outputs = 8;
 
-    super::execute(&program, &inputs, num_outputs, &options);
+    let err = super::execute(&program, &inputs, num_outputs, &options).err().unwrap();
+    assert_eq!(ExecutionErrorKind::NonBinaryCondition, err.kind());
+    assert_eq!(2, err.step());
+    assert_eq!(OpCode::Choose, err.op_code());
+    assert_eq!([3, 4, 2, 5, 0, 6, 7, 8], err.stack_top());
 }
 
 #[test]
@@ -179,7 +208,7 @@ fn math_operations() {
 
     let expected_result = vec![field::ONE, field::neg(field::inv(65))];
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(expected_result, outputs);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
@@ -201,7 +230,7 @@ fn bool_operations() {
 
     let expected_result = vec![field::ONE];
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(expected_result, outputs);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
@@ -230,7 +259,7 @@ fn hash_operations() {
     let inputs = ProgramInputs::from_public(&[0, 0, 4, 3, 2, 1]);
     let num_outputs = 2;
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(expected_hash, outputs);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
@@ -261,7 +290,7 @@ fn hash_operations() {
     let inputs = ProgramInputs::from_public(&[0, 0, 4, 3, 2, 1]);
     let num_outputs = 2;
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(expected_hash, outputs);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
@@ -281,7 +310,7 @@ fn read_operations() {
     let inputs = ProgramInputs::new(&[1], &[2, 3], &[4]);
     let num_outputs = 5;
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(vec![5, 4, 3, 2, 1], outputs);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
@@ -303,7 +332,7 @@ fn assert_operations() {
 
     let expected_result = vec![0, 0];
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options).unwrap();
     assert_eq!(expected_result, outputs);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
@@ -333,4 +362,13 @@ fn build_program(instructions: Vec<OpCode>, push_values: &[u128]) -> Program {
 
     let root = vec![ProgramBlock::Span(Span::new(instructions, hints))];
     return Program::new(Group::new(root));
+}
+
+/// Executes the program without generating a proof and returns the state of the VM at the last
+/// step of the execution trace.
+fn execute_last_state(program: &Program, inputs: &ProgramInputs) -> TraceState {
+    let (registers, ctx_depth, loop_depth) = processor::execute(program, inputs).unwrap();
+    let extension_factor = ProofOptions::default().extension_factor();
+    let trace = TraceTable::new(registers, ctx_depth, loop_depth, extension_factor);
+    return trace.get_state(trace.unextended_length() - 1);
 }
\ No newline at end of file
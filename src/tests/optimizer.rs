@@ -0,0 +1,72 @@
+use alloc::string::String;
+use sp_std::vec::Vec;
+use rand::{ rngs::StdRng, Rng, SeedableRng };
+use crate::{ ProofOptions, math::field, programs::assembly };
+use super::{ execute_last_state, super::{ execute, verify, ProgramInputs } };
+
+/// Snippets which leave stack depth unchanged; many of them contain sequences which the
+/// optimizer is expected to remove.
+const SNIPPETS: [&str; 18] = [
+    "dup drop", "swap swap", "push.0 add", "push.1 mul", "push.7 drop", "neg neg",
+    "dup swap add push.9", "swap", "swap.2", "swap.4 swap.4", "roll.4", "roll.8 roll.8",
+    "dup.2 drop drop", "pad.2 drop drop", "add push.5", "dup add", "hash.2", "push.1 not not drop",
+];
+
+#[test]
+fn optimized_programs_produce_same_outputs() {
+    // programs and inputs are generated from a fixed seed so that failures are reproducible
+    let mut rng = StdRng::seed_from_u64(17);
+    for _ in 0..40 {
+        let source = format!("begin {} end", build_body(&mut rng, 2));
+        let program = assembly::compile(&source).unwrap();
+        let optimized = assembly::optimize(&program);
+
+        let inputs: Vec<u128> = (0..8).map(|_| rng.gen_range(0, field::MODULUS)).collect();
+        let inputs = ProgramInputs::from_public(&inputs);
+        let state = execute_last_state(&program, &inputs);
+        let optimized_state = execute_last_state(&optimized, &inputs);
+
+        assert_eq!(state.user_stack()[..8], optimized_state.user_stack()[..8], "outputs differ for program: {}", source);
+        assert!(optimized_state.op_counter() <= state.op_counter(), "more operations executed for program: {}", source);
+    }
+}
+
+#[test]
+fn optimized_program_execute_verify() {
+    let source = "
+    begin
+        push.3 dup drop swap swap push.0 add mul
+        push.1 if.true push.1 mul else neg neg end
+        repeat.4 push.2 mul push.0 add end
+    end";
+    let program = assembly::compile(source).unwrap();
+    let optimized = assembly::compile_optimized(source).unwrap();
+    let inputs = ProgramInputs::from_public(&[1, 2]);
+    assert!(execute_last_state(&optimized, &inputs).op_counter() < execute_last_state(&program, &inputs).op_counter());
+
+    let options = ProofOptions::default();
+    let (outputs, proof) = execute(&optimized, &inputs, 1, &options).unwrap();
+    assert_eq!(vec![48], outputs);
+
+    let result = verify(optimized.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Builds a random program body out of snippets which never reduce the stack below 8 items.
+fn build_body(rng: &mut StdRng, depth: usize) -> String {
+    let mut body = Vec::new();
+    for _ in 0..rng.gen_range(1, 6) {
+        let choice = if depth > 0 { rng.gen_range(0, 10) } else { 0 };
+        match choice {
+            7 => body.push(format!("push.{} if.true {} else {} end",
+                rng.gen_range(0, 2), build_body(rng, depth - 1), build_body(rng, depth - 1))),
+            8 => body.push(format!("repeat.{} {} end", rng.gen_range(2, 5), build_body(rng, depth - 1))),
+            9 => body.push(format!("push.1 while.true {} push.0 end", build_body(rng, depth - 1))),
+            _ => body.push(String::from(SNIPPETS[rng.gen_range(0, SNIPPETS.len())])),
+        }
+    }
+    return body.join(" ");
+}
@@ -0,0 +1,59 @@
+use crate::{ ProofOptions, StarkProof, VerifierPolicy, PolicyViolation, VerificationError, assembly };
+use crate::crypto::hash;
+use crate::stark::MAX_DOMAIN_DEPTH;
+use super::super::{ execute, verify_with_policy, ProgramInputs };
+
+#[test]
+fn verify_with_policy_limits() {
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let inputs = ProgramInputs::none();
+
+    // default options provide 120 bits of conjectured security
+    let options = ProofOptions::default();
+    let (outputs, proof) = execute(&program, &inputs, 1, &options).unwrap();
+    let policy = VerifierPolicy::new(100);
+    assert_eq!(Ok(true), verify_with_policy(program.hash(), &[], &outputs, &proof, &policy));
+
+    // a proof with very few queries is rejected
+    let weak_options = ProofOptions::new(16, 4, 0, hash::blake3);
+    let (outputs, weak_proof) = execute(&program, &inputs, 1, &weak_options).unwrap();
+    let err = PolicyViolation::SecurityLevelTooLow { level: 4, min_level: 100 };
+    assert_eq!(Err(VerificationError::PolicyViolation(err)),
+        verify_with_policy(program.hash(), &[], &outputs, &weak_proof, &policy));
+
+    // hash function must be in the allowed set
+    let policy = VerifierPolicy::new(100).with_hash_fns(&[hash::sha3]);
+    assert_eq!(Err(VerificationError::PolicyViolation(PolicyViolation::HashFunctionNotAllowed)),
+        verify_with_policy(program.hash(), &[], &outputs, &proof, &policy));
+
+    // trace bounds
+    let depth = proof.trace_info().domain_depth;
+    let policy = VerifierPolicy::new(100).with_max_domain_depth(depth - 1);
+    let err = PolicyViolation::DomainDepthTooLarge { depth, max_depth: depth - 1 };
+    assert_eq!(Err(VerificationError::PolicyViolation(err)),
+        verify_with_policy(program.hash(), &[], &outputs, &proof, &policy));
+
+    let policy = VerifierPolicy::new(100).with_max_op_count(proof.op_count() - 1);
+    let err = PolicyViolation::OpCountTooLarge { op_count: proof.op_count(), max_op_count: proof.op_count() - 1 };
+    assert_eq!(Err(VerificationError::PolicyViolation(err)),
+        verify_with_policy(program.hash(), &[], &outputs, &proof, &policy));
+
+    let policy = VerifierPolicy::new(100).with_max_stack_depth(proof.stack_depth() - 1);
+    let err = PolicyViolation::StackDepthTooLarge { depth: proof.stack_depth(), max_depth: proof.stack_depth() - 1 };
+    assert_eq!(Err(VerificationError::PolicyViolation(err)),
+        verify_with_policy(program.hash(), &[], &outputs, &proof, &policy));
+
+    // by default, domains deeper than the verifier supports are rejected before verification
+    let max_depth = MAX_DOMAIN_DEPTH as u8;
+    let mut bytes = bincode::serialize(&proof).unwrap();
+    bytes[32] = max_depth + 1;
+    let deep_proof: StarkProof = bincode::deserialize(&bytes).unwrap();
+    let err = PolicyViolation::DomainDepthTooLarge { depth: max_depth + 1, max_depth };
+    assert_eq!(Err(VerificationError::PolicyViolation(err.clone())),
+        verify_with_policy(program.hash(), &[], &outputs, &deep_proof, &VerifierPolicy::new(100)));
+
+    // and the limit cannot be raised above what the verifier supports
+    let policy = VerifierPolicy::new(100).with_max_domain_depth(u8::MAX);
+    assert_eq!(Err(VerificationError::PolicyViolation(err)),
+        verify_with_policy(program.hash(), &[], &outputs, &deep_proof, &policy));
+}